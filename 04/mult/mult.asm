@@ -37,7 +37,7 @@ M = 0
     @value  // get the consistent VALUE
     D = M
     @R2         // access R2
-    M = M + D   // add VALUE to the number in R2
+    M = D + M   // add VALUE to the number in R2
 
     @count      // get COUNT
     M = M - 1   // decrease COUNT by 1
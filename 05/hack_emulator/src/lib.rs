@@ -0,0 +1,5 @@
+// Docs can be opened with "cargo doc --open"
+pub mod loader;
+pub mod machine;
+
+pub use machine::{ Machine, Stop };
@@ -0,0 +1,78 @@
+//! Reads the textual `.hack` format (as written by the `hack_assembler`)
+//! into 16bit words that can be loaded into the ROM of a `Machine`.
+
+use std::fmt;
+
+/// Error for a line of a `.hack` file which is not a 16 character
+/// long string of `0`s and `1`s.
+#[derive(Debug, PartialEq)]
+pub struct LoadError {
+    pub line: usize,
+    pub text: String,
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: '{}' is not a 16bit binary word", self.line, self.text)
+    }
+}
+impl std::error::Error for LoadError {}
+
+/// Parses every non-empty line of a `.hack` file into a `u16`.
+/// Line numbers in a `LoadError` start at 1.
+///
+/// # Examples
+/// ```
+/// use hack_emulator::loader::parse_hack;
+///
+/// assert_eq!(parse_hack("0000000000000010\n1110101010001000\n"), Ok(vec![2, 0xEA88]));
+/// assert!(parse_hack("0000000000000010\n1110102").is_err());
+/// ```
+pub fn parse_hack(hack_file: &str) -> Result<Vec<u16>, LoadError> {
+    hack_file
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line_nr, line)| parse_word(line).ok_or_else(|| LoadError { line: line_nr, text: line.to_string() }))
+        .collect()
+}
+
+// A word must have exactly 16 binary digits.
+fn parse_word(word: &str) -> Option<u16> {
+    if word.len() != 16 || !word.chars().all(|c| c == '0' || c == '1') {
+        return None;
+    }
+    u16::from_str_radix(word, 2).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_instruction() {
+        assert_eq!(parse_word("0000000000000111"), Some(7));
+    }
+    #[test]
+    fn parses_c_instruction() {
+        assert_eq!(parse_word("1111110000010000"), Some(0xFC10));
+    }
+    #[test]
+    fn rejects_short_word() {
+        assert_eq!(parse_word("101"), None);
+    }
+    #[test]
+    fn rejects_non_binary_word() {
+        assert_eq!(parse_word("000000000000002a"), None);
+    }
+    #[test]
+    fn skips_empty_lines() {
+        assert_eq!(parse_hack("\n0000000000000001\n\n"), Ok(vec![1]));
+    }
+    #[test]
+    fn reports_line_of_bad_word() {
+        assert_eq!(
+            parse_hack("0000000000000001\nfoo"),
+            Err(LoadError { line: 2, text: "foo".to_string() }));
+    }
+}
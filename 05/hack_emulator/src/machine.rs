@@ -0,0 +1,304 @@
+//! The headless HACK computer: 32K ROM, 32K RAM (with the memory-mapped
+//! SCREEN and KBD), the A- and D-register and the program counter.
+
+pub const ROM_SIZE: usize = 32768;
+pub const RAM_SIZE: usize = 32768;
+pub const SCREEN: usize = 16384;
+pub const SCREEN_SIZE: usize = 8192;
+pub const KBD: usize = 24576;
+
+/// Why `Machine::run` stopped executing.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stop {
+    /// The program reached an `(END) @END 0;JMP` loop, so nothing will change anymore.
+    TightLoop,
+    /// The given number of cycles has been executed.
+    CycleLimit,
+}
+
+/// A HACK computer, that executes one instruction per `step`.
+/// The registers are public so a test can set up (or inspect) a state directly,
+/// memory goes through `ram`/`set_ram` because the address space is memory-mapped.
+pub struct Machine {
+    rom: Vec<u16>,
+    ram: Vec<i16>,
+    pub a: i16,
+    pub d: i16,
+    pub pc: u16,
+    cycles: u64,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Machine {
+            rom: vec![0; ROM_SIZE],
+            ram: vec![0; RAM_SIZE],
+            a: 0,
+            d: 0,
+            pc: 0,
+            cycles: 0,
+        }
+    }
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        Machine::default()
+    }
+
+    /// Creates a machine with `program` loaded into ROM (starting at address 0).
+    ///
+    /// # Examples
+    /// ```
+    /// use hack_emulator::machine::Machine;
+    ///
+    /// // @7 D=A @0 M=D
+    /// let mut machine = Machine::with_program(&[7, 0xEC10, 0, 0xE308]);
+    /// machine.step();
+    /// machine.step();
+    /// machine.step();
+    /// machine.step();
+    /// assert_eq!(machine.ram(0), 7);
+    /// ```
+    pub fn with_program(program: &[u16]) -> Self {
+        let mut machine = Machine::default();
+        machine.load_rom(program);
+        machine
+    }
+
+    /// Replaces the ROM content with `program`. The rest of the ROM is zeroed.
+    /// Panics if the program does not fit into the 32K ROM.
+    pub fn load_rom(&mut self, program: &[u16]) {
+        if program.len() > ROM_SIZE {
+            panic!("Program has {} instructions, but the ROM only holds {}.", program.len(), ROM_SIZE);
+        }
+        self.rom = vec![0; ROM_SIZE];
+        self.rom[..program.len()].copy_from_slice(program);
+    }
+
+    /// Sets registers, RAM and the cycle count back to zero. The ROM is kept.
+    pub fn reset(&mut self) {
+        self.ram = vec![0; RAM_SIZE];
+        self.a = 0;
+        self.d = 0;
+        self.pc = 0;
+        self.cycles = 0;
+    }
+
+    pub fn rom(&self, address: usize) -> u16 {
+        self.rom[address]
+    }
+    pub fn ram(&self, address: usize) -> i16 {
+        self.ram[address]
+    }
+    /// Writes directly into RAM (also the KBD register, so tests can "press a key").
+    pub fn set_ram(&mut self, address: usize, value: i16) {
+        self.ram[address] = value;
+    }
+    pub fn screen(&self) -> &[i16] {
+        &self.ram[SCREEN..SCREEN + SCREEN_SIZE]
+    }
+    pub fn press_key(&mut self, key_code: i16) {
+        self.ram[KBD] = key_code;
+    }
+    pub fn release_key(&mut self) {
+        self.ram[KBD] = 0;
+    }
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Runs until a tight loop is detected or `max_cycles` instructions have been executed.
+    pub fn run(&mut self, max_cycles: u64) -> Stop {
+        for _ in 0..max_cycles {
+            if self.is_in_tight_loop() {
+                return Stop::TightLoop;
+            }
+            self.step();
+        }
+        Stop::CycleLimit
+    }
+
+    /// Fetches and executes the instruction the PC points to.
+    pub fn step(&mut self) {
+        let instruction = self.rom[self.pc as usize];
+        self.cycles += 1;
+
+        // A-Instruction: the 15bit value goes into A.
+        if instruction & 0x8000 == 0 {
+            self.a = instruction as i16;
+            self.pc = next_pc(self.pc);
+            return;
+        }
+
+        // C-Instruction: 111a cccc ccdd djjj
+        let y = if instruction & 0x1000 == 0 {
+            self.a
+        } else {
+            self.ram[self.address_m()]
+        };
+        let out = alu(self.d, y, ((instruction >> 6) & 0x3F) as u8);
+
+        // M has to be written to the (old) address in A, before A may change.
+        if instruction & 0x0008 != 0 {
+            let address = self.address_m();
+            if address < KBD {
+                self.ram[address] = out;
+            }
+        }
+        if instruction & 0x0010 != 0 {
+            self.d = out;
+        }
+        let jump_target = self.a as u16 & 0x7FFF;
+        if instruction & 0x0020 != 0 {
+            self.a = out;
+        }
+
+        self.pc = if is_jump(out, instruction & 0x7) {
+            jump_target
+        } else {
+            next_pc(self.pc)
+        };
+    }
+
+    // The RAM address selected by A (only 15bits are wired to the memory).
+    fn address_m(&self) -> usize {
+        (self.a as u16 & 0x7FFF) as usize
+    }
+
+    // The canonical HACK halt is `(END) @END 0;JMP`: the PC points to
+    // an unconditional jump whose target is the `@END` right in front of it.
+    fn is_in_tight_loop(&self) -> bool {
+        let pc = self.pc as usize;
+        if pc == 0 {
+            return false;
+        }
+        let instruction = self.rom[pc];
+        let is_plain_goto = instruction & 0xE000 == 0xE000 && instruction & 0x0038 == 0 && instruction & 0x7 == 0x7;
+        is_plain_goto && self.rom[pc - 1] == (pc - 1) as u16
+    }
+}
+
+fn next_pc(pc: u16) -> u16 {
+    (pc + 1) & 0x7FFF
+}
+
+// The HACK ALU, driven by the six control bits zx nx zy ny f no.
+fn alu(x: i16, y: i16, control: u8) -> i16 {
+    let bit = |n: u8| control & (1 << (5 - n)) != 0;
+    let x = if bit(0) { 0 } else { x };
+    let x = if bit(1) { !x } else { x };
+    let y = if bit(2) { 0 } else { y };
+    let y = if bit(3) { !y } else { y };
+    let out = if bit(4) { x.wrapping_add(y) } else { x & y };
+    if bit(5) { !out } else { out }
+}
+
+// Evaluates the jjj bits against the ALU output.
+fn is_jump(out: i16, jump: u16) -> bool {
+    (jump & 0x4 != 0 && out < 0) || (jump & 0x2 != 0 && out == 0) || (jump & 0x1 != 0 && out > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ALU-TESTS
+    #[test]
+    fn alu_computes_zero() {
+        assert_eq!(alu(5, 9, 0b101010), 0);
+    }
+    #[test]
+    fn alu_computes_minus_one() {
+        assert_eq!(alu(5, 9, 0b111010), -1);
+    }
+    #[test]
+    fn alu_computes_d_minus_a() {
+        assert_eq!(alu(5, 9, 0b010011), -4);
+    }
+    #[test]
+    fn alu_computes_a_minus_d() {
+        assert_eq!(alu(5, 9, 0b000111), 4);
+    }
+    #[test]
+    fn alu_computes_not_d() {
+        assert_eq!(alu(5, 9, 0b001101), !5);
+    }
+    #[test]
+    fn alu_computes_d_or_a() {
+        assert_eq!(alu(5, 9, 0b010101), 13);
+    }
+    #[test]
+    fn alu_wraps_on_overflow() {
+        assert_eq!(alu(32767, 0, 0b011111), -32768);
+    }
+
+    // JUMP-TESTS
+    #[test]
+    fn jgt_jumps_on_positive() {
+        assert!(is_jump(1, 0b001));
+        assert!(!is_jump(0, 0b001));
+    }
+    #[test]
+    fn jle_jumps_on_zero_and_negative() {
+        assert!(is_jump(0, 0b110));
+        assert!(is_jump(-3, 0b110));
+        assert!(!is_jump(3, 0b110));
+    }
+
+    // INSTRUCTION-TESTS
+    #[test]
+    fn a_instruction_loads_a() {
+        let mut machine = Machine::with_program(&[1234]);
+        machine.step();
+        assert_eq!(machine.a, 1234);
+        assert_eq!(machine.pc, 1);
+    }
+    #[test]
+    fn m_is_written_to_old_a() {
+        // @5 AM=A+1
+        let mut machine = Machine::with_program(&[5, 0xEDE8]);
+        machine.step();
+        machine.step();
+        assert_eq!(machine.ram(5), 6);
+        assert_eq!(machine.a, 6);
+    }
+    #[test]
+    fn jump_uses_old_a() {
+        // @7 A=A+1;JMP
+        let mut machine = Machine::with_program(&[7, 0xEDE7]);
+        machine.step();
+        machine.step();
+        assert_eq!(machine.pc, 7);
+        assert_eq!(machine.a, 8);
+    }
+    #[test]
+    fn kbd_is_read_only_for_programs() {
+        // @KBD M=-1
+        let mut machine = Machine::with_program(&[KBD as u16, 0xEE88]);
+        machine.run(2);
+        assert_eq!(machine.ram(KBD), 0);
+    }
+    #[test]
+    fn detects_tight_loop() {
+        // @0 (END) @1 0;JMP
+        let mut machine = Machine::with_program(&[0, 1, 0xEA87]);
+        assert_eq!(machine.run(100), Stop::TightLoop);
+        assert_eq!(machine.cycles(), 2);
+    }
+    #[test]
+    fn stops_at_cycle_limit() {
+        // (LOOP) D=D+1 @LOOP 0;JMP
+        let mut machine = Machine::with_program(&[0xE7D0, 0, 0xEA87]);
+        assert_eq!(machine.run(10), Stop::CycleLimit);
+        assert_eq!(machine.cycles(), 10);
+    }
+    #[test]
+    fn reset_keeps_rom() {
+        let mut machine = Machine::with_program(&[3]);
+        machine.step();
+        machine.reset();
+        assert_eq!(machine.a, 0);
+        assert_eq!(machine.rom(0), 3);
+    }
+}
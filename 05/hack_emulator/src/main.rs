@@ -0,0 +1,45 @@
+use hack_emulator::loader::parse_hack;
+use hack_emulator::{ Machine, Stop };
+use std::env;
+use std::fs;
+use std::process;
+
+// Default amount of instructions that get executed,
+// if the program does not stop in a tight loop before.
+const DEFAULT_CYCLES: u64 = 1_000_000;
+
+fn main() {
+    // Get's the .hack file and an optional cycle-limit
+    // via command-line arguments.
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        println!("Please enter a .hack file and optionally a max number of cycles.
+EXAMPLE: 'cargo run Mult.hack 10000'");
+        process::exit(1);
+    }
+    let max_cycles = match args.get(2) {
+        Some(cycles) => cycles.parse::<u64>().unwrap_or_else(|_| panic!("'{}' is not a valid number of cycles", cycles)),
+        None => DEFAULT_CYCLES,
+    };
+
+    let hack_file = fs::read_to_string(&args[1]).expect("could not read the .hack file");
+    let program = match parse_hack(&hack_file) {
+        Ok(program) => program,
+        Err(e) => {
+            println!("Could not load '{}': {}", args[1], e);
+            process::exit(1);
+        }
+    };
+
+    let mut machine = Machine::with_program(&program);
+    match machine.run(max_cycles) {
+        Stop::TightLoop => println!("Program halted after {} cycles.", machine.cycles()),
+        Stop::CycleLimit => println!("Stopped after the limit of {} cycles.", max_cycles),
+    }
+
+    // Dump registers and the virtual registers R0-R15
+    println!("A: {} D: {} PC: {}", machine.a, machine.d, machine.pc);
+    for address in 0..16 {
+        println!("RAM[{}]: {}", address, machine.ram(address));
+    }
+}
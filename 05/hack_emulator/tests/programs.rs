@@ -0,0 +1,43 @@
+use hack_emulator::loader::parse_hack;
+use hack_emulator::machine::{ Machine, Stop, SCREEN_SIZE };
+use std::fs;
+
+// Loads a .hack file of the 04 project into a fresh machine.
+fn load_project_04(path: &str) -> Machine {
+    let hack_file = fs::read_to_string(format!("../../04/{}", path)).expect("could not read hack-file in test");
+    let program = parse_hack(&hack_file).expect("hack-file in test is invalid");
+    Machine::with_program(&program)
+}
+
+fn run_mult(r0: i16, r1: i16) -> i16 {
+    let mut machine = load_project_04("mult/mult.hack");
+    machine.set_ram(0, r0);
+    machine.set_ram(1, r1);
+    assert_eq!(machine.run(10_000), Stop::TightLoop);
+    machine.ram(2)
+}
+
+#[test]
+fn mult_multiplies_r0_and_r1() {
+    assert_eq!(run_mult(6, 7), 42);
+    assert_eq!(run_mult(1, 123), 123);
+}
+#[test]
+fn mult_with_zero_is_zero() {
+    assert_eq!(run_mult(0, 9), 0);
+    assert_eq!(run_mult(9, 0), 0);
+}
+
+#[test]
+fn fill_blackens_screen_while_key_is_pressed() {
+    let mut machine = load_project_04("fill/Fill.hack");
+    machine.press_key(75);
+    // Fill never halts. Give it enough cycles to go over the whole screen.
+    assert_eq!(machine.run(500_000), Stop::CycleLimit);
+    assert!(machine.screen().iter().all(|&word| word == -1));
+    assert_eq!(machine.screen().len(), SCREEN_SIZE);
+
+    machine.release_key();
+    machine.run(500_000);
+    assert!(machine.screen().iter().all(|&word| word == 0));
+}
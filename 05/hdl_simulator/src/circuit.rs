@@ -0,0 +1,486 @@
+//! Flattens a chip into a netlist of `Nand` gates and `DFF`s and simulates it.
+//!
+//! While building, every bit of every pin becomes a node. A connection merges
+//! the nodes of both sides (union-find), so in the end every wire is one node,
+//! driven by a gate, a `DFF`, an input of the chip or a constant.
+//! Unconnected inputs of a part are `false`, like in the official simulator.
+
+use crate::error::HdlError;
+use crate::library::Library;
+use crate::parser::{ Body, ChipDef, Part, PinRef };
+use std::collections::{ HashMap, HashSet };
+use std::path::Path;
+use std::rc::Rc;
+
+// The nodes (and later the values) of the constants.
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+type Pins = HashMap<String, Vec<usize>>;
+
+/// A chip, that has been wired down to it's `Nand` gates and `DFF`s.
+///
+/// Combinational chips are evaluated with `eval`. Clocked chips follow the
+/// clock of the official simulator: `tick` (rising edge) stores the inputs
+/// of all `DFF`s, `tock` (falling edge) puts them onto their outputs.
+pub struct Circuit {
+    name: String,
+    inputs: HashSet<String>,
+    pins: Pins,
+    values: Vec<bool>,
+    // [a, b, out], ordered so every gate comes after the gates it depends on.
+    nands: Vec<[usize; 3]>,
+    // [in, out]
+    dffs: Vec<[usize; 2]>,
+    dff_state: Vec<bool>,
+}
+
+impl Circuit {
+    /// Builds the chip `name` from the chips of the `library`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hdl_simulator::{ Circuit, Library };
+    ///
+    /// let mut library = Library::new(&["../../01"]);
+    /// let mut xor = Circuit::new("Xor", &mut library).unwrap();
+    /// xor.set("a", 1).unwrap();
+    /// xor.eval();
+    /// assert_eq!(xor.get("out"), Ok(1));
+    /// ```
+    pub fn new(name: &str, library: &mut Library) -> Result<Circuit, HdlError> {
+        let chip = library.get(name)?;
+        let mut builder = Builder { library, parent: vec![FALSE, TRUE], nands: Vec::new(), dffs: Vec::new(), stack: Vec::new() };
+        let (interface, internal) = builder.instantiate(&chip)?;
+        builder.finish(&chip, interface.into_iter().chain(internal).collect())
+    }
+
+    /// Builds the chip of a `.hdl` file. The parts are searched in the same directory.
+    pub fn from_file(path: &Path) -> Result<Circuit, HdlError> {
+        let mut library = Library::new(&[path.parent().unwrap_or_else(|| Path::new("."))]);
+        let chip = library.load_file(path)?;
+        Circuit::new(&chip.name, &mut library)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of bits of a pin (`IN`, `OUT` or internal pin of the chip).
+    pub fn width(&self, pin: &str) -> Option<usize> {
+        self.pins.get(pin).map(|nodes| nodes.len())
+    }
+
+    /// Sets an input pin. Bits above the width of the pin are ignored.
+    /// The outputs only change with the next `eval`, `tick` or `tock`.
+    pub fn set(&mut self, pin: &str, value: u16) -> Result<(), HdlError> {
+        if !self.inputs.contains(pin) {
+            return Err(self.unknown_pin(pin));
+        }
+        for (bit, node) in self.pins[pin].iter().enumerate() {
+            self.values[*node] = value & (1 << bit) != 0;
+        }
+        Ok(())
+    }
+
+    /// The current value of a pin (`IN`, `OUT` or internal pin of the chip).
+    pub fn get(&self, pin: &str) -> Result<u16, HdlError> {
+        let nodes = self.pins.get(pin).ok_or_else(|| self.unknown_pin(pin))?;
+        Ok(nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| self.values[**node])
+            .fold(0, |value, (bit, _)| value | 1 << bit))
+    }
+
+    /// Propagates the inputs (and the outputs of the `DFF`s) through all gates.
+    pub fn eval(&mut self) {
+        for [a, b, out] in &self.nands {
+            self.values[*out] = !(self.values[*a] && self.values[*b]);
+        }
+    }
+
+    /// Rising clock edge: every `DFF` stores it's current input.
+    pub fn tick(&mut self) {
+        self.eval();
+        for (state, [input, _]) in self.dff_state.iter_mut().zip(&self.dffs) {
+            *state = self.values[*input];
+        }
+    }
+
+    /// Falling clock edge: every `DFF` outputs what it stored on `tick`.
+    pub fn tock(&mut self) {
+        for (state, [_, output]) in self.dff_state.iter().zip(&self.dffs) {
+            self.values[*output] = *state;
+        }
+        self.eval();
+    }
+
+    /// A whole clock cycle (`tick` and `tock`).
+    pub fn clock(&mut self) {
+        self.tick();
+        self.tock();
+    }
+
+    fn unknown_pin(&self, pin: &str) -> HdlError {
+        HdlError::UnknownPin { chip: self.name.to_string(), pin: pin.to_string() }
+    }
+}
+
+struct Builder<'l> {
+    library: &'l mut Library,
+    // union-find over all nodes
+    parent: Vec<usize>,
+    nands: Vec<[usize; 3]>,
+    dffs: Vec<[usize; 2]>,
+    // the chips currently being instantiated (to catch recursion)
+    stack: Vec<String>,
+}
+
+impl<'l> Builder<'l> {
+    fn node(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.parent.len() - 1
+    }
+
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut node = node;
+        while self.parent[node] != root {
+            let next = self.parent[node];
+            self.parent[node] = root;
+            node = next;
+        }
+        root
+    }
+
+    // A constant always stays the root of it's set.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b || (a <= TRUE && b <= TRUE) {
+            return;
+        }
+        if b <= TRUE {
+            self.parent[a] = b;
+        } else {
+            self.parent[b] = a;
+        }
+    }
+
+    // Creates the nodes of the chip's pins and everything inside of it.
+    // Returns the interface pins and the internal pins.
+    fn instantiate(&mut self, chip: &ChipDef) -> Result<(Pins, Pins), HdlError> {
+        if self.stack.contains(&chip.name) {
+            return Err(HdlError::RecursiveChip { name: chip.name.to_string() });
+        }
+        let mut pins = Pins::new();
+        for pin in chip.inputs.iter().chain(&chip.outputs) {
+            let nodes = (0..pin.width).map(|_| self.node()).collect();
+            pins.insert(pin.name.to_string(), nodes);
+        }
+        let internal = match &chip.body {
+            Body::Nand => {
+                self.nands.push([pins["a"][0], pins["b"][0], pins["out"][0]]);
+                Pins::new()
+            },
+            Body::Dff => {
+                self.dffs.push([pins["in"][0], pins["out"][0]]);
+                Pins::new()
+            },
+            Body::Parts(parts) => {
+                self.stack.push(chip.name.to_string());
+                let internal = self.wire(chip, parts, &pins)?;
+                self.stack.pop();
+                internal
+            },
+        };
+        Ok((pins, internal))
+    }
+
+    fn wire(&mut self, chip: &ChipDef, parts: &[Part], pins: &Pins) -> Result<Pins, HdlError> {
+        let definitions = parts
+            .iter()
+            .map(|part| self.library.get(&part.chip))
+            .collect::<Result<Vec<Rc<ChipDef>>, HdlError>>()?;
+        let internal = self.internal_pins(chip, parts, &definitions)?;
+
+        for (part, definition) in parts.iter().zip(&definitions) {
+            let (inner_pins, _) = self.instantiate(definition)?;
+            let mut connected = HashSet::new();
+            for connection in &part.connections {
+                let inner = &connection.inner;
+                let inner_nodes = inner_pins
+                    .get(&inner.name)
+                    .ok_or_else(|| HdlError::UnknownPin { chip: part.chip.to_string(), pin: inner.name.to_string() })?;
+                let inner_nodes = slice(inner_nodes, inner).ok_or_else(|| out_of_range(chip, part, inner))?;
+                let outer = &connection.outer;
+                let outer_nodes = match outer.name.as_str() {
+                    "true" | "false" if outer.range.is_none() => {
+                        let constant = if outer.name == "true" { TRUE } else { FALSE };
+                        vec![constant; inner_nodes.len()]
+                    },
+                    _ => {
+                        let nodes = pins
+                            .get(&outer.name)
+                            .or_else(|| internal.get(&outer.name))
+                            .ok_or_else(|| HdlError::UnknownPin { chip: chip.name.to_string(), pin: outer.name.to_string() })?;
+                        slice(nodes, outer).ok_or_else(|| out_of_range(chip, part, outer))?
+                    },
+                };
+                if inner_nodes.len() != outer_nodes.len() {
+                    return Err(HdlError::WidthMismatch {
+                        chip: chip.name.to_string(),
+                        part: part.chip.to_string(),
+                        pin: inner.name.to_string(),
+                        expected: inner_nodes.len(),
+                        found: outer_nodes.len(),
+                    });
+                }
+                for (inner_node, outer_node) in inner_nodes.iter().zip(outer_nodes) {
+                    self.union(*inner_node, outer_node);
+                    connected.insert(*inner_node);
+                }
+            }
+            for input in &definition.inputs {
+                for node in &inner_pins[&input.name] {
+                    if !connected.contains(node) {
+                        self.union(*node, FALSE);
+                    }
+                }
+            }
+        }
+        Ok(internal)
+    }
+
+    // An internal pin gets it's width from the part output, that drives it.
+    // Only outputs of parts may drive a pin and every internal pin has exactly one driver.
+    fn internal_pins(&mut self, chip: &ChipDef, parts: &[Part], definitions: &[Rc<ChipDef>]) -> Result<Pins, HdlError> {
+        let mut internal = Pins::new();
+        for (part, definition) in parts.iter().zip(definitions) {
+            for connection in &part.connections {
+                let (inner, outer) = (&connection.inner, &connection.outer);
+                let output = match definition.output(&inner.name) {
+                    Some(output) => output,
+                    None => continue,
+                };
+                let invalid = |message: &str| HdlError::InvalidConnection {
+                    chip: chip.name.to_string(),
+                    part: part.chip.to_string(),
+                    pin: inner.name.to_string(),
+                    message: message.to_string(),
+                };
+                if outer.name == "true" || outer.name == "false" {
+                    return Err(invalid("is an output and can not drive a constant"));
+                }
+                if chip.input(&outer.name).is_some() {
+                    return Err(invalid(&format!("can not drive the input pin '{}'", outer.name)));
+                }
+                if chip.output(&outer.name).is_some() {
+                    continue;
+                }
+                if outer.range.is_some() {
+                    return Err(invalid(&format!("can not drive a sub-bus of the internal pin '{}'", outer.name)));
+                }
+                if internal.contains_key(&outer.name) {
+                    return Err(invalid(&format!("drives the internal pin '{}', which already has a driver", outer.name)));
+                }
+                let width = match inner.range {
+                    Some((from, to)) => to - from + 1,
+                    None => output.width,
+                };
+                let nodes = (0..width).map(|_| self.node()).collect();
+                internal.insert(outer.name.to_string(), nodes);
+            }
+        }
+        Ok(internal)
+    }
+
+    // Resolves every node to it's wire and orders the gates.
+    fn finish(mut self, chip: &ChipDef, pins: Pins) -> Result<Circuit, HdlError> {
+        // Gives every wire a dense index; the constants keep 0 and 1.
+        let mut wires: HashMap<usize, usize> = HashMap::new();
+        let mut wire_of = Vec::with_capacity(self.parent.len());
+        for node in 0..self.parent.len() {
+            let root = self.find(node);
+            let next = wires.len();
+            wire_of.push(*wires.entry(root).or_insert(next));
+        }
+
+        let nands: Vec<[usize; 3]> = self.nands.iter().map(|[a, b, out]| [wire_of[*a], wire_of[*b], wire_of[*out]]).collect();
+        let dffs: Vec<[usize; 2]> = self.dffs.iter().map(|[input, output]| [wire_of[*input], wire_of[*output]]).collect();
+        let pins: Pins = pins
+            .into_iter()
+            .map(|(name, nodes)| (name, nodes.iter().map(|node| wire_of[*node]).collect()))
+            .collect();
+
+        let mut values = vec![false; wires.len()];
+        values[TRUE] = true;
+        let mut circuit = Circuit {
+            name: chip.name.to_string(),
+            inputs: chip.inputs.iter().map(|pin| pin.name.to_string()).collect(),
+            pins,
+            values,
+            nands: sort_gates(&nands).ok_or_else(|| HdlError::CombinationalLoop { chip: chip.name.to_string() })?,
+            dff_state: vec![false; dffs.len()],
+            dffs,
+        };
+        circuit.eval();
+        Ok(circuit)
+    }
+}
+
+// Orders the gates topologically (depth first, without recursion).
+// Returns `None` if the gates contain a loop.
+fn sort_gates(nands: &[[usize; 3]]) -> Option<Vec<[usize; 3]>> {
+    let drivers: HashMap<usize, usize> = nands.iter().enumerate().map(|(idx, gate)| (gate[2], idx)).collect();
+    // 0: not visited, 1: in progress, 2: done
+    let mut state = vec![0u8; nands.len()];
+    let mut sorted = Vec::with_capacity(nands.len());
+    for start in 0..nands.len() {
+        let mut stack = vec![(start, 0)];
+        while let Some((gate, input)) = stack.pop() {
+            if input == 0 {
+                match state[gate] {
+                    2 => continue,
+                    1 => return None,
+                    _ => state[gate] = 1,
+                }
+            }
+            if input < 2 {
+                stack.push((gate, input + 1));
+                if let Some(driver) = drivers.get(&nands[gate][input]) {
+                    match state[*driver] {
+                        0 => stack.push((*driver, 0)),
+                        1 => return None,
+                        _ => {},
+                    }
+                }
+            } else {
+                state[gate] = 2;
+                sorted.push(nands[gate]);
+            }
+        }
+    }
+    Some(sorted)
+}
+
+// The nodes of a (sub-)bus or `None` if the range is outside of the pin.
+fn slice(nodes: &[usize], pin: &PinRef) -> Option<Vec<usize>> {
+    match pin.range {
+        Some((from, to)) => nodes.get(from..=to).map(|nodes| nodes.to_vec()),
+        None => Some(nodes.to_vec()),
+    }
+}
+
+fn out_of_range(chip: &ChipDef, part: &Part, pin: &PinRef) -> HdlError {
+    HdlError::InvalidConnection {
+        chip: chip.name.to_string(),
+        part: part.chip.to_string(),
+        pin: pin.name.to_string(),
+        message: "selects bits outside of the pin".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(sources: &[&str]) -> Library {
+        let mut library = Library::new::<&str>(&[]);
+        for source in sources {
+            library.add_source(source, "test.hdl").unwrap();
+        }
+        library
+    }
+    const NOT: &str = "CHIP Not { IN in; OUT out; PARTS: Nand(a=in, b=in, out=out); }";
+
+    #[test]
+    fn evaluates_nand() {
+        let mut nand = Circuit::new("Nand", &mut library(&[])).unwrap();
+        nand.set("a", 1).unwrap();
+        nand.set("b", 1).unwrap();
+        nand.eval();
+        assert_eq!(nand.get("out"), Ok(0));
+    }
+    #[test]
+    fn unconnected_input_is_false() {
+        let and = "CHIP And { IN a; OUT out; PARTS: Nand(a=a, out=x); Not(in=x, out=out); }";
+        let mut circuit = Circuit::new("And", &mut library(&[NOT, and])).unwrap();
+        circuit.set("a", 1).unwrap();
+        circuit.eval();
+        assert_eq!(circuit.get("out"), Ok(0));
+        assert_eq!(circuit.get("x"), Ok(1));
+    }
+    #[test]
+    fn constants_fill_a_bus() {
+        let chip = "CHIP Ones { OUT out[4]; PARTS: Bus(in=true, out=out); }";
+        let bus = "CHIP Bus { IN in[4]; OUT out[4]; PARTS:
+            Not(in=in[0], out=n0); Not(in=n0, out=out[0]);
+            Not(in=in[3], out=n3); Not(in=n3, out=out[3]); }";
+        let circuit = Circuit::new("Ones", &mut library(&[NOT, chip, bus])).unwrap();
+        assert_eq!(circuit.get("out"), Ok(0b1001));
+    }
+    #[test]
+    fn dff_delays_by_one_cycle() {
+        let mut dff = Circuit::new("DFF", &mut library(&[])).unwrap();
+        dff.set("in", 1).unwrap();
+        dff.tick();
+        assert_eq!(dff.get("out"), Ok(0));
+        dff.tock();
+        assert_eq!(dff.get("out"), Ok(1));
+    }
+    #[test]
+    fn reports_width_mismatch() {
+        let chip = "CHIP X { IN a[2]; OUT out; PARTS: Not(in=a, out=out); }";
+        match Circuit::new("X", &mut library(&[NOT, chip])) {
+            Err(HdlError::WidthMismatch { expected: 1, found: 2, .. }) => {},
+            other => panic!("expected a width mismatch, got {:?}", other.err()),
+        }
+    }
+    #[test]
+    fn reports_unknown_internal_pin() {
+        let chip = "CHIP X { IN a; OUT out; PARTS: Not(in=nowhere, out=out); }";
+        assert_eq!(
+            Circuit::new("X", &mut library(&[NOT, chip])).err(),
+            Some(HdlError::UnknownPin { chip: "X".to_string(), pin: "nowhere".to_string() }));
+    }
+    #[test]
+    fn reports_driven_input() {
+        let chip = "CHIP X { IN a; OUT out; PARTS: Not(in=out, out=a); }";
+        match Circuit::new("X", &mut library(&[NOT, chip])) {
+            Err(HdlError::InvalidConnection { .. }) => {},
+            other => panic!("expected an invalid connection, got {:?}", other.err()),
+        }
+    }
+    #[test]
+    fn reports_combinational_loop() {
+        let chip = "CHIP X { OUT out; PARTS: Not(in=x, out=x, out=out); }";
+        assert_eq!(
+            Circuit::new("X", &mut library(&[NOT, chip])).err(),
+            Some(HdlError::CombinationalLoop { chip: "X".to_string() }));
+    }
+    #[test]
+    fn loop_through_dff_is_fine() {
+        let chip = "CHIP Toggle { OUT out; PARTS: Not(in=q, out=d); DFF(in=d, out=q, out=out); }";
+        let mut toggle = Circuit::new("Toggle", &mut library(&[NOT, chip])).unwrap();
+        toggle.clock();
+        assert_eq!(toggle.get("out"), Ok(1));
+        toggle.clock();
+        assert_eq!(toggle.get("out"), Ok(0));
+    }
+    #[test]
+    fn reports_recursive_chip() {
+        let chip = "CHIP X { IN in; OUT out; PARTS: X(in=in, out=out); }";
+        assert_eq!(
+            Circuit::new("X", &mut library(&[chip])).err(),
+            Some(HdlError::RecursiveChip { name: "X".to_string() }));
+    }
+    #[test]
+    fn only_inputs_can_be_set() {
+        let mut not = Circuit::new("Not", &mut library(&[NOT])).unwrap();
+        assert!(not.set("out", 1).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! Errors of parsing `.hdl` files and of wiring their parts into a circuit.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum HdlError {
+    /// A `.hdl` file could not be read.
+    Io { path: String, message: String },
+    /// The HDL syntax is violated in `file` at `line`.
+    Syntax { file: String, line: usize, message: String },
+    /// No `.hdl` file (and no built-in) defines the chip.
+    UnknownChip { name: String },
+    /// The chip has no pin of this name.
+    UnknownPin { chip: String, pin: String },
+    /// Both sides of a connection have a different number of bits.
+    WidthMismatch { chip: String, part: String, pin: String, expected: usize, found: usize },
+    /// A connection drives something, that can not be driven (an `IN` pin, a constant,
+    /// or an internal pin that already has a driver).
+    InvalidConnection { chip: String, part: String, pin: String, message: String },
+    /// The chip uses itself as one of it's parts.
+    RecursiveChip { name: String },
+    /// The gates of a chip form a loop, that is not broken by a `DFF`.
+    CombinationalLoop { chip: String },
+}
+
+impl fmt::Display for HdlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdlError::Io { path, message } => write!(f, "could not read '{}': {}", path, message),
+            HdlError::Syntax { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+            HdlError::UnknownChip { name } => write!(f, "chip '{}' is neither built-in nor found in any .hdl file", name),
+            HdlError::UnknownPin { chip, pin } => write!(f, "chip '{}' has no pin '{}'", chip, pin),
+            HdlError::WidthMismatch { chip, part, pin, expected, found } => write!(
+                f, "in chip '{}': pin '{}' of part '{}' has {} bit(s), but is connected to {} bit(s)",
+                chip, pin, part, expected, found),
+            HdlError::InvalidConnection { chip, part, pin, message } => write!(
+                f, "in chip '{}': pin '{}' of part '{}' {}", chip, pin, part, message),
+            HdlError::RecursiveChip { name } => write!(f, "chip '{}' contains itself", name),
+            HdlError::CombinationalLoop { chip } => write!(f, "chip '{}' has a combinational loop", chip),
+        }
+    }
+}
+
+impl std::error::Error for HdlError {}
@@ -0,0 +1,9 @@
+// Docs can be opened with "cargo doc --open"
+pub mod circuit;
+pub mod error;
+pub mod library;
+pub mod parser;
+
+pub use circuit::Circuit;
+pub use error::HdlError;
+pub use library::Library;
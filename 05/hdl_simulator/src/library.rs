@@ -0,0 +1,133 @@
+//! Finds the definition of a chip by it's name. Chips are looked up in
+//! the built-ins first and then in the `<Name>.hdl` files of the
+//! library's directories (in the order they have been added), so a chip
+//! can use the chips of it's sibling files and of earlier projects.
+
+use crate::error::HdlError;
+use crate::parser::{ parse_chip, Body, ChipDef, PinDecl };
+use std::collections::HashMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::rc::Rc;
+
+// The registers of the CPU are built-ins of the official simulator
+// (only to show them in the GUI); they behave exactly like a `Register`.
+const REGISTER_ALIASES: [&str; 2] = ["ARegister", "DRegister"];
+
+pub struct Library {
+    dirs: Vec<PathBuf>,
+    chips: HashMap<String, Rc<ChipDef>>,
+}
+
+impl Library {
+    /// Creates a library, that searches the given directories for `.hdl` files.
+    pub fn new<P: AsRef<Path>>(dirs: &[P]) -> Self {
+        let mut library = Library { dirs: Vec::new(), chips: HashMap::new() };
+        library.insert(builtin("Nand", &["a", "b"], "out", Body::Nand));
+        library.insert(builtin("DFF", &["in"], "out", Body::Dff));
+        for dir in dirs {
+            library.add_dir(dir);
+        }
+        library
+    }
+
+    /// Searches `dir` as well (after all directories added before).
+    pub fn add_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.dirs.push(dir.as_ref().to_path_buf());
+    }
+
+    /// Adds a chip from source code instead of a file. A chip of the same name gets replaced.
+    ///
+    /// # Examples
+    /// ```
+    /// use hdl_simulator::Library;
+    ///
+    /// let mut library = Library::new::<&str>(&[]);
+    /// library.add_source("CHIP Not { IN in; OUT out; PARTS: Nand(a=in, b=in, out=out); }", "Not.hdl").unwrap();
+    /// assert_eq!(library.get("Not").unwrap().inputs.len(), 1);
+    /// ```
+    pub fn add_source(&mut self, source: &str, file: &str) -> Result<Rc<ChipDef>, HdlError> {
+        let chip = Rc::new(parse_chip(source, file)?);
+        self.chips.insert(chip.name.to_string(), Rc::clone(&chip));
+        Ok(chip)
+    }
+
+    /// The definition of the chip `name`. Every file is only parsed once.
+    pub fn get(&mut self, name: &str) -> Result<Rc<ChipDef>, HdlError> {
+        if let Some(chip) = self.chips.get(name) {
+            return Ok(Rc::clone(chip));
+        }
+        if let Some(path) = self.find_file(name) {
+            let chip = self.load_file(&path)?;
+            if chip.name != name {
+                return Err(HdlError::Syntax {
+                    file: path.to_string_lossy().to_string(),
+                    line: 1,
+                    message: format!("expected the file to define chip '{}', but it defines '{}'", name, chip.name),
+                });
+            }
+            return Ok(chip);
+        }
+        if REGISTER_ALIASES.contains(&name) {
+            let register = self.get("Register")?;
+            return Ok(self.insert(ChipDef { name: name.to_string(), ..(*register).clone() }));
+        }
+        Err(HdlError::UnknownChip { name: name.to_string() })
+    }
+
+    /// Parses the `.hdl` file at `path` and adds it's chip to the library.
+    pub fn load_file(&mut self, path: &Path) -> Result<Rc<ChipDef>, HdlError> {
+        let file = path.to_string_lossy().to_string();
+        let source = fs::read_to_string(path).map_err(|e| HdlError::Io { path: file.to_string(), message: e.to_string() })?;
+        self.add_source(&source, &file)
+    }
+
+    fn find_file(&self, name: &str) -> Option<PathBuf> {
+        self.dirs.iter().map(|dir| dir.join(format!("{}.hdl", name))).find(|path| path.is_file())
+    }
+
+    fn insert(&mut self, chip: ChipDef) -> Rc<ChipDef> {
+        let chip = Rc::new(chip);
+        self.chips.insert(chip.name.to_string(), Rc::clone(&chip));
+        chip
+    }
+}
+
+fn builtin(name: &str, inputs: &[&str], output: &str, body: Body) -> ChipDef {
+    let pin = |name: &str| PinDecl { name: name.to_string(), width: 1 };
+    ChipDef {
+        name: name.to_string(),
+        inputs: inputs.iter().map(|input| pin(input)).collect(),
+        outputs: vec![pin(output)],
+        body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_builtins() {
+        let mut library = Library::new::<&str>(&[]);
+        assert_eq!(library.get("Nand").unwrap().body, Body::Nand);
+        assert_eq!(library.get("DFF").unwrap().body, Body::Dff);
+    }
+    #[test]
+    fn finds_chips_in_directories() {
+        let mut library = Library::new(&["../../01"]);
+        assert_eq!(library.get("Mux").unwrap().inputs.len(), 3);
+    }
+    #[test]
+    fn registers_of_the_cpu_are_registers() {
+        let mut library = Library::new(&["../../03/a"]);
+        let register = library.get("ARegister").unwrap();
+        assert_eq!(register.name, "ARegister");
+        assert_eq!(register.body, library.get("Register").unwrap().body);
+    }
+    #[test]
+    fn reports_unknown_chip() {
+        let mut library = Library::new(&["../../01"]);
+        assert_eq!(library.get("Foo").unwrap_err(), HdlError::UnknownChip { name: "Foo".to_string() });
+    }
+}
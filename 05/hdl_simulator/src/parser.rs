@@ -0,0 +1,358 @@
+//! Reads the `CHIP` definition of a `.hdl` file:
+//! the `IN` and `OUT` pins and the `PARTS`, that wire other chips together.
+//! Comments (`//`, `/* */` and `/** */`) are skipped by the tokenizer.
+
+use crate::error::HdlError;
+
+/// A pin of the chip's interface, e.g. `in[16]` (a single bit has width 1).
+#[derive(Debug, PartialEq, Clone)]
+pub struct PinDecl {
+    pub name: String,
+    pub width: usize,
+}
+
+/// One side of a connection. `range` selects a sub-bus: `a[3]` is `(3, 3)` and `a[0..7]` is `(0, 7)`.
+/// The constants `true` and `false` are pins as well.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PinRef {
+    pub name: String,
+    pub range: Option<(usize, usize)>,
+}
+
+/// `inner=outer`: the pin of the part on the left, the pin of the chip on the right.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Connection {
+    pub inner: PinRef,
+    pub outer: PinRef,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Part {
+    pub chip: String,
+    pub line: usize,
+    pub connections: Vec<Connection>,
+}
+
+/// What a chip is made of. Only the `Nand` gate and the `DFF` are built-in.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Body {
+    Parts(Vec<Part>),
+    Nand,
+    Dff,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChipDef {
+    pub name: String,
+    pub inputs: Vec<PinDecl>,
+    pub outputs: Vec<PinDecl>,
+    pub body: Body,
+}
+
+impl ChipDef {
+    pub fn input(&self, name: &str) -> Option<&PinDecl> {
+        self.inputs.iter().find(|pin| pin.name == name)
+    }
+    pub fn output(&self, name: &str) -> Option<&PinDecl> {
+        self.outputs.iter().find(|pin| pin.name == name)
+    }
+}
+
+/// The biggest bus the HACK platform uses.
+pub const MAX_WIDTH: usize = 16;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Ident(String),
+    Number(usize),
+    Symbol(char),
+    // The `..` of a sub-bus.
+    Range,
+}
+
+/// Parses the content of a `.hdl` file. `file` is only used to locate errors.
+///
+/// # Examples
+/// ```
+/// use hdl_simulator::parser::{parse_chip, Body};
+///
+/// let chip = parse_chip("CHIP Not { IN in; OUT out; PARTS: Nand(a=in, b=in, out=out); }", "Not.hdl").unwrap();
+/// assert_eq!(chip.name, "Not");
+/// assert_eq!(chip.inputs[0].name, "in");
+/// match chip.body {
+///     Body::Parts(parts) => assert_eq!(parts[0].connections.len(), 3),
+///     _ => panic!("Not is made of parts"),
+/// }
+/// ```
+pub fn parse_chip(source: &str, file: &str) -> Result<ChipDef, HdlError> {
+    let tokens = tokenize(source, file)?;
+    let mut parser = Parser { tokens, pos: 0, file };
+    parser.chip()
+}
+
+// Splits the source into tokens, each paired with it's line (starting at 1).
+fn tokenize(source: &str, file: &str) -> Result<Vec<(usize, Token)>, HdlError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let next = chars.get(idx + 1).cloned();
+        if c == '\n' {
+            line += 1;
+            idx += 1;
+        } else if c.is_whitespace() {
+            idx += 1;
+        } else if c == '/' && next == Some('/') {
+            while idx < chars.len() && chars[idx] != '\n' {
+                idx += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            let start_line = line;
+            idx += 2;
+            loop {
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some('*'), Some('/')) => break,
+                    (Some('\n'), _) => line += 1,
+                    (None, _) => return Err(syntax(file, start_line, "comment is never closed")),
+                    _ => {},
+                }
+                idx += 1;
+            }
+            idx += 2;
+        } else if c == '.' && next == Some('.') {
+            tokens.push((line, Token::Range));
+            idx += 2;
+        } else if c.is_ascii_digit() {
+            let start = idx;
+            while idx < chars.len() && chars[idx].is_ascii_digit() {
+                idx += 1;
+            }
+            let digits: String = chars[start..idx].iter().collect();
+            let number = digits.parse().map_err(|_| syntax(file, line, &format!("'{}' is too big", digits)))?;
+            tokens.push((line, Token::Number(number)));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = idx;
+            while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_') {
+                idx += 1;
+            }
+            tokens.push((line, Token::Ident(chars[start..idx].iter().collect())));
+        } else if "{}()[],;=:".contains(c) {
+            tokens.push((line, Token::Symbol(c)));
+            idx += 1;
+        } else {
+            return Err(syntax(file, line, &format!("unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+fn syntax(file: &str, line: usize, message: &str) -> HdlError {
+    HdlError::Syntax { file: file.to_string(), line, message: message.to_string() }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    file: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    // CHIP name { [IN pins;] [OUT pins;] PARTS: part* }
+    fn chip(&mut self) -> Result<ChipDef, HdlError> {
+        self.keyword("CHIP")?;
+        let name = self.ident()?;
+        self.symbol('{')?;
+        let inputs = if self.next_is_keyword("IN") { self.pin_decls()? } else { Vec::new() };
+        let outputs = if self.next_is_keyword("OUT") { self.pin_decls()? } else { Vec::new() };
+        self.keyword("PARTS")?;
+        self.symbol(':')?;
+        let mut parts = Vec::new();
+        while self.peek() != Some(&Token::Symbol('}')) {
+            parts.push(self.part()?);
+        }
+        self.symbol('}')?;
+        if self.pos < self.tokens.len() {
+            return Err(self.error("expected the end of the file"));
+        }
+        Ok(ChipDef { name, inputs, outputs, body: Body::Parts(parts) })
+    }
+
+    // (IN|OUT) name[width], name ... ;
+    fn pin_decls(&mut self) -> Result<Vec<PinDecl>, HdlError> {
+        self.pos += 1;
+        let mut pins = Vec::new();
+        loop {
+            let name = self.ident()?;
+            let width = if self.peek() == Some(&Token::Symbol('[')) {
+                self.pos += 1;
+                let width = self.number()?;
+                self.symbol(']')?;
+                width
+            } else {
+                1
+            };
+            if width == 0 || width > MAX_WIDTH {
+                return Err(self.error(&format!("pin '{}' must have 1 to {} bits", name, MAX_WIDTH)));
+            }
+            pins.push(PinDecl { name, width });
+            if self.peek() == Some(&Token::Symbol(';')) {
+                self.pos += 1;
+                return Ok(pins);
+            }
+            self.symbol(',')?;
+        }
+    }
+
+    // Chip(inner=outer, ...);
+    fn part(&mut self) -> Result<Part, HdlError> {
+        let line = self.line();
+        let chip = self.ident()?;
+        self.symbol('(')?;
+        let mut connections = Vec::new();
+        loop {
+            let inner = self.pin_ref()?;
+            self.symbol('=')?;
+            let outer = self.pin_ref()?;
+            connections.push(Connection { inner, outer });
+            if self.peek() == Some(&Token::Symbol(')')) {
+                self.pos += 1;
+                break;
+            }
+            self.symbol(',')?;
+        }
+        self.symbol(';')?;
+        Ok(Part { chip, line, connections })
+    }
+
+    // name, name[bit] or name[from..to]
+    fn pin_ref(&mut self) -> Result<PinRef, HdlError> {
+        let name = self.ident()?;
+        if self.peek() != Some(&Token::Symbol('[')) {
+            return Ok(PinRef { name, range: None });
+        }
+        self.pos += 1;
+        let from = self.number()?;
+        let to = if self.peek() == Some(&Token::Range) {
+            self.pos += 1;
+            self.number()?
+        } else {
+            from
+        };
+        self.symbol(']')?;
+        if from > to || to >= MAX_WIDTH {
+            return Err(self.error(&format!("'{}[{}..{}]' is no valid sub-bus", name, from, to)));
+        }
+        Ok(PinRef { name, range: Some((from, to)) })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        self.peek() == Some(&Token::Ident(keyword.to_string()))
+    }
+    // The line of the current token (or of the last one at the end of the file).
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|(line, _)| *line)
+            .unwrap_or(1)
+    }
+    fn error(&self, message: &str) -> HdlError {
+        syntax(self.file, self.line(), message)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), HdlError> {
+        if self.next_is_keyword(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", keyword)))
+        }
+    }
+    fn symbol(&mut self, symbol: char) -> Result<(), HdlError> {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", symbol)))
+        }
+    }
+    fn ident(&mut self) -> Result<String, HdlError> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.to_string();
+                self.pos += 1;
+                Ok(ident)
+            },
+            _ => Err(self.error("expected a name")),
+        }
+    }
+    fn number(&mut self) -> Result<usize, HdlError> {
+        match self.peek() {
+            Some(Token::Number(number)) => {
+                let number = *number;
+                self.pos += 1;
+                Ok(number)
+            },
+            _ => Err(self.error("expected a number")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin_ref(name: &str, range: Option<(usize, usize)>) -> PinRef {
+        PinRef { name: name.to_string(), range }
+    }
+
+    #[test]
+    fn skips_all_kinds_of_comments() {
+        let source = "/** doc\n * comment */\nCHIP And { // line comment\n IN a, b; /* inline */ OUT out;\n PARTS: }";
+        let chip = parse_chip(source, "And.hdl").unwrap();
+        assert_eq!(chip.inputs.len(), 2);
+        assert_eq!(chip.body, Body::Parts(Vec::new()));
+    }
+    #[test]
+    fn parses_bus_widths() {
+        let chip = parse_chip("CHIP Mux16 { IN a[16], b[16], sel; OUT out[16]; PARTS: }", "Mux16.hdl").unwrap();
+        assert_eq!(chip.input("a"), Some(&PinDecl { name: "a".to_string(), width: 16 }));
+        assert_eq!(chip.input("sel").unwrap().width, 1);
+        assert_eq!(chip.output("out").unwrap().width, 16);
+    }
+    #[test]
+    fn parses_sub_buses_and_constants() {
+        let source = "CHIP X { IN a[16]; OUT out; PARTS:\n Or8Way(in=a[0..7], out=out);\n Foo(x=a[15], y=true); }";
+        let chip = parse_chip(source, "X.hdl").unwrap();
+        let parts = match chip.body { Body::Parts(parts) => parts, _ => unreachable!() };
+        assert_eq!(parts[0].connections[0].outer, pin_ref("a", Some((0, 7))));
+        assert_eq!(parts[1].connections[0].outer, pin_ref("a", Some((15, 15))));
+        assert_eq!(parts[1].connections[1].outer, pin_ref("true", None));
+        assert_eq!(parts[1].line, 3);
+    }
+    #[test]
+    fn chip_without_inputs() {
+        let chip = parse_chip("CHIP Clock { OUT out; PARTS: }", "Clock.hdl").unwrap();
+        assert!(chip.inputs.is_empty());
+    }
+    #[test]
+    fn reports_line_of_syntax_error() {
+        let source = "CHIP Not {\n IN in;\n OUT out;\n PARTS:\n Nand(a=in b=in, out=out);\n}";
+        assert_eq!(
+            parse_chip(source, "Not.hdl"),
+            Err(HdlError::Syntax { file: "Not.hdl".to_string(), line: 5, message: "expected ','".to_string() }));
+    }
+    #[test]
+    fn rejects_reversed_range() {
+        assert!(parse_chip("CHIP X { IN a[16]; PARTS: Y(in=a[7..0]); }", "X.hdl").is_err());
+    }
+    #[test]
+    fn rejects_unclosed_comment() {
+        assert!(parse_chip("CHIP X { /* PARTS: }", "X.hdl").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use hdl_simulator::{ Circuit, HdlError, Library };
+use std::fs;
+
+// All projects, so a chip can use the chips of earlier projects.
+fn library() -> Library {
+    Library::new(&["../../01", "../../02", "../../03/a", "../../03/b", "../../05"])
+}
+
+fn build(name: &str) -> Circuit {
+    Circuit::new(name, &mut library()).unwrap_or_else(|e| panic!("could not build {}: {}", name, e))
+}
+
+// Sets all inputs, evaluates and returns the requested outputs.
+fn eval(chip: &mut Circuit, inputs: &[(&str, u16)], outputs: &[&str]) -> Vec<u16> {
+    for (pin, value) in inputs {
+        chip.set(pin, *value).unwrap();
+    }
+    chip.eval();
+    outputs.iter().map(|pin| chip.get(pin).unwrap()).collect()
+}
+
+#[test]
+fn every_chip_parses() -> Result<(), HdlError> {
+    for dir in &["../../01", "../../02", "../../03/a", "../../03/b", "../../05"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "hdl") {
+                library().load_file(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn mux_truth_table() {
+    let mut mux = build("Mux");
+    for input in 0..8 {
+        let (a, b, sel) = (input & 1, input >> 1 & 1, input >> 2 & 1);
+        let expected = if sel == 0 { a } else { b };
+        assert_eq!(eval(&mut mux, &[("a", a), ("b", b), ("sel", sel)], &["out"]), vec![expected]);
+    }
+}
+
+#[test]
+fn dmux8way_selects_output() {
+    let mut dmux = build("DMux8Way");
+    let outputs = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    for sel in 0..8 {
+        let expected: Vec<u16> = (0..8).map(|idx| if idx == sel { 1 } else { 0 }).collect();
+        assert_eq!(eval(&mut dmux, &[("in", 1), ("sel", sel)], &outputs), expected);
+    }
+}
+
+#[test]
+fn add16_wraps_around() {
+    let mut add = build("Add16");
+    assert_eq!(eval(&mut add, &[("a", 12345), ("b", 54321)], &["out"]), vec![12345u16.wrapping_add(54321)]);
+    assert_eq!(eval(&mut add, &[("a", 0xFFFF), ("b", 1)], &["out"]), vec![0]);
+}
+
+#[test]
+fn alu_computes_the_hack_functions() {
+    let mut alu = build("ALU");
+    let (x, y) = (17, 3);
+    // (zx, nx, zy, ny, f, no, out)
+    let functions = [
+        (1, 0, 1, 0, 1, 0, 0),
+        (1, 1, 1, 1, 1, 1, 1),
+        (1, 1, 1, 0, 1, 0, (-1i16) as u16),
+        (0, 0, 1, 1, 0, 0, x),
+        (0, 0, 1, 1, 0, 1, !x),
+        (0, 0, 0, 0, 1, 0, x + y),
+        (0, 1, 0, 0, 1, 1, x - y),
+        (0, 0, 0, 1, 1, 1, y.wrapping_sub(x)),
+        (0, 1, 0, 1, 0, 1, x | y),
+    ];
+    for (zx, nx, zy, ny, f, no, out) in functions.iter() {
+        let inputs = [("x", x), ("y", y), ("zx", *zx), ("nx", *nx), ("zy", *zy), ("ny", *ny), ("f", *f), ("no", *no)];
+        let zr = if *out == 0 { 1 } else { 0 };
+        let ng = *out >> 15;
+        assert_eq!(eval(&mut alu, &inputs, &["out", "zr", "ng"]), vec![*out, zr, ng]);
+    }
+}
+
+#[test]
+fn bit_stores_on_load() {
+    let mut bit = build("Bit");
+    bit.set("in", 1).unwrap();
+    bit.clock();
+    assert_eq!(bit.get("out"), Ok(0));
+    bit.set("load", 1).unwrap();
+    bit.tick();
+    assert_eq!(bit.get("out"), Ok(0));
+    bit.tock();
+    assert_eq!(bit.get("out"), Ok(1));
+    bit.set("in", 0).unwrap();
+    bit.set("load", 0).unwrap();
+    bit.clock();
+    assert_eq!(bit.get("out"), Ok(1));
+}
+
+#[test]
+fn pc_counts_loads_and_resets() {
+    let mut pc = build("PC");
+    pc.set("inc", 1).unwrap();
+    pc.clock();
+    pc.clock();
+    assert_eq!(pc.get("out"), Ok(2));
+    pc.set("in", 1000).unwrap();
+    pc.set("load", 1).unwrap();
+    pc.clock();
+    assert_eq!(pc.get("out"), Ok(1000));
+    pc.set("load", 0).unwrap();
+    pc.clock();
+    assert_eq!(pc.get("out"), Ok(1001));
+    pc.set("reset", 1).unwrap();
+    pc.clock();
+    assert_eq!(pc.get("out"), Ok(0));
+}
+
+#[test]
+fn ram8_keeps_every_register() {
+    let mut ram = build("RAM8");
+    ram.set("load", 1).unwrap();
+    for address in 0..8 {
+        ram.set("address", address).unwrap();
+        ram.set("in", address * 111).unwrap();
+        ram.clock();
+    }
+    ram.set("load", 0).unwrap();
+    for address in 0..8 {
+        assert_eq!(eval(&mut ram, &[("address", address)], &["out"]), vec![address * 111]);
+    }
+}
+
+#[test]
+fn cpu_executes_instructions() {
+    let mut cpu = build("CPU");
+    // @7
+    cpu.set("instruction", 7).unwrap();
+    cpu.clock();
+    assert_eq!(cpu.get("addressM"), Ok(7));
+    assert_eq!(cpu.get("pc"), Ok(1));
+    // D=A
+    cpu.set("instruction", 0b1110110000010000).unwrap();
+    cpu.clock();
+    // MD=D+1 writes 8 to RAM[7]
+    cpu.set("instruction", 0b1110011111011000).unwrap();
+    cpu.eval();
+    assert_eq!(cpu.get("writeM"), Ok(1));
+    assert_eq!(cpu.get("outM"), Ok(8));
+    assert_eq!(cpu.get("addressM"), Ok(7));
+    cpu.clock();
+    // D;JGT jumps to A
+    cpu.set("instruction", 0b1110001100000001).unwrap();
+    cpu.clock();
+    assert_eq!(cpu.get("pc"), Ok(7));
+    cpu.set("reset", 1).unwrap();
+    cpu.clock();
+    assert_eq!(cpu.get("pc"), Ok(0));
+}
+
+#[test]
+fn memory_needs_screen_and_keyboard_builtins() {
+    assert_eq!(Circuit::new("Memory", &mut library()).err(), Some(HdlError::UnknownChip { name: "Screen".to_string() }));
+}
@@ -0,0 +1,30 @@
+//! Errors of reading and running a `.tst` script.
+//! Every error that happens while running knows the script line (starting at 1).
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TstError {
+    /// A script, program or comparison file could not be read (or the output not be written).
+    Io { path: String, message: String },
+    /// The script itself is malformed.
+    Syntax { line: usize, message: String },
+    /// The simulator rejected a command of the script.
+    Simulator { line: usize, message: String },
+    /// The `.out` line `line` differs from the same line of the `.cmp` file.
+    Comparison { line: usize, expected: String, found: String },
+}
+
+impl fmt::Display for TstError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TstError::Io { path, message } => write!(f, "could not access '{}': {}", path, message),
+            TstError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            TstError::Simulator { line, message } => write!(f, "line {}: {}", line, message),
+            TstError::Comparison { line, expected, found } => write!(
+                f, "Comparison failure at line {}\nexpected: {}\nfound:    {}", line, expected, found),
+        }
+    }
+}
+
+impl std::error::Error for TstError {}
@@ -0,0 +1,188 @@
+//! The column formats of `output-list`, e.g. `out%B3.1.3`:
+//! radix (`B`inary, `D`ecimal, `X` hex or `S`tring), then the spaces
+//! left of the value, the length of the value and the spaces right of it.
+//! Also the number notation of `set` (`%B0101`, `%XFF`, `%D-1` or `-1`).
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Radix {
+    Binary,
+    Decimal,
+    Hex,
+    Str,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Format {
+    pub radix: Radix,
+    pub pad_left: usize,
+    pub len: usize,
+    pub pad_right: usize,
+}
+
+/// An entry of the `output-list`. Without a format the simulator's default is used.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Column {
+    pub variable: String,
+    pub format: Option<Format>,
+}
+
+impl Format {
+    pub fn new(radix: Radix, pad_left: usize, len: usize, pad_right: usize) -> Self {
+        Format { radix, pad_left, len, pad_right }
+    }
+
+    /// Parses the part behind the `%`.
+    ///
+    /// # Examples
+    /// ```
+    /// use tst_runner::format::{Format, Radix};
+    ///
+    /// assert_eq!(Format::parse("D2.6.2"), Some(Format::new(Radix::Decimal, 2, 6, 2)));
+    /// assert_eq!(Format::parse("Q1.1.1"), None);
+    /// ```
+    pub fn parse(spec: &str) -> Option<Format> {
+        let radix = match spec.chars().next()? {
+            'B' => Radix::Binary,
+            'D' => Radix::Decimal,
+            'X' => Radix::Hex,
+            'S' => Radix::Str,
+            _ => return None,
+        };
+        let numbers = spec[1..].split('.').map(|n| n.parse().ok()).collect::<Option<Vec<usize>>>()?;
+        match numbers.as_slice() {
+            [pad_left, len, pad_right] => Some(Format::new(radix, *pad_left, *len, *pad_right)),
+            _ => None,
+        }
+    }
+
+    /// The width of the column between the `|`s.
+    pub fn width(&self) -> usize {
+        self.pad_left + self.len + self.pad_right
+    }
+
+    /// The name of the column, centered (and cut to the width of the column).
+    ///
+    /// # Examples
+    /// ```
+    /// use tst_runner::format::{Format, Radix};
+    ///
+    /// assert_eq!(Format::new(Radix::Binary, 3, 1, 3).header("out"), "  out  ");
+    /// assert_eq!(Format::new(Radix::Binary, 1, 16, 1).header("x"), "        x         ");
+    /// ```
+    pub fn header(&self, name: &str) -> String {
+        let name: String = name.chars().take(self.width()).collect();
+        let left = (self.width() - name.chars().count()) / 2;
+        format!("{}{:<width$}", " ".repeat(left), name, width = self.width() - left)
+    }
+
+    /// Formats a value. Binary and hex show the lowest `len` digits (two's complement),
+    /// decimals are right aligned, strings left aligned.
+    ///
+    /// # Examples
+    /// ```
+    /// use tst_runner::format::{Format, Radix};
+    ///
+    /// assert_eq!(Format::new(Radix::Binary, 1, 4, 1).value(-1), " 1111 ");
+    /// assert_eq!(Format::new(Radix::Decimal, 2, 6, 2).value(-5), "      -5  ");
+    /// assert_eq!(Format::new(Radix::Hex, 0, 4, 0).value(255), "00FF");
+    /// ```
+    pub fn value(&self, value: i32) -> String {
+        let bits = value as u32 as u64;
+        let text = match self.radix {
+            Radix::Binary => {
+                let digits = format!("{:064b}", bits);
+                digits[64 - self.len.min(64)..].to_string()
+            },
+            Radix::Hex => {
+                let digits = format!("{:016X}", bits);
+                digits[16 - self.len.min(16)..].to_string()
+            },
+            Radix::Decimal => format!("{:>len$}", value, len = self.len),
+            Radix::Str => format!("{:<len$}", value, len = self.len),
+        };
+        self.pad(&text)
+    }
+
+    /// Formats a text (left aligned) like the `time` of the hardware simulator.
+    pub fn text(&self, text: &str) -> String {
+        self.pad(&format!("{:<len$}", text, len = self.len))
+    }
+
+    fn pad(&self, text: &str) -> String {
+        format!("{}{}{}", " ".repeat(self.pad_left), text, " ".repeat(self.pad_right))
+    }
+}
+
+/// Parses an entry of the `output-list` like `RAM[0]%D2.6.2` or `a`.
+pub fn parse_column(word: &str) -> Option<Column> {
+    match word.find('%') {
+        Some(idx) => Some(Column { variable: word[..idx].to_string(), format: Some(Format::parse(&word[idx + 1..])?) }),
+        None => Some(Column { variable: word.to_string(), format: None }),
+    }
+}
+
+/// Parses a number of a script.
+///
+/// # Examples
+/// ```
+/// use tst_runner::format::parse_value;
+///
+/// assert_eq!(parse_value("%B0000000000000101"), Some(5));
+/// assert_eq!(parse_value("%B1111111111111111"), Some(-1));
+/// assert_eq!(parse_value("%X7FFF"), Some(32767));
+/// assert_eq!(parse_value("%D-3"), Some(-3));
+/// assert_eq!(parse_value("42"), Some(42));
+/// assert_eq!(parse_value("x"), None);
+/// ```
+pub fn parse_value(word: &str) -> Option<i32> {
+    let (radix, digits) = match word.get(..2) {
+        Some("%B") => (2, &word[2..]),
+        Some("%X") => (16, &word[2..]),
+        Some("%D") => (10, &word[2..]),
+        _ => (10, word),
+    };
+    if radix == 10 {
+        return digits.parse().ok();
+    }
+    // Binary and hex are 16bit words, so the highest bit is the sign.
+    let word = u32::from_str_radix(digits, radix).ok()?;
+    if word > 0xFFFF {
+        return None;
+    }
+    Some(word as u16 as i16 as i32)
+}
+
+/// Joins the cells of a line like `|  a  |  b  |`.
+pub fn to_line(cells: &[String]) -> String {
+    format!("|{}|", cells.join("|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_is_cut_to_width() {
+        assert_eq!(Format::new(Radix::Decimal, 0, 3, 0).header("address"), "add");
+    }
+    #[test]
+    fn binary_shows_lowest_bits() {
+        assert_eq!(Format::new(Radix::Binary, 3, 1, 3).value(2), "   0   ");
+    }
+    #[test]
+    fn time_is_left_aligned() {
+        assert_eq!(Format::new(Radix::Str, 1, 4, 1).text("0+"), " 0+   ");
+    }
+    #[test]
+    fn parses_column_without_format() {
+        assert_eq!(parse_column("out"), Some(Column { variable: "out".to_string(), format: None }));
+    }
+    #[test]
+    fn rejects_column_with_bad_format() {
+        assert_eq!(parse_column("out%B1.1"), None);
+    }
+    #[test]
+    fn builds_line() {
+        assert_eq!(to_line(&[" 0 ".to_string(), " 1 ".to_string()]), "| 0 | 1 |");
+    }
+}
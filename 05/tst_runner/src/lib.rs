@@ -0,0 +1,10 @@
+// Docs can be opened with "cargo doc --open"
+pub mod error;
+pub mod format;
+pub mod runner;
+pub mod script;
+pub mod target;
+
+pub use error::TstError;
+pub use runner::Runner;
+pub use target::Target;
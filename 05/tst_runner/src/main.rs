@@ -0,0 +1,36 @@
+use std::env;
+use std::path::Path;
+use std::process;
+use tst_runner::Runner;
+
+fn main() {
+    // Get's the .tst script and the directories to search for chips and programs
+    // via command-line arguments.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        println!("Please enter a .tst script and optionally directories with more chips.
+EXAMPLE: 'cargo run ../../02/ALU.tst ../../01'");
+        process::exit(1);
+    }
+    let mut runner = Runner::new();
+    for dir in &args[1..] {
+        runner.add_library_dir(dir);
+    }
+
+    match runner.run_file(Path::new(&args[0])) {
+        Ok(outcome) => {
+            for text in outcome.echo {
+                println!("{}", text);
+            }
+            if outcome.compared > 0 {
+                println!("End of script - Comparison ended successfully");
+            } else {
+                println!("End of script");
+            }
+        },
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,305 @@
+//! Executes a parsed script against the `Target` it loads and writes
+//! the `.out` table. With `compare-to` every output line is checked
+//! right away against the `.cmp` file, so the run stops at the first mismatch.
+
+use crate::error::TstError;
+use crate::format::{ to_line, Column, Format, Radix };
+use crate::script::{ parse_script, Command, Statement };
+use crate::target::{ CpuTarget, HdlTarget, Target, VmTarget };
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+/// Runs `.tst` scripts. Files the script names are searched next to the script
+/// first and then in the library directories (which are also used to find the parts of chips).
+#[derive(Debug, Default)]
+pub struct Runner {
+    library_dirs: Vec<PathBuf>,
+    output_dir: Option<PathBuf>,
+}
+
+/// What a script produced, if it ran (and compared) successfully.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    /// The lines of the output table (the header is the first line).
+    pub output: Vec<String>,
+    /// How many lines have been compared against the `.cmp` file.
+    pub compared: usize,
+    /// The texts of all `echo` commands.
+    pub echo: Vec<String>,
+}
+
+impl Runner {
+    pub fn new() -> Self {
+        Runner::default()
+    }
+
+    pub fn add_library_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.library_dirs.push(dir.as_ref().to_path_buf());
+    }
+
+    /// Writes the `output-file` into `dir` instead of the directory of the script.
+    pub fn set_output_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.output_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// Reads and runs a `.tst` file.
+    pub fn run_file(&self, script: &Path) -> Result<Outcome, TstError> {
+        let source = fs::read_to_string(script).map_err(|e| io_error(script, e))?;
+        self.run(&source, script.parent().unwrap_or_else(|| Path::new(".")))
+    }
+
+    /// Runs the source of a script, that lives in `dir`.
+    ///
+    /// # Examples
+    /// ```
+    /// use tst_runner::Runner;
+    /// use std::path::Path;
+    ///
+    /// let mut runner = Runner::new();
+    /// runner.add_library_dir("../../01");
+    /// let outcome = runner.run("load Not.hdl, output-list in%B1.1.1 out%B1.1.1; set in 1, eval, output;", Path::new(".")).unwrap();
+    /// assert_eq!(outcome.output, vec!["|in |out|", "| 1 | 0 |"]);
+    /// ```
+    pub fn run(&self, source: &str, dir: &Path) -> Result<Outcome, TstError> {
+        let statements = parse_script(source)?;
+        let mut run = Run {
+            runner: self,
+            dir,
+            target: None,
+            columns: Vec::new(),
+            output: Vec::new(),
+            output_file: None,
+            compare: None,
+            echo: Vec::new(),
+            time: 0,
+            ticked: false,
+        };
+        let result = run.execute(&statements);
+        run.write_output()?;
+        result?;
+        let compared = if run.compare.is_some() { run.output.len() } else { 0 };
+        Ok(Outcome { compared, output: run.output, echo: run.echo })
+    }
+
+    // Looks for a file next to the script first, then in the library directories.
+    fn find(&self, dir: &Path, file: &str) -> PathBuf {
+        let path = dir.join(file);
+        if path.exists() {
+            return path;
+        }
+        self.library_dirs.iter().map(|lib| lib.join(file)).find(|path| path.exists()).unwrap_or(path)
+    }
+}
+
+// The state of a single run of a script.
+struct Run<'r> {
+    runner: &'r Runner,
+    dir: &'r Path,
+    target: Option<Box<dyn Target>>,
+    columns: Vec<Column>,
+    output: Vec<String>,
+    output_file: Option<PathBuf>,
+    compare: Option<Vec<String>>,
+    echo: Vec<String>,
+    // The clock of the hardware simulator, `ticked` is the `+` between `tick` and `tock`.
+    time: usize,
+    ticked: bool,
+}
+
+impl<'r> Run<'r> {
+    fn execute(&mut self, statements: &[Statement]) -> Result<(), TstError> {
+        for statement in statements {
+            self.execute_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    fn execute_statement(&mut self, statement: &Statement) -> Result<(), TstError> {
+        let line = statement.line;
+        match &statement.command {
+            Command::Load(file) => {
+                let path = match file {
+                    Some(file) => self.runner.find(self.dir, file),
+                    None => self.dir.to_path_buf(),
+                };
+                self.target = Some(self.load(&path).map_err(|message| TstError::Simulator { line, message })?);
+                self.time = 0;
+                self.ticked = false;
+            },
+            Command::OutputFile(file) => {
+                let dir = self.runner.output_dir.as_deref().unwrap_or(self.dir);
+                self.output_file = Some(dir.join(file));
+            },
+            Command::CompareTo(file) => {
+                let path = self.runner.find(self.dir, file);
+                let cmp = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+                self.compare = Some(cmp.lines().map(|line| line.to_string()).collect());
+            },
+            Command::OutputList(columns) => {
+                self.columns = columns.clone();
+                let header: Vec<String> = columns.iter().map(|column| self.format_of(column).header(&column.variable)).collect();
+                self.push_output(to_line(&header))?;
+            },
+            Command::Set { variable, value } => self.target(line)?.set(variable, *value).map_err(|message| TstError::Simulator { line, message })?,
+            Command::Output => {
+                let cells = self.columns.iter().map(|column| self.format_column(column)).collect::<Result<Vec<String>, String>>();
+                let cells = cells.map_err(|message| TstError::Simulator { line, message })?;
+                self.push_output(to_line(&cells))?;
+            },
+            Command::Echo(text) => self.echo.push(text.to_string()),
+            Command::ClearEcho => self.echo.clear(),
+            Command::Simulate(command) => {
+                self.target(line)?.simulate(command).map_err(|message| TstError::Simulator { line, message })?;
+                match command.as_str() {
+                    "tick" => self.ticked = true,
+                    "tock" | "ticktock" | "vmstep" => {
+                        self.time += 1;
+                        self.ticked = false;
+                    },
+                    _ => {},
+                }
+            },
+            Command::Repeat { times: Some(times), body } => {
+                for _ in 0..*times {
+                    self.execute(body)?;
+                }
+            },
+            Command::Repeat { times: None, body } => loop {
+                self.execute(body)?;
+            },
+            Command::While { condition, body } => {
+                while condition.holds(self.get(&condition.variable).map_err(|message| TstError::Simulator { line, message })?) {
+                    self.execute(body)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn load(&self, path: &Path) -> Result<Box<dyn Target>, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("hdl") => Ok(Box::new(HdlTarget::load(path, &self.runner.library_dirs)?)),
+            Some("hack") | Some("asm") => Ok(Box::new(CpuTarget::load(path)?)),
+            Some("vm") => Ok(Box::new(VmTarget::load(path)?)),
+            _ if path.is_dir() => Ok(Box::new(VmTarget::load(path)?)),
+            _ => Err(format!("'{}' can not be run: only .hdl, .hack, .asm and .vm files (or directories of them) can be loaded", path.display())),
+        }
+    }
+
+    fn target(&mut self, line: usize) -> Result<&mut Box<dyn Target>, TstError> {
+        self.target
+            .as_mut()
+            .ok_or_else(|| TstError::Simulator { line, message: "nothing has been loaded yet".to_string() })
+    }
+
+    fn get(&self, variable: &str) -> Result<i32, String> {
+        match &self.target {
+            Some(target) => target.get(variable),
+            None => Err("nothing has been loaded yet".to_string()),
+        }
+    }
+
+    fn format_of(&self, column: &Column) -> Format {
+        match (&column.format, &self.target) {
+            (Some(format), _) => *format,
+            _ if column.variable == "time" => Format::new(Radix::Str, 1, 4, 1),
+            (None, Some(target)) => target.default_format(&column.variable),
+            (None, None) => Format::new(Radix::Decimal, 1, 6, 1),
+        }
+    }
+
+    fn format_column(&self, column: &Column) -> Result<String, String> {
+        let format = self.format_of(column);
+        if column.variable == "time" {
+            return Ok(format.text(&format!("{}{}", self.time, if self.ticked { "+" } else { "" })));
+        }
+        Ok(format.value(self.get(&column.variable)?))
+    }
+
+    // Adds a line to the output and compares it right away.
+    fn push_output(&mut self, line: String) -> Result<(), TstError> {
+        self.output.push(line);
+        let idx = self.output.len() - 1;
+        if let Some(compare) = &self.compare {
+            let expected = compare.get(idx).map_or("", |line| line.as_str());
+            if !matches(expected, &self.output[idx]) {
+                return Err(TstError::Comparison { line: idx + 1, expected: expected.to_string(), found: self.output[idx].to_string() });
+            }
+        }
+        Ok(())
+    }
+
+    fn write_output(&self) -> Result<(), TstError> {
+        match &self.output_file {
+            Some(path) => {
+                let mut content = self.output.join("\n");
+                content.push('\n');
+                fs::write(path, content).map_err(|e| io_error(path, e))
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+// A `*` in the `.cmp` file matches any character. Trailing whitespace is ignored.
+fn matches(expected: &str, found: &str) -> bool {
+    let (expected, found) = (expected.trim_end(), found.trim_end());
+    expected.chars().count() == found.chars().count()
+        && expected.chars().zip(found.chars()).all(|(e, f)| e == '*' || e == f)
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> TstError {
+    TstError::Io { path: path.display().to_string(), message: error.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> Result<Outcome, TstError> {
+        let mut runner = Runner::new();
+        runner.add_library_dir("../../01");
+        runner.add_library_dir("../../03/a");
+        runner.run(source, Path::new("."))
+    }
+
+    #[test]
+    fn star_matches_any_character() {
+        assert!(matches("|  *  |", "|  7  |"));
+        assert!(!matches("|  *  |", "|  7 |"));
+    }
+    #[test]
+    fn counts_time_with_tick_and_tock() {
+        let outcome = run("load Bit.hdl, output-list time%S1.4.1 out;
+            set in 1, set load 1, tick, output; tock, output;").unwrap();
+        assert_eq!(outcome.output, vec!["| time |out|", "| 0+   | 0 |", "| 1    | 1 |"]);
+    }
+    #[test]
+    fn while_loops_until_condition_fails() {
+        let outcome = run("load ../../04/mult/mult.hack, output-list RAM[2]%D1.6.1;
+            set RAM[0] 3, set RAM[1] 4;
+            while RAM[2] < 12 { ticktock; }
+            output;").unwrap();
+        assert_eq!(outcome.output, vec!["| RAM[2] |", "|     12 |"]);
+    }
+    #[test]
+    fn reports_simulator_errors_with_line() {
+        assert_eq!(
+            run("load Not.hdl;\nset foo 1;").unwrap_err(),
+            TstError::Simulator { line: 2, message: "chip 'Not' has no pin 'foo'".to_string() });
+    }
+    #[test]
+    fn needs_a_loaded_target() {
+        assert!(run("eval;").is_err());
+    }
+    #[test]
+    fn runs_vm_files() {
+        let outcome = run("load ../../07/StackArithmetic/SimpleAdd/SimpleAdd.vm, output-list RAM[0]%D2.6.2 RAM[256]%D2.6.2;
+            set RAM[0] 256, repeat 3 { vmstep; } output;").unwrap();
+        assert_eq!(outcome.output, vec!["|  RAM[0]  | RAM[256] |", "|     257  |      15  |"]);
+    }
+    #[test]
+    fn rejects_unknown_files() {
+        assert!(run("load Main.txt;").is_err());
+    }
+}
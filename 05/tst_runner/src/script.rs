@@ -0,0 +1,335 @@
+//! Parses a `.tst` script into `Statement`s.
+//! Every command ends with `,`, `;` or `!` (only the body of
+//! `repeat` and `while` is closed by `}` instead).
+
+use crate::error::TstError;
+use crate::format::{ parse_column, parse_value, Column };
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    /// `load` without a file loads the directory of the script (a VM program).
+    Load(Option<String>),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<Column>),
+    Set { variable: String, value: i32 },
+    Output,
+    Echo(String),
+    ClearEcho,
+    /// The commands that depend on the simulator: `eval`, `tick`, `tock`, `ticktock`, `vmstep`.
+    Simulate(String),
+    /// `repeat {` without a number repeats forever.
+    Repeat { times: Option<usize>, body: Vec<Statement> },
+    While { condition: Condition, body: Vec<Statement> },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Statement {
+    pub line: usize,
+    pub command: Command,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Condition {
+    pub variable: String,
+    pub op: CompareOp,
+    pub value: i32,
+}
+
+impl Condition {
+    pub fn holds(&self, value: i32) -> bool {
+        match self.op {
+            CompareOp::Eq => value == self.value,
+            CompareOp::NotEq => value != self.value,
+            CompareOp::Lt => value < self.value,
+            CompareOp::Gt => value > self.value,
+            CompareOp::LtEq => value <= self.value,
+            CompareOp::GtEq => value >= self.value,
+        }
+    }
+}
+
+const SIMULATOR_COMMANDS: [&str; 5] = ["eval", "tick", "tock", "ticktock", "vmstep"];
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Word(String),
+    Str(String),
+    Symbol(char),
+}
+
+/// Parses the content of a `.tst` file.
+///
+/// # Examples
+/// ```
+/// use tst_runner::script::{parse_script, Command};
+///
+/// let script = parse_script("set a 1,\neval,\noutput;").unwrap();
+/// assert_eq!(script[0].command, Command::Set { variable: "a".to_string(), value: 1 });
+/// assert_eq!(script[1].command, Command::Simulate("eval".to_string()));
+/// assert_eq!(script[2].line, 3);
+/// ```
+pub fn parse_script(source: &str) -> Result<Vec<Statement>, TstError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let statements = parser.statements()?;
+    match parser.tokens.get(parser.pos) {
+        Some((line, _)) => Err(TstError::Syntax { line: *line, message: "unexpected '}'".to_string() }),
+        None => Ok(statements),
+    }
+}
+
+// Words end at whitespace or at one of the symbols.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, TstError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let next = chars.get(idx + 1).cloned();
+        if c == '\n' {
+            line += 1;
+            idx += 1;
+        } else if c.is_whitespace() {
+            idx += 1;
+        } else if c == '/' && next == Some('/') {
+            while idx < chars.len() && chars[idx] != '\n' {
+                idx += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            let start_line = line;
+            idx += 2;
+            loop {
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some('*'), Some('/')) => break,
+                    (Some('\n'), _) => line += 1,
+                    (None, _) => return Err(TstError::Syntax { line: start_line, message: "comment is never closed".to_string() }),
+                    _ => {},
+                }
+                idx += 1;
+            }
+            idx += 2;
+        } else if c == '"' {
+            let start = idx + 1;
+            idx = start;
+            while idx < chars.len() && chars[idx] != '"' && chars[idx] != '\n' {
+                idx += 1;
+            }
+            if chars.get(idx) != Some(&'"') {
+                return Err(TstError::Syntax { line, message: "string is never closed".to_string() });
+            }
+            tokens.push((line, Token::Str(chars[start..idx].iter().collect())));
+            idx += 1;
+        } else if ",;!{}".contains(c) {
+            tokens.push((line, Token::Symbol(c)));
+            idx += 1;
+        } else {
+            let start = idx;
+            while idx < chars.len() && !chars[idx].is_whitespace() && !",;!{}\"".contains(chars[idx]) {
+                idx += 1;
+            }
+            tokens.push((line, Token::Word(chars[start..idx].iter().collect())));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    // Parses statements until the end of the file or a `}`.
+    fn statements(&mut self) -> Result<Vec<Statement>, TstError> {
+        let mut statements = Vec::new();
+        while let Some((line, token)) = self.tokens.get(self.pos).cloned() {
+            if token == Token::Symbol('}') {
+                break;
+            }
+            let command = match token {
+                Token::Word(word) => {
+                    self.pos += 1;
+                    self.command(&word, line)?
+                },
+                _ => return Err(self.error(line, "expected a command")),
+            };
+            statements.push(Statement { line, command });
+        }
+        Ok(statements)
+    }
+
+    fn command(&mut self, word: &str, line: usize) -> Result<Command, TstError> {
+        let command = match word {
+            "repeat" => {
+                let times = match self.peek_word() {
+                    Some(times) => {
+                        self.pos += 1;
+                        Some(times.parse().map_err(|_| self.error(line, &format!("'{}' is no number of repetitions", times)))?)
+                    },
+                    None => None,
+                };
+                return Ok(Command::Repeat { times, body: self.block(line)? });
+            },
+            "while" => {
+                let variable = self.word(line)?;
+                let op = match self.word(line)?.as_str() {
+                    "=" => CompareOp::Eq,
+                    "<>" => CompareOp::NotEq,
+                    "<" => CompareOp::Lt,
+                    ">" => CompareOp::Gt,
+                    "<=" => CompareOp::LtEq,
+                    ">=" => CompareOp::GtEq,
+                    op => return Err(self.error(line, &format!("unknown comparison '{}'", op))),
+                };
+                let value = self.value(line)?;
+                return Ok(Command::While { condition: Condition { variable, op, value }, body: self.block(line)? });
+            },
+            "load" => {
+                let file = self.peek_word();
+                if file.is_some() {
+                    self.pos += 1;
+                }
+                Command::Load(file)
+            },
+            "output-file" => Command::OutputFile(self.word(line)?),
+            "compare-to" => Command::CompareTo(self.word(line)?),
+            "output-list" => {
+                let mut columns = Vec::new();
+                while let Some(word) = self.peek_word() {
+                    self.pos += 1;
+                    columns.push(parse_column(&word).ok_or_else(|| self.error(line, &format!("malformed column '{}'", word)))?);
+                }
+                Command::OutputList(columns)
+            },
+            "set" => Command::Set { variable: self.word(line)?, value: self.value(line)? },
+            "output" => Command::Output,
+            "echo" => match self.tokens.get(self.pos) {
+                Some((_, Token::Str(text))) => {
+                    let text = text.to_string();
+                    self.pos += 1;
+                    Command::Echo(text)
+                },
+                _ => return Err(self.error(line, "expected a \"text\"")),
+            },
+            "clear-echo" => Command::ClearEcho,
+            _ if SIMULATOR_COMMANDS.contains(&word) => Command::Simulate(word.to_string()),
+            _ => return Err(self.error(line, &format!("unknown command '{}'", word))),
+        };
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Symbol(',' ))) | Some((_, Token::Symbol(';'))) | Some((_, Token::Symbol('!'))) => {
+                self.pos += 1;
+                Ok(command)
+            },
+            _ => Err(self.error(line, &format!("expected ',', ';' or '!' after '{}'", word))),
+        }
+    }
+
+    // { statements } with an optional terminator behind it.
+    fn block(&mut self, line: usize) -> Result<Vec<Statement>, TstError> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Symbol('{'))) => self.pos += 1,
+            _ => return Err(self.error(line, "expected '{'")),
+        }
+        let body = self.statements()?;
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Symbol('}'))) => self.pos += 1,
+            _ => return Err(self.error(line, "block is never closed with '}'")),
+        }
+        if let Some((_, Token::Symbol(','))) | Some((_, Token::Symbol(';'))) = self.tokens.get(self.pos) {
+            self.pos += 1;
+        }
+        Ok(body)
+    }
+
+    fn peek_word(&self) -> Option<String> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Word(word))) => Some(word.to_string()),
+            _ => None,
+        }
+    }
+    fn word(&mut self, line: usize) -> Result<String, TstError> {
+        let word = self.peek_word().ok_or_else(|| self.error(line, "missing argument"))?;
+        self.pos += 1;
+        Ok(word)
+    }
+    fn value(&mut self, line: usize) -> Result<i32, TstError> {
+        let word = self.word(line)?;
+        parse_value(&word).ok_or_else(|| self.error(line, &format!("'{}' is no valid number", word)))
+    }
+    fn error(&self, line: usize, message: &str) -> TstError {
+        TstError::Syntax { line, message: message.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{ Format, Radix };
+
+    #[test]
+    fn parses_header_of_a_script() {
+        let script = parse_script("load And.hdl,\noutput-file And.out,\ncompare-to And.cmp,\noutput-list a%B3.1.3 out;").unwrap();
+        assert_eq!(script[0].command, Command::Load(Some("And.hdl".to_string())));
+        assert_eq!(script[2].command, Command::CompareTo("And.cmp".to_string()));
+        assert_eq!(script[3].command, Command::OutputList(vec![
+            Column { variable: "a".to_string(), format: Some(Format::new(Radix::Binary, 3, 1, 3)) },
+            Column { variable: "out".to_string(), format: None },
+        ]));
+    }
+    #[test]
+    fn load_without_file() {
+        assert_eq!(parse_script("load;").unwrap()[0].command, Command::Load(None));
+    }
+    #[test]
+    fn parses_nested_loops() {
+        let script = parse_script("repeat 3 {\n while RAM[0] <> 0 { ticktock; }\n output;\n}\nrepeat {\n tick, tock;\n}").unwrap();
+        match &script[0].command {
+            Command::Repeat { times: Some(3), body } => {
+                assert_eq!(body.len(), 2);
+                assert_eq!(body[0].line, 2);
+            },
+            other => panic!("expected repeat, got {:?}", other),
+        }
+        match &script[1].command {
+            Command::Repeat { times: None, body } => assert_eq!(body.len(), 2),
+            other => panic!("expected endless repeat, got {:?}", other),
+        }
+    }
+    #[test]
+    fn skips_comments() {
+        let script = parse_script("/* header\n comment */ set a %B1; // set a\n// done\noutput;").unwrap();
+        assert_eq!(script[1].line, 4);
+    }
+    #[test]
+    fn parses_echo() {
+        assert_eq!(parse_script("echo \"Press a key\";").unwrap()[0].command, Command::Echo("Press a key".to_string()));
+    }
+    #[test]
+    fn reports_missing_terminator() {
+        assert_eq!(
+            parse_script("set a 1\neval;"),
+            Err(TstError::Syntax { line: 1, message: "expected ',', ';' or '!' after 'set'".to_string() }));
+    }
+    #[test]
+    fn reports_unknown_command() {
+        assert!(parse_script("jump 5;").is_err());
+    }
+    #[test]
+    fn condition_compares() {
+        let condition = Condition { variable: "RAM[0]".to_string(), op: CompareOp::LtEq, value: 3 };
+        assert!(condition.holds(3));
+        assert!(!condition.holds(4));
+    }
+}
@@ -0,0 +1,261 @@
+//! The simulators a script can drive. The runner only talks to a `Target`,
+//! so every simulator decides on it's own which variables exist
+//! (pins, `RAM[i]`, `PC`, `local[i]`...) and which simulator commands it understands.
+
+use crate::format::{ Format, Radix };
+use hack_assembler::assembler::assemble;
+use hack_assembler::error::report;
+use hack_emulator::loader::parse_hack;
+use hack_emulator::Machine;
+use hdl_simulator::{ Circuit, Library };
+use vm_translator::emulator::{ self, Vm };
+use vm_translator::error::report as vm_report;
+use vm_translator::translator::parser::Segment;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+pub trait Target {
+    /// `set variable value`
+    fn set(&mut self, variable: &str, value: i32) -> Result<(), String>;
+    /// The value of a variable for `output` and `while`.
+    fn get(&self, variable: &str) -> Result<i32, String>;
+    /// Runs a simulator command like `eval`, `tick`, `tock`, `ticktock` or `vmstep`.
+    fn simulate(&mut self, command: &str) -> Result<(), String>;
+    /// The format of a variable in an `output-list` without explicit format.
+    fn default_format(&self, _variable: &str) -> Format {
+        Format::new(Radix::Decimal, 1, 6, 1)
+    }
+}
+
+/// Splits a variable like `RAM[17]` into it's index, if it has the given name.
+///
+/// # Examples
+/// ```
+/// use tst_runner::target::parse_index;
+///
+/// assert_eq!(parse_index("RAM[17]", "RAM"), Some(17));
+/// assert_eq!(parse_index("RAM[x]", "RAM"), None);
+/// assert_eq!(parse_index("ROM32K[1]", "RAM"), None);
+/// ```
+pub fn parse_index(variable: &str, name: &str) -> Option<usize> {
+    variable
+        .strip_prefix(name)?
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// The hardware simulator: a chip of the `hdl_simulator`, whose pins are the variables.
+pub struct HdlTarget {
+    circuit: Circuit,
+}
+
+impl HdlTarget {
+    /// Builds the chip of the `.hdl` file. Parts are searched next to the file and then in `library_dirs`.
+    pub fn load(path: &Path, library_dirs: &[PathBuf]) -> Result<HdlTarget, String> {
+        let mut library = Library::new(&[path.parent().unwrap_or_else(|| Path::new("."))]);
+        for dir in library_dirs {
+            library.add_dir(dir);
+        }
+        let chip = library.load_file(path).map_err(|e| e.to_string())?;
+        let circuit = Circuit::new(&chip.name, &mut library).map_err(|e| e.to_string())?;
+        Ok(HdlTarget { circuit })
+    }
+}
+
+impl Target for HdlTarget {
+    fn set(&mut self, variable: &str, value: i32) -> Result<(), String> {
+        self.circuit.set(variable, value as u16).map_err(|e| e.to_string())
+    }
+    // A 16bit bus is a signed number, smaller buses are unsigned.
+    fn get(&self, variable: &str) -> Result<i32, String> {
+        let value = self.circuit.get(variable).map_err(|e| e.to_string())?;
+        match self.circuit.width(variable) {
+            Some(16) => Ok(value as i16 as i32),
+            _ => Ok(value as i32),
+        }
+    }
+    fn simulate(&mut self, command: &str) -> Result<(), String> {
+        match command {
+            "eval" => self.circuit.eval(),
+            "tick" => self.circuit.tick(),
+            "tock" => self.circuit.tock(),
+            _ => return Err(format!("the hardware simulator does not know '{}'", command)),
+        }
+        Ok(())
+    }
+    fn default_format(&self, variable: &str) -> Format {
+        Format::new(Radix::Binary, 1, self.circuit.width(variable).unwrap_or(1), 1)
+    }
+}
+
+/// The CPU emulator: a `hack_emulator::Machine` with the variables `A`, `D`, `PC`, `RAM[i]` and `ROM32K[i]`.
+pub struct CpuTarget {
+    machine: Machine,
+}
+
+impl CpuTarget {
+    /// Loads a `.hack` file or assembles an `.asm` file.
+    pub fn load(path: &Path) -> Result<CpuTarget, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+        let program = if path.extension().is_some_and(|ext| ext == "asm") {
+            assemble(&source).map_err(|errors| report(&errors))?
+        } else {
+            parse_hack(&source).map_err(|e| e.to_string())?
+        };
+        Ok(CpuTarget::new(Machine::with_program(&program)))
+    }
+
+    pub fn new(machine: Machine) -> Self {
+        CpuTarget { machine }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+}
+
+impl Target for CpuTarget {
+    fn set(&mut self, variable: &str, value: i32) -> Result<(), String> {
+        match variable {
+            "A" => self.machine.a = value as i16,
+            "D" => self.machine.d = value as i16,
+            "PC" => self.machine.pc = value as u16,
+            _ => match parse_index(variable, "RAM") {
+                Some(address) if address < hack_emulator::machine::RAM_SIZE => self.machine.set_ram(address, value as i16),
+                _ => return Err(format!("the CPU emulator has no variable '{}'", variable)),
+            },
+        }
+        Ok(())
+    }
+    fn get(&self, variable: &str) -> Result<i32, String> {
+        match variable {
+            "A" => Ok(self.machine.a as i32),
+            "D" => Ok(self.machine.d as i32),
+            "PC" => Ok(self.machine.pc as i32),
+            _ => match (parse_index(variable, "RAM"), parse_index(variable, "ROM32K")) {
+                (Some(address), _) if address < hack_emulator::machine::RAM_SIZE => Ok(self.machine.ram(address) as i32),
+                (_, Some(address)) if address < hack_emulator::machine::ROM_SIZE => Ok(self.machine.rom(address) as i16 as i32),
+                _ => Err(format!("the CPU emulator has no variable '{}'", variable)),
+            },
+        }
+    }
+    fn simulate(&mut self, command: &str) -> Result<(), String> {
+        match command {
+            "ticktock" => {
+                self.machine.step();
+                Ok(())
+            },
+            _ => Err(format!("the CPU emulator does not know '{}'", command)),
+        }
+    }
+}
+
+/// The VM emulator: a `vm_translator::emulator::Vm` with the variables `sp`, `RAM[i]`
+/// and the segments `local[i]`, `argument[i]`, `this[i]`, `that[i]` and `temp[i]`.
+pub struct VmTarget {
+    vm: Vm,
+}
+
+impl VmTarget {
+    /// Loads a `.vm` file or all vm-files of a directory.
+    pub fn load(path: &Path) -> Result<VmTarget, String> {
+        let vm = if path.is_dir() { Vm::from_dir(path) } else { Vm::from_file(path) };
+        Ok(VmTarget::new(vm.map_err(|errors| vm_report(&errors))?))
+    }
+
+    pub fn new(vm: Vm) -> Self {
+        VmTarget { vm }
+    }
+
+    pub fn vm(&self) -> &Vm {
+        &self.vm
+    }
+
+    // The RAM address of a variable.
+    fn address(&self, variable: &str) -> Option<usize> {
+        if variable == "sp" {
+            return Some(emulator::SP);
+        }
+        if let Some(address) = parse_index(variable, "RAM") {
+            return Some(address).filter(|address| *address < emulator::RAM_SIZE);
+        }
+        let segments = [
+            ("local", Segment::Local),
+            ("argument", Segment::Argument),
+            ("this", Segment::This),
+            ("that", Segment::That),
+            ("temp", Segment::Temp),
+        ];
+        segments.iter().find_map(|(name, segment)| {
+            let index = parse_index(variable, name)?;
+            self.vm.segment_address(*segment, index as u32)
+        })
+    }
+}
+
+impl Target for VmTarget {
+    fn set(&mut self, variable: &str, value: i32) -> Result<(), String> {
+        let address = self.address(variable).ok_or_else(|| format!("the VM emulator has no variable '{}'", variable))?;
+        self.vm.set_ram(address, value as i16);
+        Ok(())
+    }
+    fn get(&self, variable: &str) -> Result<i32, String> {
+        let address = self.address(variable).ok_or_else(|| format!("the VM emulator has no variable '{}'", variable))?;
+        Ok(self.vm.ram(address) as i32)
+    }
+    fn simulate(&mut self, command: &str) -> Result<(), String> {
+        match command {
+            "vmstep" => self.vm.step().map_err(|e| e.to_string()),
+            _ => Err(format!("the VM emulator does not know '{}'", command)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_target_executes_ticktock() {
+        // @7 D=A
+        let mut cpu = CpuTarget::new(Machine::with_program(&[7, 0xEC10]));
+        cpu.simulate("ticktock").unwrap();
+        cpu.simulate("ticktock").unwrap();
+        assert_eq!(cpu.get("D"), Ok(7));
+        assert_eq!(cpu.get("PC"), Ok(2));
+    }
+    #[test]
+    fn cpu_target_sets_ram() {
+        let mut cpu = CpuTarget::new(Machine::new());
+        cpu.set("RAM[3]", -2).unwrap();
+        assert_eq!(cpu.get("RAM[3]"), Ok(-2));
+        assert!(cpu.set("RAM[40000]", 1).is_err());
+    }
+    #[test]
+    fn cpu_target_rejects_hardware_commands() {
+        assert!(CpuTarget::new(Machine::new()).simulate("eval").is_err());
+    }
+    #[test]
+    fn hdl_target_reads_16bit_buses_signed() {
+        let mut not16 = HdlTarget::load(Path::new("../../01/Not16.hdl"), &[]).unwrap();
+        not16.set("in", 0).unwrap();
+        not16.simulate("eval").unwrap();
+        assert_eq!(not16.get("out"), Ok(-1));
+        assert_eq!(not16.default_format("out"), Format::new(Radix::Binary, 1, 16, 1));
+    }
+    #[test]
+    fn vm_target_steps_through_commands() {
+        let vm = Vm::new(&[("Main", "function Sys.init 1\npush constant 5\npop local 0")]).unwrap();
+        let mut target = VmTarget::new(vm);
+        for _ in 0..3 {
+            target.simulate("vmstep").unwrap();
+        }
+        assert_eq!(target.get("local[0]"), Ok(5));
+        assert_eq!(target.get("sp"), Ok(262));
+        target.set("temp[2]", 9).unwrap();
+        assert_eq!(target.get("RAM[7]"), Ok(9));
+        assert!(target.get("pointer[0]").is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use tst_runner::{ Runner, TstError };
+
+fn runner() -> Runner {
+    let mut runner = Runner::new();
+    for dir in &["../../01", "../../02", "../../03/a", "../../04/mult"] {
+        runner.add_library_dir(dir);
+    }
+    runner.set_output_dir(env::temp_dir());
+    runner
+}
+
+#[test]
+fn xor_matches_cmp_file() {
+    let outcome = runner().run_file(Path::new("tests/scripts/Xor.tst")).unwrap();
+    assert_eq!(outcome.compared, 5);
+    let written = fs::read_to_string(env::temp_dir().join("Xor.out")).unwrap();
+    assert_eq!(written, fs::read_to_string("tests/scripts/Xor.cmp").unwrap());
+}
+
+#[test]
+fn mult_matches_cmp_file() {
+    let outcome = runner().run_file(Path::new("tests/scripts/Mult.tst")).unwrap();
+    assert_eq!(outcome.compared, 4);
+}
+
+#[test]
+fn reports_first_mismatching_line() {
+    let script = fs::read_to_string("tests/scripts/Xor.tst").unwrap()
+        .replace("Xor.cmp", "XorWrong.cmp")
+        .replace("Xor.out", "XorWrong.out");
+    assert_eq!(
+        runner().run(&script, Path::new("tests/scripts")).unwrap_err(),
+        TstError::Comparison {
+            line: 4,
+            expected: "|   1   |   0   |   0   |".to_string(),
+            found: "|   1   |   0   |   1   |".to_string(),
+        });
+}
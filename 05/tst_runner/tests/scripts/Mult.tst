@@ -0,0 +1,32 @@
+// Runs mult.hack of project 04 in the CPU emulator.
+
+load mult.hack,
+output-file Mult.out,
+compare-to Mult.cmp,
+output-list RAM[0]%D2.6.2 RAM[1]%D2.6.2 RAM[2]%D2.6.2;
+
+set RAM[0] 0,   // Set test arguments
+set RAM[1] 0,
+set RAM[2] -1;  // Test that program initialized product to 0
+repeat 20 {
+  ticktock;
+}
+output;
+
+set PC 0,
+set RAM[0] 3,
+set RAM[1] 1,
+set RAM[2] -1;
+repeat 50 {
+  ticktock;
+}
+output;
+
+set PC 0,
+set RAM[0] 6,
+set RAM[1] 7,
+set RAM[2] -1;
+repeat 150 {
+  ticktock;
+}
+output;
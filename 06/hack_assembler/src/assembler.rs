@@ -0,0 +1,249 @@
+//! Runs the passes, that turn the content of an `.asm` file
+//! into the 16bit words of a HACK program.
+//! Every pass keeps going after an error, so all errors
+//! of a pass can be reported at once.
+
+use crate::encoder::translate_instruction;
+use crate::error::AsmError;
+use crate::instruction::{ Instruction, Operand };
+use crate::parser::{ clean_line, get_command_fields };
+use crate::tables::SymbolsTable;
+use std::collections::{ HashMap, HashSet };
+
+/// Everything the passes know about an assembled program, so a `listing`
+/// and a symbol map can be written next to the `.hack` file.
+#[derive(Debug, PartialEq)]
+pub struct Assembly {
+    /// The words of the program.
+    pub program: Vec<u16>,
+    /// The source line every word has been assembled from (same index as `program`).
+    pub source_lines: Vec<usize>,
+    /// The labels with their ROM address, in the order they are defined.
+    pub labels: Vec<(String, u16)>,
+    /// The variables with their RAM address, in the order they are allocated.
+    pub variables: Vec<(String, u16)>,
+}
+
+/// Assembles the content of an `.asm` file into the words of the program.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::assembler::assemble;
+///
+/// let hack = assemble("@2\nD=A // comment\n(END)\n@END\n0;JMP").unwrap();
+/// assert_eq!(hack, vec![0b0000000000000010, 0b1110110000010000, 0b0000000000000010, 0b1110101010000111]);
+///
+/// let errors = assemble("D=Q+1\n(A)\n(A)\n0;JMPX").unwrap_err();
+/// assert_eq!(errors.iter().map(|e| e.line()).collect::<Vec<usize>>(), vec![1, 4, 3]);
+/// ```
+pub fn assemble(asm_file: &str) -> Result<Vec<u16>, Vec<AsmError>> {
+    assemble_with_symbols(asm_file).map(|assembly| assembly.program)
+}
+
+/// Like `assemble`, but keeps the source line of every word and
+/// the addresses of all labels and variables.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::assembler::assemble_with_symbols;
+///
+/// let assembly = assemble_with_symbols("@i\nM=0\n(LOOP)\n@LOOP\n0;JMP").unwrap();
+/// assert_eq!(assembly.source_lines, vec![1, 2, 4, 5]);
+/// assert_eq!(assembly.labels, vec![("LOOP".to_string(), 2)]);
+/// assert_eq!(assembly.variables, vec![("i".to_string(), 16)]);
+/// ```
+pub fn assemble_with_symbols(asm_file: &str) -> Result<Assembly, Vec<AsmError>> {
+    // Brings the SmybolTable with all predefined (HACK-specified)
+    // symbols into scope.
+    let mut symbols = SymbolsTable{symbols: HashMap::new()};
+    symbols.load_predefined_symbols();
+
+    // Labels of the lines, that could be translated, still get checked,
+    // so errors of the first and second pass are reported together.
+    let (first_pass, mut errors) = translate_lines(asm_file);
+    match second_pass(&first_pass, &mut symbols) {
+        Ok(second_pass) if errors.is_empty() => {
+            let labels = collect_labels(&first_pass, &symbols);
+            let known: HashSet<&str> = symbols.symbols.keys().cloned().collect();
+            let program = third_pass(&second_pass, &mut symbols);
+            Ok(Assembly {
+                program,
+                source_lines: second_pass.iter().map(|(line, _)| *line).collect(),
+                labels,
+                variables: collect_variables(&second_pass, &known, &symbols),
+            })
+        },
+        Ok(_) => Err(errors),
+        Err(label_errors) => {
+            errors.extend(label_errors);
+            Err(errors)
+        },
+    }
+}
+
+/// First-pass:
+/// Goes through every line of the input file and keeps
+/// relevant instruction (new-/empty-lines and comments are ignored).
+/// Every instruction is translated into an `Instruction` and
+/// paired with it's source line number.
+pub fn first_pass(asm_file: &str) -> Result<Vec<(usize, Instruction)>, Vec<AsmError>> {
+    let (first_pass, errors) = translate_lines(asm_file);
+    if errors.is_empty() { Ok(first_pass) } else { Err(errors) }
+}
+
+// Translates every line it can and collects the errors of all others.
+fn translate_lines(asm_file: &str) -> (Vec<(usize, Instruction)>, Vec<AsmError>) {
+    let mut first_pass = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in asm_file.split('\n').enumerate() {
+        let line_nr = idx + 1;
+        let cleaned = clean_line(line);
+        if cleaned.is_empty() {
+            continue;
+        }
+        match get_command_fields(cleaned, line_nr).and_then(|command| translate_instruction(command, line_nr)) {
+            Ok(instruction) => first_pass.push((line_nr, instruction)),
+            Err(error) => errors.push(error),
+        }
+    }
+    (first_pass, errors)
+}
+
+/// Second-pass:
+/// The counter keeps track of the following line-instruction. Because
+/// thats the value a label should have if it's not yet in the symbol-table.
+/// Labels get removed from the output, a label which is defined twice is an error.
+pub fn second_pass<'a>(first_pass: &'a [(usize, Instruction)], symbols: &mut SymbolsTable<'a>) -> Result<Vec<(usize, Instruction)>, Vec<AsmError>> {
+    let mut line_count = 0u32;
+    let mut label_lines: HashMap<&str, usize> = HashMap::new();
+    let mut second_pass = Vec::new();
+    let mut errors = Vec::new();
+    for (line, inst) in first_pass {
+        match inst {
+            Instruction::Label(label) => {
+                if let Some(first_line) = label_lines.get(label.as_str()) {
+                    errors.push(AsmError::DuplicateLabel { line: *line, text: format!("({})", label), first_line: *first_line });
+                    continue;
+                }
+                label_lines.insert(label, *line);
+                symbols.symbols.entry(label).or_insert(line_count);
+            },
+            _ => {
+                line_count += 1;
+                second_pass.push((*line, inst.clone()));
+            },
+        }
+    }
+    if errors.is_empty() { Ok(second_pass) } else { Err(errors) }
+}
+
+/// Third-pass
+/// Finally resolve all variables. Labels have been dealt with the pass before.
+/// New variables get the next free RAM address, starting at 16.
+/// Every instruction is encoded into it's 16bit word.
+pub fn third_pass<'a>(second_pass: &'a [(usize, Instruction)], symbols: &mut SymbolsTable<'a>) -> Vec<u16> {
+    let mut address_16_plus = 16;
+    second_pass.iter().map(|(_, inst)| {
+        match inst {
+            Instruction::A(Operand::Symbol(symbol)) => {
+                let address = *symbols.symbols.entry(symbol).or_insert_with(|| {
+                    address_16_plus += 1;
+                    address_16_plus - 1
+                });
+                address as u16
+            },
+            _ => inst.encode().expect("labels are removed by the second pass"),
+        }
+    }).collect()
+}
+
+// The ROM address of every label, that has been registered by the second pass.
+fn collect_labels(first_pass: &[(usize, Instruction)], symbols: &SymbolsTable) -> Vec<(String, u16)> {
+    let mut labels: Vec<(String, u16)> = Vec::new();
+    for (_, inst) in first_pass {
+        if let Instruction::Label(label) = inst {
+            if !labels.iter().any(|(known, _)| known == label) {
+                labels.push((label.to_string(), symbols.symbols[label.as_str()] as u16));
+            }
+        }
+    }
+    labels
+}
+
+// Every symbol, that was not known before the third pass, is a variable.
+fn collect_variables(second_pass: &[(usize, Instruction)], known: &HashSet<&str>, symbols: &SymbolsTable) -> Vec<(String, u16)> {
+    let mut variables: Vec<(String, u16)> = Vec::new();
+    for (_, inst) in second_pass {
+        if let Instruction::A(Operand::Symbol(symbol)) = inst {
+            if !known.contains(symbol.as_str()) && !variables.iter().any(|(known, _)| known == symbol) {
+                variables.push((symbol.to_string(), symbols.symbols[symbol.as_str()] as u16));
+            }
+        }
+    }
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::to_binary_string;
+
+    fn predefined_symbols<'a>() -> SymbolsTable<'a> {
+        let mut symbols = SymbolsTable{symbols: HashMap::new()};
+        symbols.load_predefined_symbols();
+        symbols
+    }
+    fn symbol(name: &str) -> Instruction {
+        Instruction::A(Operand::Symbol(name.to_string()))
+    }
+    fn label(name: &str) -> Instruction {
+        Instruction::Label(name.to_string())
+    }
+
+    #[test]
+    fn first_pass_keeps_line_numbers() {
+        let first_pass = first_pass("// comment\n\n@3\n(LOOP)").unwrap();
+        assert_eq!(first_pass, vec![(3, Instruction::A(Operand::Literal(3))), (4, label("LOOP"))]);
+    }
+    #[test]
+    fn first_pass_reports_all_errors() {
+        let errors = first_pass("D=Q+1\n@40000\n(LOOP\n0;JMPX").unwrap_err();
+        assert_eq!(errors.iter().map(|e| e.line()).collect::<Vec<usize>>(), vec![1, 2, 3, 4]);
+    }
+    #[test]
+    fn second_pass_registers_labels() {
+        let first_pass = vec![(1, label("START")), (2, symbol("END")), (3, label("END"))];
+        let mut symbols = predefined_symbols();
+        let second_pass = second_pass(&first_pass, &mut symbols).unwrap();
+        assert_eq!(second_pass, vec![(2, symbol("END"))]);
+        assert_eq!(symbols.symbols["START"], 0);
+        assert_eq!(symbols.symbols["END"], 1);
+    }
+    #[test]
+    fn second_pass_reports_duplicate_label() {
+        let first_pass = vec![(1, label("LOOP")), (5, label("LOOP"))];
+        let mut symbols = predefined_symbols();
+        assert_eq!(
+            second_pass(&first_pass, &mut symbols),
+            Err(vec![AsmError::DuplicateLabel { line: 5, text: "(LOOP)".to_string(), first_line: 1 }]));
+    }
+    #[test]
+    fn third_pass_allocates_variables_from_16() {
+        let second_pass = vec![(1, symbol("i")), (2, symbol("R2")), (3, symbol("sum")), (4, symbol("i"))];
+        let mut symbols = predefined_symbols();
+        assert_eq!(third_pass(&second_pass, &mut symbols), vec![16, 2, 17, 16]);
+    }
+    #[test]
+    fn collects_symbols_of_the_program() {
+        let assembly = assemble_with_symbols("@R2\n(START)\n@sum\nM=0\n@i\n@sum\n(END)\n@END\n0;JMP").unwrap();
+        assert_eq!(assembly.labels, vec![("START".to_string(), 1), ("END".to_string(), 5)]);
+        assert_eq!(assembly.variables, vec![("sum".to_string(), 16), ("i".to_string(), 17)]);
+    }
+    #[test]
+    fn assembles_mult_like_the_reference() {
+        let asm = std::fs::read_to_string("../../04/mult/mult.asm").unwrap();
+        let hack = std::fs::read_to_string("../../04/mult/mult.hack").unwrap();
+        let binary: Vec<String> = assemble(&asm).unwrap().into_iter().map(to_binary_string).collect();
+        assert_eq!(binary, hack.lines().collect::<Vec<&str>>());
+    }
+}
@@ -0,0 +1,244 @@
+//! The inverse of the `encoder`: decodes the words of a `.hack` file
+//! back into readable HACK assembly.
+//! The mnemonics come from the same `tables` the encoder uses, so
+//! assembling a disassembled program reproduces the same binary.
+
+use crate::error::DisasmError;
+use crate::instruction::{ Instruction, Operand };
+use crate::tables::*;
+use std::collections::{ BTreeSet, HashMap };
+
+/// Optional readability-features of `disassemble`.
+/// Without any option every A-instruction is a plain constant.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// Places a `(LABEL_<address>)` at every jump target and uses it in the `@`-instruction before the jump.
+    pub labels: bool,
+    /// Names constants after the predefined symbols `R0`-`R15`, `SCREEN` and `KBD`.
+    pub symbols: bool,
+    /// Uses `SP`, `LCL`, `ARG`, `THIS` and `THAT` instead of `R0`-`R4` (only together with `symbols`).
+    pub vm_pointers: bool,
+}
+
+/// Reads the lines of a `.hack` file into words.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::disassembler::parse_hack_file;
+///
+/// assert_eq!(parse_hack_file("0000000000000010\n1110110000010000\n"), Ok(vec![2, 0xEC10]));
+/// assert!(parse_hack_file("0000000000000010\n111").is_err());
+/// ```
+pub fn parse_hack_file(hack_file: &str) -> Result<Vec<u16>, DisasmError> {
+    hack_file
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line, word)| {
+            let is_binary_word = word.len() == 16 && word.chars().all(|c| c == '0' || c == '1');
+            match u16::from_str_radix(word, 2) {
+                Ok(word) if is_binary_word => Ok(word),
+                _ => Err(DisasmError::MalformedWord { line, text: word.to_string() }),
+            }
+        })
+        .collect()
+}
+
+/// Splits a word into the typed `Instruction` it encodes.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::disassembler::decode;
+/// use hack_assembler::instruction::{Instruction, Operand};
+///
+/// assert_eq!(decode(0b0000000000001011), Instruction::A(Operand::Literal(11)));
+/// assert_eq!(decode(0b1110110001001000), Instruction::C { dest: 0b001, comp: 0b0110001, jmp: 0 });
+/// ```
+pub fn decode(word: u16) -> Instruction {
+    if word & 0x8000 == 0 {
+        return Instruction::A(Operand::Literal(word));
+    }
+    Instruction::C {
+        comp: (word >> 6) & 0b1111111,
+        dest: (word >> 3) & 0b111,
+        jmp: word & 0b111,
+    }
+}
+
+/// Writes a single decoded instruction in assembly notation.
+/// A C-instruction whose computation is not part of the HACK-spec has no notation.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::disassembler::{decode, to_assembly};
+///
+/// assert_eq!(to_assembly(&decode(0b1110101010000111)), Some("0;JMP".to_string()));
+/// assert_eq!(to_assembly(&decode(0b1111110111011000)), Some("MD=M+1".to_string()));
+/// assert_eq!(to_assembly(&decode(0b1110000001000000)), None);
+/// ```
+pub fn to_assembly(inst: &Instruction) -> Option<String> {
+    match inst {
+        Instruction::A(Operand::Literal(value)) => Some(format!("@{}", value)),
+        Instruction::A(Operand::Symbol(symbol)) => Some(format!("@{}", symbol)),
+        Instruction::Label(label) => Some(format!("({})", label)),
+        Instruction::C { dest, comp, jmp } => {
+            let mut assembly = String::new();
+            if *dest != 0 {
+                assembly.push_str(find_mnemonic(&get_dest_table(), *dest, 3)?);
+                assembly.push('=');
+            }
+            assembly.push_str(get_comp_mnemonic(*comp)?);
+            if *jmp != 0 {
+                assembly.push(';');
+                assembly.push_str(find_mnemonic(&get_jmp_table(), *jmp, 3)?);
+            }
+            Some(assembly)
+        },
+    }
+}
+
+/// Turns the words of a program back into assembly lines.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::disassembler::{disassemble, Options};
+///
+/// // @R0 D=M @END D;JGT (END) @END 0;JMP
+/// let program = [0, 0xFC10, 4, 0xE301, 4, 0xEA87];
+/// assert_eq!(
+///     disassemble(&program, Options::default()).unwrap(),
+///     vec!["@0", "D=M", "@4", "D;JGT", "@4", "0;JMP"]);
+/// assert_eq!(
+///     disassemble(&program, Options { labels: true, symbols: true, vm_pointers: false }).unwrap(),
+///     vec!["@R0", "D=M", "@LABEL_4", "D;JGT", "(LABEL_4)", "@LABEL_4", "0;JMP"]);
+/// ```
+pub fn disassemble(program: &[u16], options: Options) -> Result<Vec<String>, DisasmError> {
+    let instructions: Vec<Instruction> = program.iter().map(|word| decode(*word)).collect();
+    let jump_targets = if options.labels { find_jump_targets(&instructions) } else { BTreeSet::new() };
+    let symbol_names = if options.symbols { get_symbol_names(options.vm_pointers) } else { HashMap::new() };
+
+    let mut lines = Vec::new();
+    for (address, inst) in instructions.iter().enumerate() {
+        if jump_targets.contains(&(address as u16)) {
+            lines.push(format!("(LABEL_{})", address));
+        }
+        let named = match inst {
+            Instruction::A(Operand::Literal(value)) if is_jump_address(&instructions, address) && jump_targets.contains(value) => {
+                Instruction::A(Operand::Symbol(format!("LABEL_{}", value)))
+            },
+            Instruction::A(Operand::Literal(value)) if symbol_names.contains_key(value) => {
+                Instruction::A(Operand::Symbol(symbol_names[value].to_string()))
+            },
+            _ => inst.clone(),
+        };
+        let assembly = to_assembly(&named).ok_or(DisasmError::UnknownComp { address, word: program[address] })?;
+        lines.push(assembly);
+    }
+    // A jump right behind the last instruction still needs it's label
+    if jump_targets.contains(&(program.len() as u16)) {
+        lines.push(format!("(LABEL_{})", program.len()));
+    }
+    Ok(lines)
+}
+
+// The value of an A-instruction is a jump target, if the next instruction jumps.
+// Only targets inside (or right behind) the program can get a label.
+fn find_jump_targets(instructions: &[Instruction]) -> BTreeSet<u16> {
+    instructions
+        .iter()
+        .enumerate()
+        .filter(|(address, _)| is_jump_address(instructions, *address))
+        .filter_map(|(_, inst)| match inst {
+            Instruction::A(Operand::Literal(value)) if *value as usize <= instructions.len() => Some(*value),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_jump_address(instructions: &[Instruction], address: usize) -> bool {
+    match instructions.get(address + 1) {
+        Some(Instruction::C { jmp, .. }) => *jmp != 0,
+        _ => false,
+    }
+}
+
+// The predefined symbols by address (the inverse of `SymbolsTable::load_predefined_symbols`).
+fn get_symbol_names(vm_pointers: bool) -> HashMap<u16, String> {
+    let mut names: HashMap<u16, String> = (0..16).map(|register| (register, format!("R{}", register))).collect();
+    if vm_pointers {
+        for (address, pointer) in ["SP", "LCL", "ARG", "THIS", "THAT"].iter().enumerate() {
+            names.insert(address as u16, pointer.to_string());
+        }
+    }
+    names.insert(16384, "SCREEN".to_string());
+    names.insert(24576, "KBD".to_string());
+    names
+}
+
+// The comp-bits include the a-bit, which decides between the two comp-tables.
+fn get_comp_mnemonic(comp: u16) -> Option<&'static str> {
+    if comp & 0b1000000 == 0 {
+        find_mnemonic(&get_comp_table_not_a(), comp, 6)
+    } else {
+        find_mnemonic(&get_comp_table_a(), comp & 0b111111, 6)
+    }
+}
+
+// Looks up the mnemonic of the given bits. The tables hold the bits as binary strings.
+fn find_mnemonic(table: &HashMap<&'static str, &'static str>, bits: u16, width: usize) -> Option<&'static str> {
+    let bits = format!("{:0>width$b}", bits, width = width);
+    table
+        .iter()
+        .find(|(mnemonic, table_bits)| **table_bits == bits && **mnemonic != "null")
+        .map(|(mnemonic, _)| *mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_c_instruction_fields() {
+        assert_eq!(decode(0b1110010101101010), Instruction::C { dest: 0b101, comp: 0b0010101, jmp: 0b010 });
+    }
+    #[test]
+    fn writes_all_fields() {
+        assert_eq!(to_assembly(&decode(0b1110010101101010)), Some("AM=D|A;JEQ".to_string()));
+    }
+    #[test]
+    fn writes_negative_m() {
+        assert_eq!(to_assembly(&decode(0b1111110011010000)), Some("D=-M".to_string()));
+    }
+    #[test]
+    fn every_comp_mnemonic_round_trips() {
+        for table in [get_comp_table_not_a(), get_comp_table_a()].iter() {
+            for mnemonic in table.keys() {
+                let asm = format!("D={}", mnemonic);
+                let word = crate::assembler::assemble(&asm).unwrap()[0];
+                assert_eq!(to_assembly(&decode(word)), Some(asm));
+            }
+        }
+    }
+    #[test]
+    fn names_vm_pointers() {
+        let options = Options { labels: false, symbols: true, vm_pointers: true };
+        assert_eq!(disassemble(&[0, 4, 5, 24576], options).unwrap(), vec!["@SP", "@THAT", "@R5", "@KBD"]);
+    }
+    #[test]
+    fn target_after_last_instruction_gets_label() {
+        let options = Options { labels: true, symbols: false, vm_pointers: false };
+        assert_eq!(disassemble(&[2, 0xEA87], options).unwrap(), vec!["@LABEL_2", "0;JMP", "(LABEL_2)"]);
+    }
+    #[test]
+    fn target_outside_program_stays_constant() {
+        let options = Options { labels: true, symbols: false, vm_pointers: false };
+        assert_eq!(disassemble(&[100, 0xEA87], options).unwrap(), vec!["@100", "0;JMP"]);
+    }
+    #[test]
+    fn reports_unknown_comp() {
+        assert_eq!(
+            disassemble(&[0, 0b1110000001000000], Options::default()),
+            Err(DisasmError::UnknownComp { address: 1, word: 0b1110000001000000 }));
+    }
+}
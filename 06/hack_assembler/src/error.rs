@@ -0,0 +1,99 @@
+//! Errors the assembler can run into while parsing, encoding and
+//! resolving the symbols of a `.asm` file (and the disassembler
+//! while reading a `.hack` file).
+//! Every error knows the source line (starting at 1) and the
+//! instruction text it was found in, so a whole file can be reported at once.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AsmError {
+    UnknownComp { line: usize, text: String, comp: String },
+    UnknownDest { line: usize, text: String, dest: String },
+    UnknownJump { line: usize, text: String, jmp: String },
+    MalformedLabel { line: usize, text: String },
+    MalformedInstruction { line: usize, text: String },
+    DuplicateLabel { line: usize, text: String, first_line: usize },
+    ConstantOutOfRange { line: usize, text: String },
+}
+
+impl AsmError {
+    /// The source line the error was found in.
+    pub fn line(&self) -> usize {
+        match self {
+            AsmError::UnknownComp { line, .. }
+            | AsmError::UnknownDest { line, .. }
+            | AsmError::UnknownJump { line, .. }
+            | AsmError::MalformedLabel { line, .. }
+            | AsmError::MalformedInstruction { line, .. }
+            | AsmError::DuplicateLabel { line, .. }
+            | AsmError::ConstantOutOfRange { line, .. } => *line,
+        }
+    }
+    /// The (cleaned) instruction the error was found in.
+    pub fn text(&self) -> &str {
+        match self {
+            AsmError::UnknownComp { text, .. }
+            | AsmError::UnknownDest { text, .. }
+            | AsmError::UnknownJump { text, .. }
+            | AsmError::MalformedLabel { text, .. }
+            | AsmError::MalformedInstruction { text, .. }
+            | AsmError::DuplicateLabel { text, .. }
+            | AsmError::ConstantOutOfRange { text, .. } => text,
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            AsmError::UnknownComp { comp, .. } => format!("unknown computation '{}'", comp),
+            AsmError::UnknownDest { dest, .. } => format!("unknown destination '{}'", dest),
+            AsmError::UnknownJump { jmp, .. } => format!("unknown jump '{}'", jmp),
+            AsmError::MalformedLabel { .. } => "malformed label".to_string(),
+            AsmError::MalformedInstruction { .. } => "malformed instruction".to_string(),
+            AsmError::DuplicateLabel { first_line, .. } => format!("label has already been defined in line {}", first_line),
+            AsmError::ConstantOutOfRange { .. } => "constant is not in the range 0..=32767".to_string(),
+        };
+        write!(f, "line {}: {} in '{}'", self.line(), reason, self.text())
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Errors of the `disassembler`. A `.hack` line has to be a 16 character
+/// binary word and every C-instruction needs a computation of the HACK-spec.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DisasmError {
+    MalformedWord { line: usize, text: String },
+    UnknownComp { address: usize, word: u16 },
+}
+
+impl fmt::Display for DisasmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisasmError::MalformedWord { line, text } => write!(f, "line {}: '{}' is not a 16bit binary word", line, text),
+            DisasmError::UnknownComp { address, word } => write!(f, "ROM[{}]: {:016b} has no known computation", address, word),
+        }
+    }
+}
+
+impl std::error::Error for DisasmError {}
+
+/// Builds a report with one error per line, sorted by source line.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::error::{AsmError, report};
+///
+/// let errors = vec![
+///     AsmError::UnknownJump { line: 7, text: "0;JMPX".to_string(), jmp: "JMPX".to_string() },
+///     AsmError::MalformedLabel { line: 2, text: "(LOOP".to_string() },
+/// ];
+/// assert_eq!(report(&errors), "line 2: malformed label in '(LOOP'\nline 7: unknown jump 'JMPX' in '0;JMPX'");
+/// ```
+pub fn report(errors: &[AsmError]) -> String {
+    let mut sorted: Vec<&AsmError> = errors.iter().collect();
+    sorted.sort_by_key(|error| error.line());
+    sorted.iter().map(|error| error.to_string()).collect::<Vec<String>>().join("\n")
+}
@@ -0,0 +1,75 @@
+//! The typed intermediate representation, that flows through the passes
+//! of the `assembler`. Once every symbol is resolved, an `Instruction`
+//! can be encoded into the 16bit word of the HACK machine language.
+
+/// The value of an A-instruction: either a constant or a symbol
+/// (label, variable or predefined symbol), which is resolved in the third pass.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operand {
+    Literal(u16),
+    Symbol(String),
+}
+
+/// A single instruction of a HACK program.
+/// The fields of a C-instruction hold the bits of their part of the word:
+/// `comp` the 7 bits `a c1..c6`, `dest` the 3 bits `d1 d2 d3` and `jmp` the 3 bits `j1 j2 j3`.
+/// A `Label` is a pseudo-instruction, it has no word of it's own.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+    A(Operand),
+    C { dest: u16, comp: u16, jmp: u16 },
+    Label(String),
+}
+
+impl Instruction {
+    /// The 16bit word of the instruction. Instructions with an unresolved
+    /// symbol and labels can not be encoded, so they result in `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hack_assembler::instruction::{Instruction, Operand};
+    ///
+    /// assert_eq!(Instruction::A(Operand::Literal(11)).encode(), Some(0b0000000000001011));
+    /// assert_eq!(Instruction::C { dest: 0b001, comp: 0b0110001, jmp: 0 }.encode(), Some(0b1110110001001000));
+    /// assert_eq!(Instruction::A(Operand::Symbol("i".to_string())).encode(), None);
+    /// ```
+    pub fn encode(&self) -> Option<u16> {
+        match self {
+            Instruction::A(Operand::Literal(value)) => Some(*value),
+            Instruction::C { dest, comp, jmp } => Some(0b111 << 13 | comp << 6 | dest << 3 | jmp),
+            Instruction::A(Operand::Symbol(_)) | Instruction::Label(_) => None,
+        }
+    }
+}
+
+/// Formats a word the way it is written into a `.hack` file.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::instruction::to_binary_string;
+///
+/// assert_eq!(to_binary_string(2013), "0000011111011101");
+/// ```
+pub fn to_binary_string(word: u16) -> String {
+    format!("{:016b}", word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_biggest_literal() {
+        assert_eq!(Instruction::A(Operand::Literal(32767)).encode(), Some(0x7FFF));
+    }
+    #[test]
+    fn encodes_c_instruction_with_all_fields() {
+        // AM=D|A;JEQ
+        let inst = Instruction::C { dest: 0b101, comp: 0b0010101, jmp: 0b010 };
+        assert_eq!(to_binary_string(inst.encode().unwrap()), "1110010101101010");
+    }
+    #[test]
+    fn label_has_no_word() {
+        assert_eq!(Instruction::Label("LOOP".to_string()).encode(), None);
+    }
+}
@@ -1,4 +1,9 @@
 // Docs can be opened with "cargo doc --open"
+pub mod assembler;
+pub mod disassembler;
+pub mod error;
+pub mod instruction;
+pub mod listing;
 pub mod parser;
 pub mod tables;
 
@@ -6,96 +11,128 @@ pub mod encoder {
     //! Takes care of the correct binary-translation
     //! for every CommandType.
     
+    use crate::error::AsmError;
+    use crate::instruction::{ Instruction, Operand };
     use crate::parser::CommandType;
     use crate::parser::CommandType::{ ACommand, CCommand, LCommand };
     use crate::tables::*;
 
-    /// Uses the `parser` module to translate the different `CommandType`s.
+    /// Uses the `parser` module to translate the different `CommandType`s
+    /// into a typed `Instruction`.
     /// It also uses the `tables` module to get the mapping for certain instructions.
-    /// In case of an `LCommand` variant, a `#` is put in front of the `&str` value.
-    /// This is usefull to later identify labels and variables.
+    /// If a field of a C-instruction is no valid HACK mnemonic, an `AsmError`
+    /// located at `line` is returned.
     /// 
     /// # Examples
     /// ```
     /// use hack_assembler::parser::CommandType::{ACommand, CCommand, LCommand};
     /// use hack_assembler::encoder::translate_instruction;
+    /// use hack_assembler::instruction::{Instruction, Operand};
     ///
-    /// assert_eq!(translate_instruction(ACommand("11")), "0000000000001011");
-    /// assert_eq!(translate_instruction(LCommand("loop")), "#loop");
-    /// assert_eq!(translate_instruction(CCommand{dest: Some("M"), comp: Some("!A"), jmp: None}), "1110110001001000");
-    ///
-    pub fn translate_instruction<'a>(inst: CommandType) -> String {
+    /// assert_eq!(translate_instruction(ACommand("11"), 1), Ok(Instruction::A(Operand::Literal(11))));
+    /// assert_eq!(translate_instruction(ACommand("i"), 1), Ok(Instruction::A(Operand::Symbol("i".to_string()))));
+    /// assert_eq!(translate_instruction(LCommand("loop"), 1), Ok(Instruction::Label("loop".to_string())));
+    /// assert_eq!(translate_instruction(CCommand{dest: Some("M"), comp: Some("!A"), jmp: None}, 1), Ok(Instruction::C{dest: 0b001, comp: 0b0110001, jmp: 0}));
+    /// assert!(translate_instruction(CCommand{dest: Some("D"), comp: Some("Q+1"), jmp: None}, 1).is_err());
+    /// ```
+    pub fn translate_instruction(inst: CommandType, line: usize) -> Result<Instruction, AsmError> {
         match inst {
-            ACommand(value) => get_bin_value(&value),
-            LCommand(value) => "#".to_owned() + value,
-            CCommand{dest, comp, jmp} => translate_c_command(dest, comp, jmp),
+            ACommand(value) => Ok(Instruction::A(get_operand(value))),
+            LCommand(value) => Ok(Instruction::Label(value.to_string())),
+            CCommand{dest, comp, jmp} => translate_c_command(dest, comp, jmp).map_err(|field| {
+                let text = inst.to_string();
+                match field {
+                    Field::Dest(dest) => AsmError::UnknownDest { line, text, dest },
+                    Field::Comp(comp) => AsmError::UnknownComp { line, text, comp },
+                    Field::Jmp(jmp) => AsmError::UnknownJump { line, text, jmp },
+                }
+            }),
         }
     }
 
-    /// Receives an instruction which is either a value or still variable.
-    /// If it is a value: the function parses the numeric `&str` into a number
-    /// and returns it as a 16bit binary `String`.
+    /// Receives the value of an A-instruction, which is either a constant or still variable.
+    /// If it is a constant: the function parses the numeric `&str` into a `Literal`.
     /// If the parsing does not work (the value is not numeric and must still be
-    /// representing a variable), the function attaches an `*` infront of
-    /// of the `&str` value and returns it as `String`. The `*` helps later to
-    /// distinguish between label (`#some-label`) and a variable (`*some-variable`).
+    /// representing a variable or label), it becomes a `Symbol`, which gets
+    /// resolved by the `assembler` passes.
     /// 
     /// # Examples
     ///
     /// ```
-    /// use hack_assembler::encoder::get_bin_value;
+    /// use hack_assembler::encoder::get_operand;
+    /// use hack_assembler::instruction::Operand;
     ///
-    /// assert_eq!(get_bin_value("7"), "0000000000000111");
-    /// assert_eq!(get_bin_value("2013"), "0000011111011101");
-    /// assert_eq!(get_bin_value("R11"), "*R11");
-    /// assert_eq!(get_bin_value("counter"), "*counter");
+    /// assert_eq!(get_operand("7"), Operand::Literal(7));
+    /// assert_eq!(get_operand("R11"), Operand::Symbol("R11".to_string()));
     /// ```
-    pub fn get_bin_value(a_inst: &str) -> String {
-        match a_inst.parse::<i32>() {
-            Ok(value) => format!("{:0>16b}", value),
-            Err(_) => format!("*{}", a_inst),
+    pub fn get_operand(a_inst: &str) -> Operand {
+        match a_inst.parse::<u16>() {
+            Ok(value) => Operand::Literal(value),
+            Err(_) => Operand::Symbol(a_inst.to_string()),
         }
     }
 
-    // A helper to build a final binary representation of a C-instruction.
-    fn translate_c_command<'a>(dest: Option<&str>, comp: Option<&str>, jmp: Option<&str>) -> String {
-
-        let mut c_inst_bin = String::from("111");
+    // The C-instruction-field which could not be translated.
+    #[derive(Debug, PartialEq)]
+    enum Field {
+        Dest(String),
+        Comp(String),
+        Jmp(String),
+    }
 
-        match comp {
-            Some(comp) => c_inst_bin.push_str(&get_comp(comp)),
-            None => c_inst_bin.push_str("0000000"),
+    // A helper to build the typed representation of a C-instruction.
+    fn translate_c_command(dest: Option<&str>, comp: Option<&str>, jmp: Option<&str>) -> Result<Instruction, Field> {
+        let comp = match comp {
+            Some(comp) => get_comp(comp).ok_or_else(|| Field::Comp(comp.to_string()))?,
+            None => 0,
         };
-        match dest {
-            Some(dest) => c_inst_bin.push_str(get_dest(dest)),
-            None => c_inst_bin.push_str("000"),
+        let dest = match dest {
+            Some(dest) => get_dest(dest).ok_or_else(|| Field::Dest(dest.to_string()))?,
+            None => 0,
         };
-        match jmp {
-            Some(jmp) => c_inst_bin.push_str(get_jmp(jmp)),
-            None => c_inst_bin.push_str("000"),
-        }
-        c_inst_bin
+        let jmp = match jmp {
+            Some(jmp) => get_jmp(jmp).ok_or_else(|| Field::Jmp(jmp.to_string()))?,
+            None => 0,
+        };
+        Ok(Instruction::C { dest, comp, jmp })
     }
 
     // Helper-functions that access the tables module and
-    // deliver the correct binary values for destination,
+    // deliver the correct bits for destination,
     // computation and jump fields of a C-instruction.
-    fn get_dest<'a>(dest: &str) -> &'a str {
+    // The destination registers may be given in any order (e.g. "DM").
+    fn get_dest(dest: &str) -> Option<u16> {
         let dest_table = get_dest_table();
-        dest_table[dest]
+        if dest_table.contains_key(dest) {
+            return Some(bits(dest_table[dest]));
+        }
+        let is_register_set = dest.chars().all(|c| "AMD".contains(c))
+            && dest.chars().enumerate().all(|(idx, c)| !dest[idx+1..].contains(c));
+        if !is_register_set {
+            return None;
+        }
+        let ordered: String = "AMD".chars().filter(|c| dest.contains(*c)).collect();
+        dest_table.get(ordered.as_str()).map(|dest_bits| bits(dest_bits))
     }
-    fn get_comp<'a>(comp: &str) -> String {
+    // Blanks are ignored (e.g. "M - 1"), the operands keep the order of the spec.
+    fn get_comp(comp: &str) -> Option<u16> {
+        let comp: String = comp.chars().filter(|c| !c.is_whitespace()).collect();
         let comp_table_a = get_comp_table_not_a();
         let comp_table_b = get_comp_table_a();
-        if comp_table_a.contains_key(comp) {
-            "0".to_owned() + comp_table_a[comp]
+        if let Some(comp_bits) = comp_table_a.get(comp.as_str()) {
+            Some(bits(comp_bits))
         } else {
-            "1".to_owned() + comp_table_b[comp]
+            comp_table_b.get(comp.as_str()).map(|comp_bits| 0b1000000 | bits(comp_bits))
         }
     }
-    fn get_jmp<'a>(jmp: &str) -> &'a str {
+    fn get_jmp(jmp: &str) -> Option<u16> {
         let jmp_table = get_jmp_table();
-        jmp_table[jmp]
+        jmp_table.get(jmp).map(|jmp_bits| bits(jmp_bits))
+    }
+
+    // The tables hold the bits as binary strings.
+    fn bits(binary: &str) -> u16 {
+        u16::from_str_radix(binary, 2).expect("tables only contain binary strings")
     }
 
     #[cfg(test)]
@@ -103,65 +140,99 @@ pub mod encoder {
         use super::*;
 
         #[test]
-        fn turns_a_inst_into_bin_value_1() {
-            assert_eq!(get_bin_value("100"), "0000000001100100");
+        fn turns_a_inst_into_literal() {
+            assert_eq!(get_operand("100"), Operand::Literal(100));
         }
 
         // test instruction translation
         #[test]
         fn translates_a_command() {
             let test_inst = ACommand("4");
-            assert_eq!(translate_instruction(test_inst), "0000000000000100");
+            assert_eq!(translate_instruction(test_inst, 1), Ok(Instruction::A(Operand::Literal(4))));
         }
 
         // Test instruction-part getters
         #[test]
         fn gets_correct_comp_a() {
-            assert_eq!(get_comp("D-A"), "0010011");
+            assert_eq!(get_comp("D-A"), Some(0b0010011));
         }
         #[test]
         fn gets_correct_comp_b() {
-            assert_eq!(get_comp("M+1"), "1110111");
+            assert_eq!(get_comp("M+1"), Some(0b1110111));
         }
         #[test]
         fn gets_correct_dest() {
-            assert_eq!(get_dest("A"), "100");
+            assert_eq!(get_dest("A"), Some(0b100));
         }
         #[test]
         fn gets_correct_dest_none() {
-            assert_eq!(get_dest("null"), "000");
+            assert_eq!(get_dest("null"), Some(0b000));
         }
         #[test]
         fn gets_correct_jmp() {
-            assert_eq!(get_jmp("JLT"), "100");
+            assert_eq!(get_jmp("JLT"), Some(0b100));
         }
         #[test]
         fn gets_correct_jmp_none() {
-            assert_eq!(get_jmp("null"), "000");
+            assert_eq!(get_jmp("null"), Some(0b000));
         }
 
         // test c-instruction translation
         #[test]
         fn translate_c_inst_all_none() {
-            assert_eq!(translate_c_command(None, None, None), "1110000000000000")
+            assert_eq!(translate_c_command(None, None, None).unwrap().encode(), Some(0b1110000000000000))
         }
         #[test]
         fn translate_c_inst_dest() {
-            assert_eq!(translate_c_command(Some("AM"), None, None), "1110000000101000")
+            assert_eq!(translate_c_command(Some("AM"), None, None).unwrap().encode(), Some(0b1110000000101000))
         }
 
         // test GENERAL-Translation works
         #[test]
         fn translates_c_instruction_a() {
-            assert_eq!(translate_instruction(CCommand{dest: Some("AM"), comp: Some("D|A"), jmp: Some("JEQ")}), "1110010101101010");
+            let inst = translate_instruction(CCommand{dest: Some("AM"), comp: Some("D|A"), jmp: Some("JEQ")}, 1).unwrap();
+            assert_eq!(inst.encode(), Some(0b1110010101101010));
         }
         #[test]
         fn translates_c_instruction_b() {
-            assert_eq!(translate_instruction(CCommand{dest: Some("AMD"), comp: Some("D&M"), jmp: Some("null")}), "1111000000111000");
+            let inst = translate_instruction(CCommand{dest: Some("AMD"), comp: Some("D&M"), jmp: Some("null")}, 1).unwrap();
+            assert_eq!(inst.encode(), Some(0b1111000000111000));
         }
         #[test]
         fn translates_a_instruction() {
-            assert_eq!(translate_instruction(ACommand("9")), "0000000000001001");
+            assert_eq!(translate_instruction(ACommand("9"), 1).unwrap().encode(), Some(0b0000000000001001));
+        }
+
+        // test invalid fields
+        #[test]
+        fn unknown_comp_is_reported() {
+            assert_eq!(
+                translate_instruction(CCommand{dest: Some("D"), comp: Some("Q+1"), jmp: None}, 3),
+                Err(AsmError::UnknownComp { line: 3, text: "D=Q+1".to_string(), comp: "Q+1".to_string() }));
+        }
+        #[test]
+        fn unknown_dest_is_reported() {
+            assert_eq!(
+                translate_instruction(CCommand{dest: Some("X"), comp: Some("0"), jmp: None}, 4),
+                Err(AsmError::UnknownDest { line: 4, text: "X=0".to_string(), dest: "X".to_string() }));
+        }
+        #[test]
+        fn unknown_jmp_is_reported() {
+            assert_eq!(
+                translate_instruction(CCommand{dest: None, comp: Some("0"), jmp: Some("JMPX")}, 5),
+                Err(AsmError::UnknownJump { line: 5, text: "0;JMPX".to_string(), jmp: "JMPX".to_string() }));
+        }
+        #[test]
+        fn gets_dest_in_any_order() {
+            assert_eq!(get_dest("DM"), Some(0b011));
+            assert_eq!(get_dest("DMA"), Some(0b111));
+            assert_eq!(get_dest("MM"), None);
+        }
+        #[test]
+        fn gets_spaced_comp() {
+            assert_eq!(get_comp("M - 1"), Some(0b1110010));
+            assert_eq!(get_comp("D + M"), Some(0b1000010));
+            assert_eq!(get_comp("M + D"), None);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,116 @@
+//! Debug output of an `Assembly`: a listing, that shows which ROM address
+//! and word every source line became, a symbol map with the
+//! addresses of all labels (ROM) and variables (RAM), and a source map
+//! from the ROM addresses back to the source lines.
+
+use crate::assembler::Assembly;
+use crate::instruction::to_binary_string;
+use std::collections::HashMap;
+
+/// Writes every source line with the ROM address and the word it has been assembled into.
+/// Lines without a word (labels, comments, empty lines) keep empty columns,
+/// so the source stays readable as a whole.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::assembler::assemble_with_symbols;
+/// use hack_assembler::listing::write_listing;
+///
+/// let asm = "(END)\n@END\n0;JMP";
+/// let listing = write_listing(asm, &assemble_with_symbols(asm).unwrap());
+/// assert_eq!(listing, "
+///                        (END)
+/// 00000 0000000000000000 @END
+/// 00001 1110101010000111 0;JMP".trim_start_matches('\n'));
+/// ```
+pub fn write_listing(asm_file: &str, assembly: &Assembly) -> String {
+    let addresses: HashMap<usize, usize> = assembly
+        .source_lines
+        .iter()
+        .enumerate()
+        .map(|(address, line)| (*line, address))
+        .collect();
+
+    asm_file
+        .split('\n')
+        .enumerate()
+        .map(|(idx, source)| match addresses.get(&(idx + 1)) {
+            Some(address) => format!("{:05} {} {}", address, to_binary_string(assembly.program[*address]), source),
+            None => format!("{:22} {}", "", source),
+        })
+        .map(|line| line.trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Writes one symbol per line as `<memory> <address> <name>`.
+/// Labels (`ROM`) come first, then the variables (`RAM`), both ordered by address.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::assembler::assemble_with_symbols;
+/// use hack_assembler::listing::write_symbol_map;
+///
+/// let assembly = assemble_with_symbols("@i\nM=0\n(END)\n@END\n0;JMP").unwrap();
+/// assert_eq!(write_symbol_map(&assembly), "ROM 2 END\nRAM 16 i");
+/// ```
+pub fn write_symbol_map(assembly: &Assembly) -> String {
+    let mut labels = assembly.labels.clone();
+    labels.sort_by_key(|(_, address)| *address);
+    let mut variables = assembly.variables.clone();
+    variables.sort_by_key(|(_, address)| *address);
+
+    labels
+        .iter()
+        .map(|(name, address)| format!("ROM {} {}", address, name))
+        .chain(variables.iter().map(|(name, address)| format!("RAM {} {}", address, name)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Writes the source map of the program: one `<address> <asm_file_name>:<line>` per word.
+/// It has the format of the source maps of the vm-translator and the jack-compiler
+/// (with ROM addresses instead of lines), so addresses can be traced back to Jack.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::assembler::assemble_with_symbols;
+/// use hack_assembler::listing::write_source_map;
+///
+/// let assembly = assemble_with_symbols("// loop\n(END)\n@END\n0;JMP").unwrap();
+/// assert_eq!(write_source_map(&assembly, "Prog.asm"), "0 Prog.asm:3\n1 Prog.asm:4");
+/// ```
+pub fn write_source_map(assembly: &Assembly, asm_file_name: &str) -> String {
+    assembly
+        .source_lines
+        .iter()
+        .enumerate()
+        .map(|(address, line)| format!("{} {}:{}", address, asm_file_name, line))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_with_symbols;
+
+    #[test]
+    fn listing_keeps_comments_and_empty_lines() {
+        let asm = "// sets D\n\n@2\nD=A";
+        let listing = write_listing(asm, &assemble_with_symbols(asm).unwrap());
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].ends_with(" // sets D"));
+        assert_eq!(lines[1], "");
+        assert_eq!(lines[2], "00000 0000000000000010 @2");
+        assert_eq!(lines[3], "00001 1110110000010000 D=A");
+    }
+    #[test]
+    fn symbol_map_orders_by_address() {
+        let asm = "@b\n@a\n(SECOND)\n@SECOND\n(FIRST)\n@FIRST";
+        let mut assembly = assemble_with_symbols(asm).unwrap();
+        assembly.labels.reverse();
+        assert_eq!(write_symbol_map(&assembly), "ROM 2 SECOND\nROM 3 FIRST\nRAM 16 b\nRAM 17 a");
+    }
+}
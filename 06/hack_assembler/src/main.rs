@@ -1,111 +1,116 @@
 extern crate hack_assembler;
 
-use hack_assembler::tables::*;
-use hack_assembler::encoder::{translate_instruction, get_bin_value};
-use hack_assembler::parser::{clean_line, get_command_fields}
-;
-use std::collections::HashMap;
+use hack_assembler::assembler::assemble_with_symbols;
+use hack_assembler::disassembler::{ disassemble, parse_hack_file, Options };
+use hack_assembler::error::report;
+use hack_assembler::instruction::to_binary_string;
+use hack_assembler::listing::{ write_listing, write_source_map, write_symbol_map };
 use std::fs::{self, File};
 use std::io::prelude::*;
+use std::path::Path;
 use std::process;
 use std::env;
 
 fn main() {
 
     // Get's input file and name of output file from user
-    // via command-line arguments.
-    fn read_command_line() -> (String, String) {
-        let args: Vec<_> = env::args().collect();
-        if args.len() < 3 {
+    // via command-line arguments. Arguments starting with "--" are flags.
+    fn read_command_line() -> (String, String, Vec<String>) {
+        let known_flags = ["--listing", "--symbol-map", "--source-map", "--disassemble", "--labels", "--symbols", "--vm-pointers"];
+        let (flags, args): (Vec<String>, Vec<String>) = env::args().skip(1).partition(|arg| arg.starts_with("--"));
+        if let Some(flag) = flags.iter().find(|flag| !known_flags.contains(&flag.as_str())) {
+            println!("Unknown flag '{}'", flag);
+            process::exit(1);
+        }
+        if args.len() < 2 {
             println!("Please enter a source file and a destination file.
-EXAMPLE: 'cargo run source.txt dest.hack'");
+EXAMPLE: 'cargo run source.txt dest.hack'
+Add '--listing', '--symbol-map' and/or '--source-map' to also write 'dest.lst', 'dest.sym' and 'dest.hack.map'.
+To turn a .hack file back into assembly use:
+'cargo run -- --disassemble [--labels] [--symbols] [--vm-pointers] source.hack dest.asm'");
             process::exit(1);
         }
-        (args[1].to_string(), args[2].to_string())
+        (args[0].to_string(), args[1].to_string(), flags)
     }
 
+    // Writes the lines of the result into the destination file.
+    fn write_file(dest_file: &str, lines: &[String]) {
+        // Create the ouput file.
+        let mut file = match File::create(dest_file) {
+            Err(m) => panic!("Could not create file because: {}", m),
+            Ok(file) => file,
+        };
+
+        // Concat all lines with a new-line.
+        let file_content_str = lines.join("\n");
+
+        // Write the final new-line-string to the file.
+        match file.write_all(file_content_str.as_bytes()) {
+            Err(m) => panic!("Coulnd not write to file because: {}", m),
+            Ok(_) => println!("File '{}' has been created successfully.", dest_file),
+        }
+    }
 
     // The work-horse of the program
     // Builds and writes the final binary-file.
-    fn build_pure_file<'a>() {
-
-        let (source_file, dest_file) = read_command_line();
+    // On request the listing, the symbol map and the source map are written next to it.
+    fn build_pure_file(source_file: &str, dest_file: &str, listing: bool, symbol_map: bool, source_map: bool) {
         let asm_file_string = fs::read_to_string(source_file).unwrap();
 
-        // Brings the SmybolTable with all predefined (HACK-specified)
-        // symbols into scope.
-        let mut symbols = SymbolsTable{symbols: HashMap::new()};
-        symbols.load_predefined_symbols();
-
-        // First-pass:
-        // Goes through every line of the input file and keeps
-        // relevant instruction (new-/empty-lines and comments are ignored).
-        // Also marks every instruction with the correct CommandType::Variant.
-        let mut first_pass = Vec::new();
-        for line in asm_file_string.split("\n") {
-            let cleaned = clean_line(line);
-            if !cleaned.is_empty() {
-                let command = get_command_fields(&cleaned);
-                let binary_or_label = translate_instruction(command);
-                first_pass.push(binary_or_label)
-            }
-        }
-        
-        // Second-pass:
-        // The counter keeps track of the following line-instruction. Because
-        // thats the value a label should have if it's not yet in the symbol-table.
-        let mut line_count = 0u32;
-        let second_pass: Vec<String> = first_pass.iter().fold(Vec::new(), |mut acc, inst| {
-            match &inst[0..1] {
-                "#" => {
-                    symbols.symbols.entry(&inst[1..]).or_insert(line_count);
-                    acc
-                },
-                _ => {
-                    line_count += 1;
-                    acc.push(inst.to_string());
-                    acc
-                },
-            }
-        });
-
+        // Runs all passes. If any instruction could not be assembled,
+        // all errors of the file get reported and no file is written.
+        let assembly = match assemble_with_symbols(&asm_file_string) {
+            Ok(assembly) => assembly,
+            Err(errors) => {
+                println!("Could not assemble '{}':\n{}", source_file, report(&errors));
+                process::exit(1);
+            },
+        };
 
-        // Third-pass
-        // Finally resolve all variables. Labels have been dealt with the pass before.
-        let mut address_16_plus = 16;
-        let third_pass: Vec<String> = second_pass.iter().map(|inst| {
-            match &inst[0..1] {
-                "*" => {
-                    if symbols.symbols.contains_key(&inst[1..]) {
-                        let a_inst = format!("{}", symbols.symbols[&inst[1..]]);
-                        get_bin_value(&a_inst)
-                    } else {
-                        symbols.symbols.insert(&inst[1..], address_16_plus);
-                        address_16_plus += 1;
-                        let symbol = format!("{}", symbols.symbols[&inst[1..]]);
-                        get_bin_value(&symbol)
-                    }
-                },
-                _ => inst.to_string(),
-            }
-        }).collect();
+        let binary_lines: Vec<String> = assembly.program.iter().cloned().map(to_binary_string).collect();
+        write_file(dest_file, &binary_lines);
 
-        // Create the ouput file.
-        let mut hack_file = match File::create(dest_file) {
-            Err(m) => panic!("Could not create file because: {}", m),
-            Ok(file) => file,
-        };
+        if listing {
+            let listing_file = Path::new(dest_file).with_extension("lst");
+            write_file(&listing_file.to_string_lossy(), &[write_listing(&asm_file_string, &assembly)]);
+        }
+        if symbol_map {
+            let symbol_file = Path::new(dest_file).with_extension("sym");
+            write_file(&symbol_file.to_string_lossy(), &[write_symbol_map(&assembly)]);
+        }
+        if source_map {
+            let map_file = Path::new(dest_file).with_extension("hack.map");
+            let asm_file_name = Path::new(source_file).file_name().unwrap_or_default().to_string_lossy();
+            write_file(&map_file.to_string_lossy(), &[write_source_map(&assembly, &asm_file_name)]);
+        }
+    }
 
-        // Concat all binary values with a new-line.
-        let file_content_str = third_pass.join("\n");
+    // Turns a .hack-file back into an assembly file.
+    fn build_asm_file(source_file: &str, dest_file: &str, options: Options) {
+        let hack_file_string = fs::read_to_string(source_file).unwrap();
 
-        // Write the final binray-new-line-string to the file.
-        match hack_file.write_all(file_content_str.as_bytes()) {
-            Err(m) => panic!("Coulnd not write to file because: {}", m),
-            Ok(_) => println!("HackFile has been created successfully."),
+        let assembly = parse_hack_file(&hack_file_string).and_then(|program| disassemble(&program, options));
+        match assembly {
+            Ok(lines) => write_file(dest_file, &lines),
+            Err(error) => {
+                println!("Could not disassemble '{}':\n{}", source_file, error);
+                process::exit(1);
+            },
         }
     }
-    
+
+    let (source_file, dest_file, flags) = read_command_line();
+    let has_flag = |flag: &str| flags.iter().any(|f| f == flag);
+
     // Start the readFile-translation-write-file process.
-    build_pure_file();
-}
\ No newline at end of file
+    if has_flag("--disassemble") {
+        let options = Options {
+            labels: has_flag("--labels"),
+            symbols: has_flag("--symbols"),
+            vm_pointers: has_flag("--vm-pointers"),
+        };
+        build_asm_file(&source_file, &dest_file, options);
+    } else {
+        build_pure_file(&source_file, &dest_file, has_flag("--listing"), has_flag("--symbol-map"), has_flag("--source-map"));
+    }
+}
@@ -4,7 +4,9 @@
 
 extern crate regex;
 
+use crate::error::AsmError;
 use regex::{ Regex, Captures };
+use std::fmt;
 
 /// The `CommandType`has three different variants.
 /// One for each possible HACK instruction (A-Instruction, C-Instruction, L-Instruction)
@@ -17,6 +19,26 @@ pub enum CommandType<'a> {
     LCommand(&'a str),
 }
 
+/// Writes the command back in it's assembly notation (e.g. for error messages).
+impl<'a> fmt::Display for CommandType<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandType::ACommand(value) => write!(f, "@{}", value),
+            CommandType::LCommand(label) => write!(f, "({})", label),
+            CommandType::CCommand{dest, comp, jmp} => {
+                if let Some(dest) = dest {
+                    write!(f, "{}=", dest)?;
+                }
+                write!(f, "{}", comp.unwrap_or(""))?;
+                match jmp {
+                    Some(jmp) => write!(f, ";{}", jmp),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+}
+
 /// Cleanes line, so that only instruction gets kept.
 ///
 /// # Examples
@@ -37,23 +59,69 @@ pub fn clean_line(line: &str) -> &str {
 /// based on the instruction it receivess. Ideally the instruction has no
 /// white space or comment. So it is useful to first use `clean_line` on the
 /// instruction befor it get's passed to get_command_fields.
+/// The `line` is only used to locate an `AsmError`, if the instruction is malformed.
 ///
 /// # Examples
 /// ```
 /// use hack_assembler::parser::{CommandType, get_command_fields};
 ///
 /// let instruction = "@100";
-/// let command = get_command_fields(&instruction);
-/// assert_eq!(command, CommandType::ACommand("100"));
-///
-pub fn get_command_fields(instruction: &str) -> CommandType {
-    let mut inst_chars = instruction.chars();
+/// let command = get_command_fields(&instruction, 1);
+/// assert_eq!(command, Ok(CommandType::ACommand("100")));
+/// assert!(get_command_fields("@40000", 1).is_err());
+/// assert!(get_command_fields("(LOOP", 1).is_err());
+/// ```
+pub fn get_command_fields(instruction: &str, line: usize) -> Result<CommandType<'_>, AsmError> {
     let len = instruction.len();
 
-    match inst_chars.next().unwrap() {
-        '@' => CommandType::ACommand(&instruction[1..]),
-        '(' => CommandType::LCommand(&instruction[1..len-1]),
-        _ => get_ccom_fields(instruction),
+    match instruction.chars().next() {
+        Some('@') => get_acom_field(instruction, line),
+        Some('(') => {
+            if len > 2 && instruction.ends_with(')') && is_symbol(&instruction[1..len-1]) {
+                Ok(CommandType::LCommand(&instruction[1..len-1]))
+            } else {
+                Err(AsmError::MalformedLabel { line, text: instruction.to_string() })
+            }
+        },
+        Some(_) => get_ccom_fields(instruction, line),
+        None => Err(AsmError::MalformedInstruction { line, text: instruction.to_string() }),
+    }
+}
+
+// An A-Instruction is either a constant (0..=32767) or a symbol.
+fn get_acom_field(instruction: &str, line: usize) -> Result<CommandType<'_>, AsmError> {
+    let value = &instruction[1..];
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        return match value.parse::<u16>() {
+            Ok(constant) if constant <= 32767 => Ok(CommandType::ACommand(value)),
+            _ => Err(AsmError::ConstantOutOfRange { line, text: instruction.to_string() }),
+        };
+    }
+    if is_symbol(value) {
+        Ok(CommandType::ACommand(value))
+    } else {
+        Err(AsmError::MalformedInstruction { line, text: instruction.to_string() })
+    }
+}
+
+/// A symbol is a sequence of letters, digits, `_`, `.`, `$` and `:`,
+/// which does not start with a digit.
+///
+/// # Examples
+/// ```
+/// use hack_assembler::parser::is_symbol;
+///
+/// assert!(is_symbol("ponggame.newinstance"));
+/// assert!(is_symbol("bat.move$if_end0"));
+/// assert!(!is_symbol("1abc"));
+/// assert!(!is_symbol("my label"));
+/// ```
+pub fn is_symbol(symbol: &str) -> bool {
+    match symbol.chars().next() {
+        Some(first) if !first.is_ascii_digit() => {
+            symbol.chars().all(|c| c.is_ascii_alphanumeric() || "_.$:".contains(c))
+        },
+        _ => false,
     }
 }
 
@@ -61,35 +129,37 @@ pub fn get_command_fields(instruction: &str) -> CommandType {
 /// Therefore it must be a C-Command.
 /// The function populates the CCommand Variant by
 /// regexing through the cleaned line.
+/// Whether the fields are valid HACK mnemonics is checked by the `encoder`.
 ///
 /// #Examples
 ///
 ///```
 /// use hack_assembler::parser::{get_ccom_fields, CommandType};
 ///
-/// assert_eq!(get_ccom_fields("A=M+1; JGT"), CommandType::CCommand{dest: Some("A"), comp: Some("M+1"), jmp: Some("JGT")});
-/// assert_eq!(get_ccom_fields("D-1"), CommandType::CCommand{dest: None, comp: Some("D-1"), jmp: None});
-/// 
-pub fn get_ccom_fields(instruction: &str) -> CommandType {
-    
-    let c_com_regex = Regex::new(r"((?P<dest>^[DMA]{1,3}|null)=)?(?P<comp>[DMA01!&|+-]{1,3})(;\s*?(?P<jmp>[A-Z]{1,3}|null))?").unwrap();
-    let fields = c_com_regex.captures(instruction).unwrap();
-
-    CommandType::CCommand {
+/// assert_eq!(get_ccom_fields("A=M+1; JGT", 1), Ok(CommandType::CCommand{dest: Some("A"), comp: Some("M+1"), jmp: Some("JGT")}));
+/// assert_eq!(get_ccom_fields("D-1", 1), Ok(CommandType::CCommand{dest: None, comp: Some("D-1"), jmp: None}));
+/// assert!(get_ccom_fields("D=M=1", 1).is_err());
+/// ```
+pub fn get_ccom_fields(instruction: &str, line: usize) -> Result<CommandType<'_>, AsmError> {
+
+    let c_com_regex = Regex::new(r"^(?:(?P<dest>[^=;]+?)\s*=)?\s*(?P<comp>[^=;]+?)\s*(?:;\s*(?P<jmp>[^=;]+?))?\s*$").unwrap();
+    let fields = match c_com_regex.captures(instruction) {
+        Some(fields) => fields,
+        None => return Err(AsmError::MalformedInstruction { line, text: instruction.to_string() }),
+    };
+
+    Ok(CommandType::CCommand {
         dest: unpack_match("dest", &fields),
         comp: unpack_match("comp", &fields),
         jmp: unpack_match("jmp", &fields),
-    }
+    })
 }
 
 /// Helper (used by `parser::get_ccom_field` to unpack the C-Instruction-field
 /// using regex-name-matches.
 ///
 fn unpack_match<'a>(name: &str, fields: &Captures<'a>) -> Option<&'a str> {
-    match fields.name(name) {
-        Some(field) => Some(field.as_str()),
-        _ => None,
-    }
+    fields.name(name).map(|field| field.as_str())
 }
 
 #[cfg(test)]
@@ -140,70 +210,111 @@ mod tests {
     // TESTS for get A-Command
     #[test]
     fn finds_value() {
-        assert_eq!(get_command_fields("@5"), CommandType::ACommand("5"));
+        assert_eq!(get_command_fields("@5", 1), Ok(CommandType::ACommand("5")));
     }
     #[test]
     fn finds_register() {
-        assert_eq!(get_command_fields("@R0"), CommandType::ACommand("R0"));
+        assert_eq!(get_command_fields("@R0", 1), Ok(CommandType::ACommand("R0")));
     }
     #[test]
     fn gets_complex_a_command_field() {
-        assert_eq!(get_command_fields("@ponggame.newinstance"), CommandType::ACommand("ponggame.newinstance"));;
+        assert_eq!(get_command_fields("@ponggame.newinstance", 1), Ok(CommandType::ACommand("ponggame.newinstance")));
     }
 
     // TESTS for get L-Command
     #[test]
     fn gets_complex_l_command_field() {
-        assert_eq!(get_command_fields("(bat.move$if_end0)"), CommandType::LCommand("bat.move$if_end0"));;
+        assert_eq!(get_command_fields("(bat.move$if_end0)", 1), Ok(CommandType::LCommand("bat.move$if_end0")));
     }
 
     // TESTS for get C-Command
     #[test]
     fn get_dest_comp_and_jmp() {
         assert_eq!(
-            get_ccom_fields("D=M; JNE"),
-            CommandType::CCommand{dest: Some("D"), comp: Some("M"), jmp: Some("JNE")});
+            get_ccom_fields("D=M; JNE", 1),
+            Ok(CommandType::CCommand{dest: Some("D"), comp: Some("M"), jmp: Some("JNE")}));
     }
     #[test]
     fn get_dest_and_comp() {
         assert_eq!(
-            get_ccom_fields("A=A-D"),
-            CommandType::CCommand{dest: Some("A"), comp: Some("A-D"), jmp: None});
+            get_ccom_fields("A=A-D", 1),
+            Ok(CommandType::CCommand{dest: Some("A"), comp: Some("A-D"), jmp: None}));
     }
     #[test]
     fn get_comp() {
         assert_eq!(
-            get_ccom_fields("D+1"),
-            CommandType::CCommand{dest: None, comp: Some("D+1"), jmp: None});
+            get_ccom_fields("D+1", 1),
+            Ok(CommandType::CCommand{dest: None, comp: Some("D+1"), jmp: None}));
     }
     #[test]
     fn accounts_for_null_as_dest() {
         assert_eq!(
-            get_ccom_fields("null=D+M; JNE"),
-            CommandType::CCommand{dest: Some("null"), comp: Some("D+M"), jmp: Some("JNE")});
+            get_ccom_fields("null=D+M; JNE", 1),
+            Ok(CommandType::CCommand{dest: Some("null"), comp: Some("D+M"), jmp: Some("JNE")}));
     }
     #[test]
     fn accounts_for_null_as_jmp() {
         assert_eq!(
-            get_ccom_fields("D+M; null"),
-            CommandType::CCommand{dest: None, comp: Some("D+M"), jmp: Some("null")});
+            get_ccom_fields("D+M; null", 1),
+            Ok(CommandType::CCommand{dest: None, comp: Some("D+M"), jmp: Some("null")}));
     }
     #[test]
     fn get_multi_dest_comp_jmp() {
         assert_eq!(
-            get_ccom_fields("DM=A+1; JMP"),
-            CommandType::CCommand{dest: Some("DM"), comp: Some("A+1"), jmp: Some("JMP")});
+            get_ccom_fields("DM=A+1; JMP", 1),
+            Ok(CommandType::CCommand{dest: Some("DM"), comp: Some("A+1"), jmp: Some("JMP")}));
     }
     #[test]
     fn get_multi_dest_no_jmp() {
         assert_eq!(
-            get_ccom_fields("DM=A+1"),
-            CommandType::CCommand{dest: Some("DM"), comp: Some("A+1"), jmp: None});
+            get_ccom_fields("DM=A+1", 1),
+            Ok(CommandType::CCommand{dest: Some("DM"), comp: Some("A+1"), jmp: None}));
     }
     #[test]
     fn get_negotiation_of_m_no_jmp() {
         assert_eq!(
-            get_ccom_fields("M=!M"),
-            CommandType::CCommand{dest: Some("M"), comp: Some("!M"), jmp: None});
+            get_ccom_fields("M=!M", 1),
+            Ok(CommandType::CCommand{dest: Some("M"), comp: Some("!M"), jmp: None}));
     }
-}
\ No newline at end of file
+
+    // TESTS for malformed instructions
+    #[test]
+    fn accepts_spaces_around_fields() {
+        assert_eq!(
+            get_ccom_fields("M = M + D", 1),
+            Ok(CommandType::CCommand{dest: Some("M"), comp: Some("M + D"), jmp: None}));
+    }
+    #[test]
+    fn rejects_empty_a_command() {
+        assert_eq!(get_command_fields("@", 4), Err(AsmError::MalformedInstruction { line: 4, text: "@".to_string() }));
+    }
+    #[test]
+    fn rejects_symbol_starting_with_digit() {
+        assert_eq!(get_command_fields("@1abc", 2), Err(AsmError::MalformedInstruction { line: 2, text: "@1abc".to_string() }));
+    }
+    #[test]
+    fn rejects_too_big_constant() {
+        assert_eq!(get_command_fields("@32768", 3), Err(AsmError::ConstantOutOfRange { line: 3, text: "@32768".to_string() }));
+    }
+    #[test]
+    fn accepts_biggest_constant() {
+        assert_eq!(get_command_fields("@32767", 3), Ok(CommandType::ACommand("32767")));
+    }
+    #[test]
+    fn rejects_unclosed_label() {
+        assert_eq!(get_command_fields("(LOOP", 9), Err(AsmError::MalformedLabel { line: 9, text: "(LOOP".to_string() }));
+    }
+    #[test]
+    fn rejects_empty_label() {
+        assert_eq!(get_command_fields("()", 9), Err(AsmError::MalformedLabel { line: 9, text: "()".to_string() }));
+    }
+    #[test]
+    fn rejects_double_assignment() {
+        assert_eq!(get_ccom_fields("A=D=M", 5), Err(AsmError::MalformedInstruction { line: 5, text: "A=D=M".to_string() }));
+    }
+    #[test]
+    fn displays_c_command_as_assembly() {
+        let command = CommandType::CCommand{dest: Some("D"), comp: Some("D+1"), jmp: Some("JGT")};
+        assert_eq!(command.to_string(), "D=D+1;JGT");
+    }
+}
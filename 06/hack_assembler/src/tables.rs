@@ -143,7 +143,7 @@ pub fn get_comp_table_a<'a>() -> HashMap<&'a str, &'a str> {
     let mut comp_options = HashMap::new();
     comp_options.insert("M", "110000");
     comp_options.insert("!M", "110001");
-    comp_options.insert("-M", "110001");
+    comp_options.insert("-M", "110011");
     comp_options.insert("M+1", "110111");
     comp_options.insert("M-1", "110010");
     comp_options.insert("D+M", "000010");
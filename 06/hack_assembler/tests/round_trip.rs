@@ -0,0 +1,24 @@
+use hack_assembler::assembler::assemble;
+use hack_assembler::disassembler::{ disassemble, parse_hack_file, Options };
+use std::fs;
+
+// Disassembles a .hack file of the 04 project and assembles the result again.
+fn round_trip(path: &str, options: Options) {
+    let hack_file = fs::read_to_string(format!("../../04/{}", path)).expect("could not read hack-file in test");
+    let program = parse_hack_file(&hack_file).unwrap();
+    let assembly = disassemble(&program, options).unwrap().join("\n");
+    assert_eq!(assemble(&assembly).unwrap(), program);
+}
+
+#[test]
+fn fill_round_trips() {
+    round_trip("fill/Fill.hack", Options::default());
+}
+#[test]
+fn fill_round_trips_with_labels_and_symbols() {
+    round_trip("fill/Fill.hack", Options { labels: true, symbols: true, vm_pointers: false });
+}
+#[test]
+fn mult_round_trips_with_labels_and_symbols() {
+    round_trip("mult/mult.hack", Options { labels: true, symbols: true, vm_pointers: true });
+}
@@ -1,2 +0,0 @@
-pub mod parser;
-pub mod code_writer;
\ No newline at end of file
@@ -0,0 +1,33 @@
+use std::path::{ Path, PathBuf };
+
+/// Returns the path of the asm-file to write: a file `Foo.vm` becomes `Foo.asm`,
+/// a directory `Foo/` becomes `Foo/Foo.asm`.
+pub fn path_builder(path: &Path) -> PathBuf {
+    (match path.is_dir() {
+        true  => {
+            let file = path.file_name().unwrap();
+            path.join(file)
+        },
+        false => path.to_path_buf(),
+    }).with_extension("asm")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_file_path() {
+        let input_file = Path::new("../my/great/path/with/singleFile.vm");
+        let output_file = Path::new("../my/great/path/with/singleFile.asm").to_path_buf();
+
+        assert_eq!(path_builder(input_file), output_file);
+    }
+    #[test]
+    fn handle_dir_path() {
+        let input_dir = Path::new("../../07/StackArithmetic/SimpleAdd");
+        let output_file = Path::new("../../07/StackArithmetic/SimpleAdd/SimpleAdd.asm").to_path_buf();
+
+        assert_eq!(path_builder(input_dir), output_file);
+    }
+}
@@ -0,0 +1,695 @@
+//! Runs VM programs directly on the parsed commands, without translating them.
+//! The memory is the one of the HACK platform: `SP`, `LCL`, `ARG`, `THIS` and `THAT`
+//! in RAM 0-4, `temp` in 5-12, the statics from 16 and the stack from 256.
+//! A `call` writes the same frame into the RAM as the translated code does,
+//! so a program behaves exactly like it's assembly.
+//! A call of an OS function, that the program does not define, is served by
+//! the native OS of `os` (so `12/` only needs to be loaded to test it's own classes).
+
+mod font;
+pub mod os;
+
+use crate::error::VmError;
+use crate::translator::labels::Labels;
+use crate::translator::parser::{ ArithOp, BranchKind, Com, Segment };
+use crate::{ file_stem, io_error, parse_lines, vm_files };
+use os::{ Builtin, Native, Os, KBD, SCREEN, SCREEN_SIZE };
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::read_to_string;
+use std::path::{ Path, PathBuf };
+
+pub const RAM_SIZE: usize = 32768;
+pub const SP: usize = 0;
+pub const LCL: usize = 1;
+pub const ARG: usize = 2;
+pub const THIS: usize = 3;
+pub const THAT: usize = 4;
+pub const TEMP: usize = 5;
+pub const STATIC: usize = 16;
+pub const STACK: usize = 256;
+
+/// Why `Vm::run` stopped executing.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stop {
+    /// The program ran past it's last command (or `Sys.init` returned).
+    Halted,
+    /// The program reached a `label END goto END` loop, so nothing will change anymore.
+    TightLoop,
+    /// The given number of steps has been executed.
+    StepLimit,
+    /// The OS waits for a key (see `Vm::press_key`).
+    WaitingForKey,
+}
+
+// A command with everything resolved the emulator needs to execute it.
+// The index of a `static` is replaced by it's address.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Push(Segment, u32),
+    Pop(Segment, u32),
+    Arith(ArithOp),
+    Label,
+    Goto(usize),
+    IfGoto(usize),
+    Function(u32),
+    Call(usize, u32),
+    Native(Builtin),
+    Return,
+}
+
+#[derive(Debug)]
+struct Instruction {
+    command: Com,
+    op: Op,
+    file: usize,
+    line: usize,
+    // The function the command belongs to (the index of it's `function` command).
+    function: Option<usize>,
+}
+
+/// A VM program and the RAM it runs on. Every `step` executes one command.
+#[derive(Debug)]
+pub struct Vm {
+    program: Vec<Instruction>,
+    files: Vec<String>,
+    functions: HashMap<String, usize>,
+    statics: HashMap<(usize, u32), usize>,
+    ram: Vec<i16>,
+    pc: usize,
+    // The `function` commands of the called functions (the innermost is last).
+    call_stack: Vec<usize>,
+    steps: u64,
+    os: Os,
+    waiting: bool,
+}
+
+impl Vm {
+    /// Builds the program of vm-files, given as `(file_stem, vm_code)`.
+    /// If the program defines `Sys.init`, it is called first (like the bootstrap code does).
+    /// Without `Sys.init` the native OS calls `Main.main` (if there is one),
+    /// otherwise the program starts with the first command of the first file.
+    /// The errors of all files are collected.
+    ///
+    /// # Examples
+    /// ```
+    /// use vm_translator::emulator::{Stop, Vm};
+    ///
+    /// let mut vm = Vm::new(&[("Main", "push constant 7\npush constant 8\nadd")]).unwrap();
+    /// assert_eq!(vm.run(100), Ok(Stop::Halted));
+    /// assert_eq!(vm.stack(), &[15]);
+    /// ```
+    pub fn new(sources: &[(&str, &str)]) -> Result<Vm, Vec<VmError>> {
+        let mut program = Vec::new();
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        for (file, (stem, vm_code)) in sources.iter().enumerate() {
+            files.push(stem.to_string());
+            match parse_lines(vm_code, stem) {
+                Ok(commands) => program.extend(commands.into_iter().map(|(line, command)| Instruction {
+                    command,
+                    op: Op::Label,
+                    file,
+                    line,
+                    function: None,
+                })),
+                Err(file_errors) => errors.extend(file_errors),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut vm = Vm {
+            program,
+            files,
+            functions: HashMap::new(),
+            statics: HashMap::new(),
+            ram: vec![0; RAM_SIZE],
+            pc: 0,
+            call_stack: Vec::new(),
+            steps: 0,
+            os: Os::new(),
+            waiting: false,
+        };
+        vm.link()?;
+        vm.reset();
+        Ok(vm)
+    }
+
+    /// Reads and loads a single vm-file.
+    pub fn from_file(path: &Path) -> Result<Vm, Vec<VmError>> {
+        Vm::from_files(&[path.to_path_buf()])
+    }
+
+    /// Loads all vm-files of a directory (in alphabetical order) as one program.
+    pub fn from_dir(dir: &Path) -> Result<Vm, Vec<VmError>> {
+        let mut paths = vm_files(dir).map_err(|e| vec![io_error(dir, e)])?;
+        paths.sort();
+        Vm::from_files(&paths)
+    }
+
+    fn from_files(paths: &[PathBuf]) -> Result<Vm, Vec<VmError>> {
+        let mut sources = Vec::new();
+        for path in paths {
+            let vm_code = read_to_string(path).map_err(|e| vec![io_error(path, e)])?;
+            sources.push((file_stem(path), vm_code));
+        }
+        let sources: Vec<(&str, &str)> = sources.iter().map(|(stem, vm_code)| (stem.as_str(), vm_code.as_str())).collect();
+        Vm::new(&sources)
+    }
+
+    // Resolves functions, labels and statics into addresses.
+    fn link(&mut self) -> Result<(), Vec<VmError>> {
+        let mut errors = Vec::new();
+
+        // Labels are scoped like the translator does it, so `LOOP` in two functions are two labels.
+        let mut labels = HashMap::new();
+        let mut scoped = Vec::new();
+        let mut scope = Labels::new("");
+        let mut function = None;
+        for idx in 0..self.program.len() {
+            let instruction = &self.program[idx];
+            if idx == 0 || self.program[idx - 1].file != instruction.file {
+                scope = Labels::new(&self.files[instruction.file]);
+                function = None;
+            }
+            match &instruction.command {
+                Com::Function(name, _) => {
+                    scope.enter(name);
+                    function = Some(idx);
+                    if self.functions.insert(name.to_string(), idx).is_some() {
+                        errors.push(self.link_error(idx, "the function has already been defined"));
+                    }
+                },
+                Com::Label(label) if labels.insert(scope.user(label), idx).is_some() => {
+                    errors.push(self.link_error(idx, "the label has already been defined in this function"));
+                },
+                _ => {},
+            }
+            self.program[idx].function = function;
+            scoped.push(match &self.program[idx].command {
+                Com::Branch(_, label) => scope.user(label),
+                _ => String::new(),
+            });
+        }
+
+        for (idx, scoped_label) in scoped.iter().enumerate() {
+            let op = match &self.program[idx].command {
+                Com::Push(Segment::Static, index) | Com::Pop(Segment::Static, index) => {
+                    let next = STATIC + self.statics.len();
+                    let address = *self.statics.entry((self.program[idx].file, *index)).or_insert(next);
+                    if address >= STACK {
+                        errors.push(self.link_error(idx, "there are more statics than fit into RAM 16-255"));
+                    }
+                    match self.program[idx].command {
+                        Com::Push(..) => Op::Push(Segment::Static, address as u32),
+                        _ => Op::Pop(Segment::Static, address as u32),
+                    }
+                },
+                Com::Push(segment, index) => Op::Push(*segment, *index),
+                Com::Pop(segment, index) => Op::Pop(*segment, *index),
+                Com::Arith(op) => Op::Arith(*op),
+                Com::Label(_) | Com::Empty => Op::Label,
+                Com::Branch(kind, label) => match labels.get(scoped_label) {
+                    Some(target) if *kind == BranchKind::Goto => Op::Goto(*target),
+                    Some(target) => Op::IfGoto(*target),
+                    None => {
+                        errors.push(self.link_error(idx, &format!("the label '{}' does not exist", label)));
+                        Op::Label
+                    },
+                },
+                Com::Function(_, locals) => Op::Function(*locals),
+                Com::Call(name, args) => match (self.functions.get(name), Builtin::find(name)) {
+                    (Some(target), _) => Op::Call(*target, *args),
+                    (None, Some(builtin)) if builtin.args() == *args => Op::Native(builtin),
+                    (None, Some(builtin)) => {
+                        let message = format!("the OS function '{}' takes {} arguments", name, builtin.args());
+                        errors.push(self.link_error(idx, &message));
+                        Op::Label
+                    },
+                    (None, None) => {
+                        errors.push(self.link_error(idx, &format!("the function '{}' does not exist", name)));
+                        Op::Label
+                    },
+                },
+                Com::Return => Op::Return,
+            };
+            self.program[idx].op = op;
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Clears the RAM and starts the program again.
+    pub fn reset(&mut self) {
+        self.ram = vec![0; RAM_SIZE];
+        self.ram[SP] = STACK as i16;
+        self.pc = 0;
+        self.call_stack.clear();
+        self.steps = 0;
+        self.waiting = false;
+        self.os = Os::new();
+        let entry = match self.functions.get("Sys.init") {
+            Some(sys_init) => Some(*sys_init),
+            None => {
+                // The native `Sys.init`: initialize the OS and call `Main.main`.
+                self.os.init(&mut self.ram);
+                self.functions.get("Main.main").copied()
+            },
+        };
+        if let Some(entry) = entry {
+            // The frame of the bootstrap: returning from the entry ends the program.
+            let end = self.program.len() as i16;
+            self.ram[STACK] = end;
+            self.ram[SP] = STACK as i16 + 5;
+            self.ram[ARG] = STACK as i16;
+            self.ram[LCL] = self.ram[SP];
+            self.pc = entry;
+            self.call_stack.push(entry);
+        }
+    }
+
+    /// Runs until the program halts, a tight loop is detected or `max_steps` commands have been executed.
+    pub fn run(&mut self, max_steps: u64) -> Result<Stop, VmError> {
+        for _ in 0..max_steps {
+            if self.is_halted() {
+                return Ok(Stop::Halted);
+            }
+            if self.is_in_tight_loop() {
+                return Ok(Stop::TightLoop);
+            }
+            self.step()?;
+            if self.waiting {
+                return Ok(Stop::WaitingForKey);
+            }
+        }
+        if self.is_halted() { Ok(Stop::Halted) } else { Ok(Stop::StepLimit) }
+    }
+
+    /// Executes the command the pc points to (a halted program does not change anymore).
+    pub fn step(&mut self) -> Result<(), VmError> {
+        let instruction = match self.program.get(self.pc) {
+            Some(instruction) => instruction,
+            None => return Ok(()),
+        };
+        let op = instruction.op;
+        self.steps += 1;
+        match self.execute(op) {
+            Ok(next) => {
+                self.pc = next;
+                Ok(())
+            },
+            Err(message) => Err(self.runtime_error(self.pc, &message)),
+        }
+    }
+
+    // Executes a command and returns the pc of the next one.
+    fn execute(&mut self, op: Op) -> Result<usize, String> {
+        let mut next = self.pc + 1;
+        match op {
+            Op::Push(Segment::Constant, value) => self.push(value as i16)?,
+            Op::Push(segment, index) => {
+                let value = self.read(self.address(segment, index)?)?;
+                self.push(value)?;
+            },
+            Op::Pop(segment, index) => {
+                let value = self.pop()?;
+                self.write(self.address(segment, index)?, value)?;
+            },
+            Op::Arith(arith) => self.arithmetic(arith)?,
+            Op::Label => {},
+            Op::Goto(target) => next = target,
+            Op::IfGoto(target) => {
+                if self.pop()? != 0 {
+                    next = target;
+                }
+            },
+            Op::Function(locals) => {
+                for _ in 0..locals {
+                    self.push(0)?;
+                }
+            },
+            Op::Call(target, args) => {
+                self.push(next as i16)?;
+                for pointer in [LCL, ARG, THIS, THAT].iter() {
+                    self.push(self.ram[*pointer])?;
+                }
+                self.ram[ARG] = self.ram[SP] - 5 - args as i16;
+                self.ram[LCL] = self.ram[SP];
+                self.call_stack.push(target);
+                next = target;
+            },
+            Op::Native(builtin) => {
+                // The arguments stay on the stack until the builtin is done.
+                let sp = self.ram[SP] as i32;
+                let args = match usize::try_from(sp - builtin.args() as i32) {
+                    Ok(first) => self.ram[first..sp as usize].to_vec(),
+                    Err(_) => return Err(format!("stack underflow (SP is {})", sp)),
+                };
+                self.waiting = false;
+                match self.os.call(builtin, &args, &mut self.ram) {
+                    Native::Return(value) => {
+                        self.ram[SP] -= args.len() as i16;
+                        self.push(value)?;
+                    },
+                    Native::Block => {
+                        self.waiting = true;
+                        next = self.pc;
+                    },
+                    Native::Halt => {
+                        self.ram[SP] -= args.len() as i16;
+                        next = self.program.len();
+                    },
+                }
+            },
+            Op::Return => {
+                let frame = self.ram[LCL] as i32;
+                let return_address = self.read(frame - 5)?;
+                let value = self.pop()?;
+                self.write(self.ram[ARG] as i32, value)?;
+                self.ram[SP] = self.ram[ARG] + 1;
+                self.ram[THAT] = self.read(frame - 1)?;
+                self.ram[THIS] = self.read(frame - 2)?;
+                self.ram[ARG] = self.read(frame - 3)?;
+                self.ram[LCL] = self.read(frame - 4)?;
+                self.call_stack.pop();
+                next = return_address as u16 as usize;
+            },
+        }
+        Ok(next)
+    }
+
+    fn arithmetic(&mut self, op: ArithOp) -> Result<(), String> {
+        let y = self.pop()?;
+        let value = match op {
+            ArithOp::Neg => y.wrapping_neg(),
+            ArithOp::Not => !y,
+            _ => {
+                let x = self.pop()?;
+                match op {
+                    ArithOp::Add => x.wrapping_add(y),
+                    ArithOp::Sub => x.wrapping_sub(y),
+                    ArithOp::And => x & y,
+                    ArithOp::Or => x | y,
+                    ArithOp::Eq => -((x == y) as i16),
+                    ArithOp::Gt => -((x > y) as i16),
+                    _ => -((x < y) as i16),
+                }
+            },
+        };
+        self.push(value)
+    }
+
+    // The RAM address of a segment entry (statics are already resolved to their address).
+    fn address(&self, segment: Segment, index: u32) -> Result<i32, String> {
+        let base = match segment {
+            Segment::Local => self.ram[LCL] as i32,
+            Segment::Argument => self.ram[ARG] as i32,
+            Segment::This => self.ram[THIS] as i32,
+            Segment::That => self.ram[THAT] as i32,
+            Segment::Pointer => THIS as i32,
+            Segment::Temp => TEMP as i32,
+            Segment::Static => 0,
+            Segment::Constant => return Err("the constant segment has no address".to_string()),
+        };
+        Ok(base + index as i32)
+    }
+
+    fn read(&self, address: i32) -> Result<i16, String> {
+        match usize::try_from(address) {
+            Ok(address) if address < RAM_SIZE => Ok(self.ram[address]),
+            _ => Err(format!("address {} is outside of the RAM", address)),
+        }
+    }
+    fn write(&mut self, address: i32, value: i16) -> Result<(), String> {
+        match usize::try_from(address) {
+            Ok(address) if address < RAM_SIZE => {
+                self.ram[address] = value;
+                Ok(())
+            },
+            _ => Err(format!("address {} is outside of the RAM", address)),
+        }
+    }
+    // SP has to stay an address of the RAM (and an i16) after the push.
+    fn push(&mut self, value: i16) -> Result<(), String> {
+        let sp = self.ram[SP] as i32;
+        if sp + 1 >= RAM_SIZE as i32 {
+            return Err(format!("stack overflow (SP is {})", sp));
+        }
+        self.write(sp, value).map_err(|_| format!("stack overflow (SP is {})", sp))?;
+        self.ram[SP] += 1;
+        Ok(())
+    }
+    fn pop(&mut self) -> Result<i16, String> {
+        let sp = self.ram[SP] as i32 - 1;
+        let value = self.read(sp).map_err(|_| format!("stack underflow (SP is {})", sp + 1))?;
+        self.ram[SP] -= 1;
+        Ok(value)
+    }
+
+    // The canonical VM halt is `label END goto END`: a goto, that jumps
+    // (over nothing but labels) back to itself.
+    fn is_in_tight_loop(&self) -> bool {
+        match self.program.get(self.pc).map(|instruction| instruction.op) {
+            Some(Op::Goto(target)) if target <= self.pc => {
+                self.program[target..self.pc].iter().all(|instruction| matches!(instruction.op, Op::Label))
+            },
+            _ => false,
+        }
+    }
+
+    fn link_error(&self, idx: usize, message: &str) -> VmError {
+        let instruction = &self.program[idx];
+        VmError::Link {
+            file: format!("{}.vm", self.files[instruction.file]),
+            line: instruction.line,
+            text: instruction.command.to_string(),
+            message: message.to_string(),
+        }
+    }
+    fn runtime_error(&self, idx: usize, message: &str) -> VmError {
+        let instruction = &self.program[idx];
+        VmError::Runtime {
+            file: format!("{}.vm", self.files[instruction.file]),
+            line: instruction.line,
+            text: instruction.command.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn ram(&self, address: usize) -> i16 {
+        self.ram[address]
+    }
+    pub fn set_ram(&mut self, address: usize, value: i16) {
+        self.ram[address] = value;
+    }
+    /// The memory map of the screen (16384-24575), the lowest bit of a word is the leftmost pixel.
+    pub fn screen(&self) -> &[i16] {
+        &self.ram[SCREEN..SCREEN + SCREEN_SIZE]
+    }
+    /// Holds a key down (the keyboard codes of the HACK platform, 128 is a new line).
+    /// The OS takes the key, so every press is read once.
+    pub fn press_key(&mut self, key: i16) {
+        self.ram[KBD] = key;
+    }
+    pub fn release_key(&mut self) {
+        self.ram[KBD] = 0;
+    }
+    /// The values on the stack, the top is the last one.
+    pub fn stack(&self) -> &[i16] {
+        let sp = (self.ram[SP].max(0) as usize).clamp(STACK, RAM_SIZE);
+        &self.ram[STACK..sp]
+    }
+
+    /// The RAM address of `segment index` right now. `static` refers to the statics of the
+    /// file, that is executed at the moment (and only exists if the file uses it).
+    pub fn segment_address(&self, segment: Segment, index: u32) -> Option<usize> {
+        let address = match segment {
+            Segment::Static => *self.statics.get(&(self.program.get(self.pc)?.file, index))? as i32,
+            _ => self.address(segment, index).ok()?,
+        };
+        usize::try_from(address).ok().filter(|address| *address < RAM_SIZE)
+    }
+    /// The value of `segment index` right now (see `segment_address`).
+    ///
+    /// # Examples
+    /// ```
+    /// use vm_translator::emulator::Vm;
+    /// use vm_translator::translator::parser::Segment;
+    ///
+    /// let mut vm = Vm::new(&[("Main", "function Sys.init 2\npush constant 3\npop local 1\nlabel END\ngoto END")]).unwrap();
+    /// vm.run(100).unwrap();
+    /// assert_eq!(vm.segment(Segment::Local, 1), Some(3));
+    /// assert_eq!(vm.current_function(), Some("Sys.init"));
+    /// ```
+    pub fn segment(&self, segment: Segment, index: u32) -> Option<i16> {
+        match segment {
+            Segment::Constant => Some(index as i16),
+            _ => self.segment_address(segment, index).map(|address| self.ram[address]),
+        }
+    }
+
+    /// The index of the next command.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+    /// The next command, `None` if the program has halted.
+    pub fn current_command(&self) -> Option<&Com> {
+        self.program.get(self.pc).map(|instruction| &instruction.command)
+    }
+    /// The file (without `.vm`) and line of the next command.
+    pub fn current_location(&self) -> Option<(&str, usize)> {
+        self.program.get(self.pc).map(|instruction| (self.files[instruction.file].as_str(), instruction.line))
+    }
+    /// The function the next command belongs to.
+    pub fn current_function(&self) -> Option<&str> {
+        let function = self.program.get(self.pc)?.function?;
+        self.function_name(function)
+    }
+    /// The names of the called functions, the innermost call is last.
+    pub fn call_stack(&self) -> Vec<&str> {
+        self.call_stack.iter().filter_map(|function| self.function_name(*function)).collect()
+    }
+    fn function_name(&self, idx: usize) -> Option<&str> {
+        match &self.program[idx].command {
+            Com::Function(name, _) => Some(name),
+            _ => None,
+        }
+    }
+    /// The number of executed commands.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(vm_code: &str) -> Vm {
+        let mut vm = Vm::new(&[("Main", vm_code)]).unwrap();
+        vm.run(10_000).unwrap();
+        vm
+    }
+
+    #[test]
+    fn computes_arithmetic() {
+        let vm = run("push constant 5\nneg\npush constant 3\nsub\npush constant 1\npush constant 2\nlt\npush constant 0\nnot");
+        assert_eq!(vm.stack(), &[-8, -1, -1]);
+    }
+    #[test]
+    fn comparisons_are_signed() {
+        assert_eq!(run("push constant 0\nnot\npush constant 1\ngt").stack(), &[0]);
+    }
+    #[test]
+    fn calls_and_returns_with_a_frame() {
+        let mut vm = Vm::new(&[("Main", "function Sys.init 0
+            push constant 6
+            push constant 7
+            call Main.add 2
+            label END
+            goto END
+            function Main.add 1
+            push argument 0
+            push argument 1
+            add
+            return")]).unwrap();
+        assert_eq!(vm.run(1000), Ok(Stop::TightLoop));
+        // Behind the bootstrap frame (returning to the end of the 11 commands)
+        // the value replaces the arguments on the stack of Sys.init.
+        assert_eq!(vm.stack(), &[11, 0, 0, 0, 0, 13]);
+        assert_eq!(vm.ram(LCL), 261);
+        assert_eq!(vm.call_stack(), vec!["Sys.init"]);
+    }
+    #[test]
+    fn labels_are_scoped_by_function() {
+        let vm = Vm::new(&[("Main", "function Main.a 0\nlabel LOOP\nfunction Main.b 0\ngoto LOOP")]);
+        let errors = vm.unwrap_err();
+        assert_eq!(errors[0].location(), "Main.vm:4");
+    }
+    #[test]
+    fn reports_unknown_functions() {
+        assert_eq!(
+            Vm::new(&[("Main", "call Foo.bar 0")]).unwrap_err(),
+            vec![VmError::Link {
+                file: "Main.vm".to_string(),
+                line: 1,
+                text: "call Foo.bar 0".to_string(),
+                message: "the function 'Foo.bar' does not exist".to_string(),
+            }]);
+    }
+    #[test]
+    fn unbounded_recursion_overflows_the_stack() {
+        let mut vm = Vm::new(&[("Main", "function Main.main 0\ncall Main.main 0\nreturn")]).unwrap();
+        match vm.run(100_000) {
+            Err(VmError::Runtime { message, .. }) => assert_eq!(message, "stack overflow (SP is 32767)"),
+            stop => panic!("the recursion ended with {:?}", stop),
+        }
+    }
+    #[test]
+    fn statics_belong_to_their_file() {
+        let mut vm = Vm::new(&[("A", "push constant 1\npop static 0"), ("B", "push constant 2\npop static 0")]).unwrap();
+        vm.run(100).unwrap();
+        assert_eq!((vm.ram(16), vm.ram(17)), (1, 2));
+    }
+    #[test]
+    fn reports_memory_outside_of_the_ram() {
+        let mut vm = Vm::new(&[("Main", "push constant 0\nnot\npop pointer 1\npush that 0")]).unwrap();
+        match vm.run(100) {
+            Err(VmError::Runtime { line, message, .. }) => {
+                assert_eq!(line, 4);
+                assert_eq!(message, "address -1 is outside of the RAM");
+            },
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+    #[test]
+    fn os_functions_are_native() {
+        let vm = run("push constant 6\npush constant 7\ncall Math.multiply 2\npush constant 3\ncall Memory.alloc 1");
+        assert_eq!(vm.stack(), &[42, 16381]);
+        assert_eq!(vm.ram(16380), 3);
+    }
+    #[test]
+    fn defined_functions_replace_the_os() {
+        let vm = run("push constant 6\npush constant 7\ncall Math.multiply 2\nlabel END\ngoto END
+            function Math.multiply 0\npush constant 1\nreturn");
+        assert_eq!(vm.stack(), &[1]);
+    }
+    #[test]
+    fn os_functions_need_their_arguments() {
+        let errors = Vm::new(&[("Main", "call Math.multiply 1")]).unwrap_err();
+        assert_eq!(errors[0].to_string(), "Main.vm:1: the OS function 'Math.multiply' takes 2 arguments in 'call Math.multiply 1'");
+    }
+    #[test]
+    fn main_main_is_called_without_sys_init() {
+        let mut vm = Vm::new(&[("Main", "function Main.main 0
+            push constant 1
+            push constant 1
+            call Screen.drawPixel 2
+            call Output.println 0
+            push constant 0
+            return")]).unwrap();
+        assert_eq!(vm.run(1000), Ok(Stop::Halted));
+        assert_eq!(vm.screen()[32], 2);
+    }
+    #[test]
+    fn waits_for_keys() {
+        let mut vm = Vm::new(&[("Main", "function Main.main 0\ncall Keyboard.readChar 0\npop temp 0\ncall Sys.halt 0")]).unwrap();
+        assert_eq!(vm.run(1000), Ok(Stop::WaitingForKey));
+        vm.press_key(65);
+        assert_eq!(vm.run(1000), Ok(Stop::Halted));
+        assert_eq!(vm.ram(TEMP), 65);
+        // The character is echoed at the cursor.
+        assert_eq!(vm.screen()[0], 12);
+    }
+    #[test]
+    fn stops_at_the_step_limit() {
+        let mut vm = Vm::new(&[("Main", "label LOOP\npush constant 1\npop temp 0\ngoto LOOP")]).unwrap();
+        assert_eq!(vm.run(10), Ok(Stop::StepLimit));
+        assert_eq!(vm.steps(), 10);
+        assert_eq!(vm.current_location(), Some(("Main", 3)));
+    }
+}
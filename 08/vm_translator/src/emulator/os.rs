@@ -0,0 +1,729 @@
+//! Native implementations of the Jack OS (the classes of `12/`). A `call` of an
+//! OS function, that the program does not define itself, is served by a
+//! `Builtin`. The builtins keep their objects in the RAM like the Jack classes do:
+//! a `String` is `[length, capacity, chars]` (like `12/src/String.jack`), every
+//! heap block has it's size in front of it and the screen is the memory map at 16384.
+//! A builtin always uses the other builtins, so a program that brings it's own
+//! `Memory.vm` should also bring the classes, that allocate (`Array`, `String`).
+
+use super::font::glyph;
+
+pub const SCREEN: usize = 16384;
+pub const SCREEN_SIZE: usize = 8192;
+pub const KBD: usize = 24576;
+pub const HEAP: usize = 2048;
+pub const HEAP_END: usize = SCREEN;
+
+const NEW_LINE: i16 = 128;
+const BACKSPACE: i16 = 129;
+const DOUBLE_QUOTE: i16 = 34;
+const ROWS: i16 = 23;
+const COLUMNS: i16 = 64;
+
+/// The OS functions, that have a native implementation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Builtin {
+    MathInit,
+    MathAbs,
+    MathMultiply,
+    MathDivide,
+    MathMin,
+    MathMax,
+    MathSqrt,
+    StringNew,
+    StringDispose,
+    StringLength,
+    StringCharAt,
+    StringSetCharAt,
+    StringAppendChar,
+    StringEraseLastChar,
+    StringIntValue,
+    StringSetInt,
+    StringBackSpace,
+    StringDoubleQuote,
+    StringNewLine,
+    ArrayNew,
+    ArrayDispose,
+    OutputInit,
+    OutputMoveCursor,
+    OutputPrintChar,
+    OutputPrintString,
+    OutputPrintInt,
+    OutputPrintln,
+    OutputBackSpace,
+    ScreenInit,
+    ScreenClearScreen,
+    ScreenSetColor,
+    ScreenDrawPixel,
+    ScreenDrawLine,
+    ScreenDrawRectangle,
+    ScreenDrawCircle,
+    KeyboardInit,
+    KeyboardKeyPressed,
+    KeyboardReadChar,
+    KeyboardReadLine,
+    KeyboardReadInt,
+    MemoryInit,
+    MemoryPeek,
+    MemoryPoke,
+    MemoryAlloc,
+    MemoryDeAlloc,
+    SysHalt,
+    SysError,
+    SysWait,
+}
+
+// The name and number of arguments of every builtin.
+const BUILTINS: [(Builtin, &str, u32); 48] = [
+    (Builtin::MathInit, "Math.init", 0),
+    (Builtin::MathAbs, "Math.abs", 1),
+    (Builtin::MathMultiply, "Math.multiply", 2),
+    (Builtin::MathDivide, "Math.divide", 2),
+    (Builtin::MathMin, "Math.min", 2),
+    (Builtin::MathMax, "Math.max", 2),
+    (Builtin::MathSqrt, "Math.sqrt", 1),
+    (Builtin::StringNew, "String.new", 1),
+    (Builtin::StringDispose, "String.dispose", 1),
+    (Builtin::StringLength, "String.length", 1),
+    (Builtin::StringCharAt, "String.charAt", 2),
+    (Builtin::StringSetCharAt, "String.setCharAt", 3),
+    (Builtin::StringAppendChar, "String.appendChar", 2),
+    (Builtin::StringEraseLastChar, "String.eraseLastChar", 1),
+    (Builtin::StringIntValue, "String.intValue", 1),
+    (Builtin::StringSetInt, "String.setInt", 2),
+    (Builtin::StringBackSpace, "String.backSpace", 0),
+    (Builtin::StringDoubleQuote, "String.doubleQuote", 0),
+    (Builtin::StringNewLine, "String.newLine", 0),
+    (Builtin::ArrayNew, "Array.new", 1),
+    (Builtin::ArrayDispose, "Array.dispose", 1),
+    (Builtin::OutputInit, "Output.init", 0),
+    (Builtin::OutputMoveCursor, "Output.moveCursor", 2),
+    (Builtin::OutputPrintChar, "Output.printChar", 1),
+    (Builtin::OutputPrintString, "Output.printString", 1),
+    (Builtin::OutputPrintInt, "Output.printInt", 1),
+    (Builtin::OutputPrintln, "Output.println", 0),
+    (Builtin::OutputBackSpace, "Output.backSpace", 0),
+    (Builtin::ScreenInit, "Screen.init", 0),
+    (Builtin::ScreenClearScreen, "Screen.clearScreen", 0),
+    (Builtin::ScreenSetColor, "Screen.setColor", 1),
+    (Builtin::ScreenDrawPixel, "Screen.drawPixel", 2),
+    (Builtin::ScreenDrawLine, "Screen.drawLine", 4),
+    (Builtin::ScreenDrawRectangle, "Screen.drawRectangle", 4),
+    (Builtin::ScreenDrawCircle, "Screen.drawCircle", 3),
+    (Builtin::KeyboardInit, "Keyboard.init", 0),
+    (Builtin::KeyboardKeyPressed, "Keyboard.keyPressed", 0),
+    (Builtin::KeyboardReadChar, "Keyboard.readChar", 0),
+    (Builtin::KeyboardReadLine, "Keyboard.readLine", 1),
+    (Builtin::KeyboardReadInt, "Keyboard.readInt", 1),
+    (Builtin::MemoryInit, "Memory.init", 0),
+    (Builtin::MemoryPeek, "Memory.peek", 1),
+    (Builtin::MemoryPoke, "Memory.poke", 2),
+    (Builtin::MemoryAlloc, "Memory.alloc", 1),
+    (Builtin::MemoryDeAlloc, "Memory.deAlloc", 1),
+    (Builtin::SysHalt, "Sys.halt", 0),
+    (Builtin::SysError, "Sys.error", 1),
+    (Builtin::SysWait, "Sys.wait", 1),
+];
+
+impl Builtin {
+    /// The builtin of an OS function like `Math.multiply`.
+    pub fn find(name: &str) -> Option<Builtin> {
+        BUILTINS.iter().find(|(_, builtin_name, _)| *builtin_name == name).map(|(builtin, _, _)| *builtin)
+    }
+    pub fn name(&self) -> &'static str {
+        self.entry().1
+    }
+    /// The number of arguments (`this` included).
+    pub fn args(&self) -> u32 {
+        self.entry().2
+    }
+    fn entry(&self) -> &'static (Builtin, &'static str, u32) {
+        BUILTINS.iter().find(|(builtin, _, _)| builtin == self).expect("every builtin is in the table")
+    }
+}
+
+/// What the VM does after a builtin.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Native {
+    /// Replace the arguments by the value (`void` functions return 0).
+    Return(i16),
+    /// Waiting for the keyboard: the call is executed again with the next step.
+    Block,
+    /// `Sys.halt` or `Sys.error`: the program ends.
+    Halt,
+}
+
+// The state of the OS, that the Jack classes keep in their statics.
+#[derive(Debug, Clone)]
+pub struct Os {
+    free_list: usize,
+    color: bool,
+    row: i16,
+    column: i16,
+    // The characters of a `readLine`, that has already printed it's message.
+    line: Option<Vec<i16>>,
+}
+
+impl Default for Os {
+    fn default() -> Self {
+        Os { free_list: HEAP, color: true, row: 0, column: 0, line: None }
+    }
+}
+
+// Addresses are 15bit like the address bus of the HACK computer,
+// so a broken pointer can not leave the RAM.
+fn peek(ram: &[i16], address: i32) -> i16 {
+    ram[(address & 0x7FFF) as usize]
+}
+fn poke(ram: &mut [i16], address: i32, value: i16) {
+    ram[(address & 0x7FFF) as usize] = value;
+}
+
+impl Os {
+    pub fn new() -> Self {
+        Os::default()
+    }
+
+    /// Initializes every class, like `Sys.init` of the Jack OS does.
+    pub fn init(&mut self, ram: &mut [i16]) {
+        self.memory_init(ram);
+        self.color = true;
+        self.row = 0;
+        self.column = 0;
+        self.line = None;
+    }
+
+    /// Executes a builtin with it's arguments.
+    pub fn call(&mut self, builtin: Builtin, args: &[i16], ram: &mut [i16]) -> Native {
+        let arg = |idx: usize| args.get(idx).copied().unwrap_or(0);
+        let value = match builtin {
+            Builtin::MathInit | Builtin::KeyboardInit | Builtin::SysWait => 0,
+            Builtin::MathAbs => arg(0).wrapping_abs(),
+            Builtin::MathMultiply => arg(0).wrapping_mul(arg(1)),
+            Builtin::MathDivide => match arg(1) {
+                0 => return self.error(3, ram),
+                y => arg(0).wrapping_div(y),
+            },
+            Builtin::MathMin => arg(0).min(arg(1)),
+            Builtin::MathMax => arg(0).max(arg(1)),
+            Builtin::MathSqrt => match arg(0) {
+                x if x < 0 => return self.error(4, ram),
+                x => (x as f64).sqrt() as i16,
+            },
+
+            Builtin::StringNew => match arg(0) {
+                capacity if capacity < 0 => return self.error(14, ram),
+                capacity => match self.string_new(capacity, ram) {
+                    Some(string) => string,
+                    None => return self.error(6, ram),
+                },
+            },
+            Builtin::StringDispose => {
+                self.dealloc(peek(ram, arg(0) as i32 + 2), ram);
+                self.dealloc(arg(0), ram);
+                0
+            },
+            Builtin::StringLength => peek(ram, arg(0) as i32),
+            Builtin::StringCharAt => {
+                let (string, idx) = (arg(0) as i32, arg(1));
+                if idx < 0 || idx >= peek(ram, string) {
+                    return self.error(15, ram);
+                }
+                peek(ram, peek(ram, string + 2) as i32 + idx as i32)
+            },
+            Builtin::StringSetCharAt => {
+                let (string, idx) = (arg(0) as i32, arg(1));
+                if idx < 0 || idx >= peek(ram, string) {
+                    return self.error(16, ram);
+                }
+                poke(ram, peek(ram, string + 2) as i32 + idx as i32, arg(2));
+                0
+            },
+            Builtin::StringAppendChar => {
+                if !self.append_char(arg(0), arg(1), ram) {
+                    return self.error(17, ram);
+                }
+                arg(0)
+            },
+            Builtin::StringEraseLastChar => {
+                let string = arg(0) as i32;
+                match peek(ram, string) {
+                    0 => return self.error(18, ram),
+                    length => poke(ram, string, length - 1),
+                }
+                0
+            },
+            Builtin::StringIntValue => int_value(&string_chars(arg(0), ram)),
+            Builtin::StringSetInt => {
+                let string = arg(0) as i32;
+                let digits: Vec<i16> = arg(1).to_string().bytes().map(|c| c as i16).collect();
+                if digits.len() as i16 > peek(ram, string + 1) {
+                    return self.error(19, ram);
+                }
+                poke(ram, string, 0);
+                for c in digits {
+                    self.append_char(string as i16, c, ram);
+                }
+                0
+            },
+            Builtin::StringBackSpace => BACKSPACE,
+            Builtin::StringDoubleQuote => DOUBLE_QUOTE,
+            Builtin::StringNewLine => NEW_LINE,
+
+            Builtin::ArrayNew => match arg(0) {
+                size if size <= 0 => return self.error(2, ram),
+                size => match self.alloc(size, ram) {
+                    Some(array) => array,
+                    None => return self.error(6, ram),
+                },
+            },
+            Builtin::ArrayDispose | Builtin::MemoryDeAlloc => {
+                self.dealloc(arg(0), ram);
+                0
+            },
+
+            Builtin::OutputInit => {
+                self.row = 0;
+                self.column = 0;
+                0
+            },
+            Builtin::OutputMoveCursor => {
+                let (row, column) = (arg(0), arg(1));
+                if !(0..ROWS).contains(&row) || !(0..COLUMNS).contains(&column) {
+                    return self.error(20, ram);
+                }
+                self.row = row;
+                self.column = column;
+                self.draw_char(b' ' as i16, ram);
+                0
+            },
+            Builtin::OutputPrintChar => {
+                self.print_char(arg(0), ram);
+                0
+            },
+            Builtin::OutputPrintString => {
+                self.print_chars(&string_chars(arg(0), ram), ram);
+                0
+            },
+            Builtin::OutputPrintInt => {
+                self.print_int(arg(0), ram);
+                0
+            },
+            Builtin::OutputPrintln => {
+                self.println();
+                0
+            },
+            Builtin::OutputBackSpace => {
+                self.back_space(ram);
+                0
+            },
+
+            Builtin::ScreenInit => {
+                self.color = true;
+                0
+            },
+            Builtin::ScreenClearScreen => {
+                ram[SCREEN..SCREEN + SCREEN_SIZE].iter_mut().for_each(|word| *word = 0);
+                0
+            },
+            Builtin::ScreenSetColor => {
+                self.color = arg(0) != 0;
+                0
+            },
+            Builtin::ScreenDrawPixel => {
+                if !on_screen(arg(0), arg(1)) {
+                    return self.error(7, ram);
+                }
+                self.draw_pixel(arg(0), arg(1), ram);
+                0
+            },
+            Builtin::ScreenDrawLine => {
+                if !on_screen(arg(0), arg(1)) || !on_screen(arg(2), arg(3)) {
+                    return self.error(8, ram);
+                }
+                self.draw_line(arg(0), arg(1), arg(2), arg(3), ram);
+                0
+            },
+            Builtin::ScreenDrawRectangle => {
+                let (x1, y1, x2, y2) = (arg(0), arg(1), arg(2), arg(3));
+                if !on_screen(x1, y1) || !on_screen(x2, y2) || x1 > x2 || y1 > y2 {
+                    return self.error(9, ram);
+                }
+                for y in y1..=y2 {
+                    self.draw_line(x1, y, x2, y, ram);
+                }
+                0
+            },
+            Builtin::ScreenDrawCircle => {
+                let (x, y, r) = (arg(0), arg(1), arg(2));
+                if !on_screen(x, y) {
+                    return self.error(12, ram);
+                }
+                if !(0..=181).contains(&r) || !on_screen(x - r, y - r) || !on_screen(x + r, y + r) {
+                    return self.error(13, ram);
+                }
+                self.draw_circle(x, y, r, ram);
+                0
+            },
+
+            Builtin::KeyboardKeyPressed => ram[KBD],
+            Builtin::KeyboardReadChar => match self.take_key(ram) {
+                Some(key) => {
+                    self.print_char(key, ram);
+                    key
+                },
+                None => return Native::Block,
+            },
+            Builtin::KeyboardReadLine | Builtin::KeyboardReadInt => {
+                let line = match self.read_line(arg(0), ram) {
+                    Some(line) => line,
+                    None => return Native::Block,
+                };
+                if builtin == Builtin::KeyboardReadInt {
+                    int_value(&line)
+                } else {
+                    match self.string_new(line.len() as i16, ram) {
+                        Some(string) => {
+                            for c in line {
+                                self.append_char(string, c, ram);
+                            }
+                            string
+                        },
+                        None => return self.error(6, ram),
+                    }
+                }
+            },
+
+            Builtin::MemoryInit => {
+                self.memory_init(ram);
+                0
+            },
+            Builtin::MemoryPeek => peek(ram, arg(0) as i32),
+            Builtin::MemoryPoke => {
+                poke(ram, arg(0) as i32, arg(1));
+                0
+            },
+            Builtin::MemoryAlloc => match arg(0) {
+                size if size <= 0 => return self.error(5, ram),
+                size => match self.alloc(size, ram) {
+                    Some(block) => block,
+                    None => return self.error(6, ram),
+                },
+            },
+
+            Builtin::SysHalt => return Native::Halt,
+            Builtin::SysError => return self.error(arg(0), ram),
+        };
+        Native::Return(value)
+    }
+
+    // Prints "ERR<code>" and halts (like `Sys.error`).
+    fn error(&mut self, code: i16, ram: &mut [i16]) -> Native {
+        self.print_chars(&[69, 82, 82], ram);
+        self.print_int(code, ram);
+        Native::Halt
+    }
+
+    // The heap is a list of free segments `[next, size]` (the size without these two words).
+    // A block is cut from the end of a segment and has it's size in front of it.
+    fn memory_init(&mut self, ram: &mut [i16]) {
+        self.free_list = HEAP;
+        ram[HEAP] = 0;
+        ram[HEAP + 1] = (HEAP_END - HEAP - 2) as i16;
+    }
+    fn alloc(&mut self, size: i16, ram: &mut [i16]) -> Option<i16> {
+        let mut segment = self.free_list as i32;
+        while segment != 0 {
+            let space = peek(ram, segment + 1);
+            // (in i32, the size of a block can be up to 32767)
+            if space as i32 >= size as i32 + 2 {
+                let block = segment + 2 + (space - size) as i32;
+                poke(ram, block - 1, size);
+                poke(ram, segment + 1, space - size - 2);
+                return Some(block as i16);
+            }
+            segment = peek(ram, segment) as i32;
+        }
+        None
+    }
+    fn dealloc(&mut self, block: i16, ram: &mut [i16]) {
+        let segment = block as i32 - 2;
+        let size = peek(ram, segment + 1);
+        poke(ram, segment, self.free_list as i16);
+        poke(ram, segment + 1, size);
+        self.free_list = (segment & 0x7FFF) as usize;
+    }
+
+    fn string_new(&mut self, capacity: i16, ram: &mut [i16]) -> Option<i16> {
+        let string = self.alloc(3, ram)?;
+        let chars = match self.alloc(capacity.max(1), ram) {
+            Some(chars) => chars,
+            None => {
+                self.dealloc(string, ram);
+                return None;
+            },
+        };
+        poke(ram, string as i32, 0);
+        poke(ram, string as i32 + 1, capacity);
+        poke(ram, string as i32 + 2, chars);
+        Some(string)
+    }
+    fn append_char(&mut self, string: i16, c: i16, ram: &mut [i16]) -> bool {
+        let string = string as i32;
+        let length = peek(ram, string);
+        if length >= peek(ram, string + 1) {
+            return false;
+        }
+        poke(ram, peek(ram, string + 2) as i32 + length as i32, c);
+        poke(ram, string, length + 1);
+        true
+    }
+
+    fn print_char(&mut self, c: i16, ram: &mut [i16]) {
+        match c {
+            NEW_LINE => self.println(),
+            BACKSPACE => self.back_space(ram),
+            _ => {
+                self.draw_char(c, ram);
+                self.column += 1;
+                if self.column == COLUMNS {
+                    self.println();
+                }
+            },
+        }
+    }
+    fn print_chars(&mut self, chars: &[i16], ram: &mut [i16]) {
+        for c in chars {
+            self.print_char(*c, ram);
+        }
+    }
+    fn print_int(&mut self, value: i16, ram: &mut [i16]) {
+        let digits: Vec<i16> = value.to_string().bytes().map(|c| c as i16).collect();
+        self.print_chars(&digits, ram);
+    }
+    fn println(&mut self) {
+        self.column = 0;
+        self.row = (self.row + 1) % ROWS;
+    }
+    fn back_space(&mut self, ram: &mut [i16]) {
+        if self.column > 0 {
+            self.column -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.column = COLUMNS - 1;
+        }
+        self.draw_char(b' ' as i16, ram);
+    }
+    // Two characters share a word: the even column is the low byte.
+    fn draw_char(&self, c: i16, ram: &mut [i16]) {
+        let mut address = SCREEN + (self.row * 11 * 32 + self.column / 2) as usize;
+        let (shift, keep) = if self.column % 2 == 0 { (0, 0xFF00u16) } else { (8, 0x00FFu16) };
+        for row in glyph(c).iter() {
+            let word = (ram[address] as u16 & keep) | ((*row as u16) << shift);
+            ram[address] = word as i16;
+            address += 32;
+        }
+    }
+
+    fn draw_pixel(&self, x: i16, y: i16, ram: &mut [i16]) {
+        let address = SCREEN + (y as usize) * 32 + (x as usize) / 16;
+        let bit = 1u16 << (x % 16);
+        let word = ram[address] as u16;
+        ram[address] = if self.color { word | bit } else { word & !bit } as i16;
+    }
+    // Walks from (x1, y1) to (x2, y2) like the algorithm of the book, that
+    // decides with `a * dy - b * dx` whether to go right or down.
+    fn draw_line(&self, x1: i16, y1: i16, x2: i16, y2: i16, ram: &mut [i16]) {
+        let (dx, dy) = ((x2 - x1) as i32, (y2 - y1) as i32);
+        let (step_x, step_y) = (dx.signum(), dy.signum());
+        let (dx, dy) = (dx.abs(), dy.abs());
+        let (mut a, mut b, mut diff) = (0, 0, 0);
+        while a <= dx && b <= dy {
+            self.draw_pixel((x1 as i32 + a * step_x) as i16, (y1 as i32 + b * step_y) as i16, ram);
+            if dx == 0 {
+                b += 1;
+            } else if dy == 0 || diff < 0 {
+                a += 1;
+                diff += dy;
+            } else {
+                b += 1;
+                diff -= dx;
+            }
+        }
+    }
+    fn draw_circle(&self, x: i16, y: i16, r: i16, ram: &mut [i16]) {
+        let r = r as i32;
+        for dy in -r..=r {
+            let half = ((r * r - dy * dy) as f64).sqrt() as i32;
+            let row = (y as i32 + dy) as i16;
+            self.draw_line((x as i32 - half) as i16, row, (x as i32 + half) as i16, row, ram);
+        }
+    }
+
+    // Takes the pressed key (a key counts once, as if it had been released again).
+    fn take_key(&mut self, ram: &mut [i16]) -> Option<i16> {
+        match ram[KBD] {
+            0 => None,
+            key => {
+                ram[KBD] = 0;
+                Some(key)
+            },
+        }
+    }
+    // Prints the message once and collects keys until a new line, which returns the line.
+    fn read_line(&mut self, message: i16, ram: &mut [i16]) -> Option<Vec<i16>> {
+        if self.line.is_none() {
+            self.print_chars(&string_chars(message, ram), ram);
+            self.line = Some(Vec::new());
+        }
+        let key = self.take_key(ram)?;
+        let line = self.line.as_mut().expect("the line has been started");
+        match key {
+            NEW_LINE => {
+                self.println();
+                return self.line.take();
+            },
+            BACKSPACE => {
+                if line.pop().is_some() {
+                    self.back_space(ram);
+                }
+            },
+            c => {
+                line.push(c);
+                self.print_char(c, ram);
+            },
+        }
+        None
+    }
+}
+
+fn on_screen(x: i16, y: i16) -> bool {
+    (0..512).contains(&x) && (0..256).contains(&y)
+}
+
+// The characters of a `String` object.
+fn string_chars(string: i16, ram: &[i16]) -> Vec<i16> {
+    let string = string as i32;
+    let chars = peek(ram, string + 2) as i32;
+    (0..peek(ram, string).max(0) as i32).map(|idx| peek(ram, chars + idx)).collect()
+}
+
+// The number at the start of the characters (with an optional `-`).
+fn int_value(chars: &[i16]) -> i16 {
+    let (sign, digits) = match chars.first() {
+        Some(45) => (-1, &chars[1..]),
+        _ => (1, chars),
+    };
+    let value = digits
+        .iter()
+        .take_while(|c| (48..=57).contains(*c))
+        .fold(0i16, |value, c| value.wrapping_mul(10).wrapping_add(c - 48));
+    value.wrapping_mul(sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os() -> (Os, Vec<i16>) {
+        let mut ram = vec![0; 32768];
+        let mut os = Os::new();
+        os.init(&mut ram);
+        (os, ram)
+    }
+    fn string(os: &mut Os, text: &str, ram: &mut [i16]) -> i16 {
+        let string = os.string_new(text.len() as i16, ram).unwrap();
+        for c in text.bytes() {
+            os.append_char(string, c as i16, ram);
+        }
+        string
+    }
+
+    #[test]
+    fn finds_builtins_by_name() {
+        assert_eq!(Builtin::find("Math.multiply"), Some(Builtin::MathMultiply));
+        assert_eq!(Builtin::MathMultiply.args(), 2);
+        assert_eq!(Builtin::find("Math.foo"), None);
+    }
+    #[test]
+    fn math_divides_towards_zero() {
+        let (mut os, mut ram) = os();
+        assert_eq!(os.call(Builtin::MathDivide, &[-7, 2], &mut ram), Native::Return(-3));
+        assert_eq!(os.call(Builtin::MathSqrt, &[17], &mut ram), Native::Return(4));
+        assert_eq!(os.call(Builtin::MathMultiply, &[300, 300], &mut ram), Native::Return(300i16.wrapping_mul(300)));
+    }
+    #[test]
+    fn division_by_zero_prints_error_and_halts() {
+        let (mut os, mut ram) = os();
+        assert_eq!(os.call(Builtin::MathDivide, &[1, 0], &mut ram), Native::Halt);
+        assert_eq!(os.column, 4);
+    }
+    #[test]
+    fn alloc_cuts_blocks_from_the_end_of_the_heap() {
+        let (mut os, mut ram) = os();
+        assert_eq!(os.call(Builtin::MemoryAlloc, &[10], &mut ram), Native::Return(16374));
+        assert_eq!(ram[16373], 10);
+        assert_eq!(os.call(Builtin::MemoryAlloc, &[4], &mut ram), Native::Return(16368));
+        os.call(Builtin::MemoryDeAlloc, &[16374], &mut ram);
+        // The freed block is the first segment now and the next block is cut from it's end.
+        assert_eq!(os.call(Builtin::MemoryAlloc, &[3], &mut ram), Native::Return(16381));
+    }
+    #[test]
+    fn oversized_blocks_are_an_error() {
+        for builtin in [Builtin::MemoryAlloc, Builtin::ArrayNew, Builtin::StringNew].iter() {
+            let (mut os, mut ram) = os();
+            assert_eq!(os.call(*builtin, &[32767], &mut ram), Native::Halt);
+            // "ERR6" and the heap can still be used
+            assert_eq!(os.column, 4);
+            assert!(matches!(os.call(Builtin::MemoryAlloc, &[10], &mut ram), Native::Return(_)));
+        }
+    }
+    #[test]
+    fn strings_use_the_layout_of_string_jack() {
+        let (mut os, mut ram) = os();
+        let s = string(&mut os, "-42x", &mut ram);
+        assert_eq!(ram[s as usize..s as usize + 2], [4, 4]);
+        assert_eq!(os.call(Builtin::StringIntValue, &[s], &mut ram), Native::Return(-42));
+        assert_eq!(os.call(Builtin::StringSetInt, &[s, 1234], &mut ram), Native::Return(0));
+        assert_eq!(string_chars(s, &ram), vec![49, 50, 51, 52]);
+        assert_eq!(os.call(Builtin::StringAppendChar, &[s, 53], &mut ram), Native::Halt);
+    }
+    #[test]
+    fn prints_characters_into_the_screen() {
+        let (mut os, mut ram) = os();
+        os.call(Builtin::OutputPrintChar, &[65], &mut ram);
+        os.call(Builtin::OutputPrintChar, &[65], &mut ram);
+        // 'A' starts with 12 (00001100), the second one is in the high byte.
+        assert_eq!(ram[SCREEN] as u16, 12 | (12 << 8));
+        assert_eq!(ram[SCREEN + 4 * 32], 63 | (63 << 8));
+        assert_eq!(os.column, 2);
+    }
+    #[test]
+    fn draws_lines_and_rectangles() {
+        let (mut os, mut ram) = os();
+        os.call(Builtin::ScreenDrawLine, &[0, 0, 15, 0], &mut ram);
+        assert_eq!(ram[SCREEN], -1);
+        // A diagonal goes down first and then right.
+        os.call(Builtin::ScreenDrawLine, &[0, 2, 2, 4], &mut ram);
+        assert_eq!((ram[SCREEN + 64], ram[SCREEN + 96], ram[SCREEN + 128]), (1, 3, 6));
+        os.call(Builtin::ScreenSetColor, &[0], &mut ram);
+        os.call(Builtin::ScreenDrawRectangle, &[0, 0, 7, 0], &mut ram);
+        assert_eq!(ram[SCREEN] as u16, 0xFF00);
+    }
+    #[test]
+    fn read_line_waits_for_keys() {
+        let (mut os, mut ram) = os();
+        let message = string(&mut os, "? ", &mut ram);
+        assert_eq!(os.call(Builtin::KeyboardReadInt, &[message], &mut ram), Native::Block);
+        for key in [55, 129, 52, 50, 128].iter() {
+            ram[KBD] = *key;
+            let result = os.call(Builtin::KeyboardReadInt, &[message], &mut ram);
+            if *key == 128 {
+                assert_eq!(result, Native::Return(42));
+            } else {
+                assert_eq!(result, Native::Block);
+            }
+        }
+        assert_eq!((os.row, os.column), (1, 0));
+    }
+}
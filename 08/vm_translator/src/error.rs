@@ -0,0 +1,72 @@
+//! Errors of translating (or emulating) a vm-file. Every error of a command knows the
+//! file and the line (starting at 1) it was found in and the command text,
+//! so all errors of a program can be reported at once.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum VmError {
+    /// A file or directory could not be read.
+    Io { path: String, message: String },
+    /// The first word is no command of the VM language (or has the wrong number of arguments).
+    UnknownCommand { file: String, line: usize, text: String },
+    UnknownSegment { file: String, line: usize, text: String, segment: String },
+    /// An index or count, that is no (positive) number.
+    InvalidNumber { file: String, line: usize, text: String, number: String },
+    /// The index does not fit into the segment (`pointer 0/1`, `temp 0-7`, `constant 0-32767`).
+    IndexOutOfRange { file: String, line: usize, text: String, max: u32 },
+    /// `pop constant` has nowhere to pop to.
+    PopConstant { file: String, line: usize, text: String },
+    /// A label is a symbol of letters, digits, `_`, `.`, `:` and `$`,
+    /// that starts with neither a digit nor `$` (those are the labels of the translator).
+    InvalidLabel { file: String, line: usize, text: String, label: String },
+    /// A command of a valid file, that does not fit into the program
+    /// (e.g. a `goto` to a label that does not exist in the function).
+    Link { file: String, line: usize, text: String, message: String },
+    /// The emulator can not execute the command (e.g. it accesses memory outside of the RAM).
+    Runtime { file: String, line: usize, text: String, message: String },
+    /// A line of a source map, that is no `<line> <file>:<line>` (or `<line> -`).
+    InvalidSourceMap { file: String, line: usize, text: String },
+}
+
+impl VmError {
+    /// The `file:line` the error was found at (or the path of an io-error).
+    pub fn location(&self) -> String {
+        match self {
+            VmError::Io { path, .. } => path.to_string(),
+            VmError::UnknownCommand { file, line, .. }
+            | VmError::UnknownSegment { file, line, .. }
+            | VmError::InvalidNumber { file, line, .. }
+            | VmError::IndexOutOfRange { file, line, .. }
+            | VmError::PopConstant { file, line, .. }
+            | VmError::InvalidLabel { file, line, .. }
+            | VmError::Link { file, line, .. }
+            | VmError::Runtime { file, line, .. }
+            | VmError::InvalidSourceMap { file, line, .. } => format!("{}:{}", file, line),
+        }
+    }
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (reason, text) = match self {
+            VmError::Io { message, .. } => return write!(f, "{}: {}", self.location(), message),
+            VmError::UnknownCommand { text, .. } => ("unknown command".to_string(), text),
+            VmError::UnknownSegment { text, segment, .. } => (format!("unknown segment '{}'", segment), text),
+            VmError::InvalidNumber { text, number, .. } => (format!("'{}' is no valid number", number), text),
+            VmError::IndexOutOfRange { text, max, .. } => (format!("index must be in the range 0..={}", max), text),
+            VmError::PopConstant { text, .. } => ("a constant can not be popped".to_string(), text),
+            VmError::InvalidLabel { text, label, .. } => (format!("'{}' is no valid label", label), text),
+            VmError::Link { text, message, .. } | VmError::Runtime { text, message, .. } => (message.to_string(), text),
+            VmError::InvalidSourceMap { text, .. } => ("no source map entry".to_string(), text),
+        };
+        write!(f, "{}: {} in '{}'", self.location(), reason, text)
+    }
+}
+
+impl std::error::Error for VmError {}
+
+/// Builds a report with one error per line.
+pub fn report(errors: &[VmError]) -> String {
+    errors.iter().map(|error| error.to_string()).collect::<Vec<String>>().join("\n")
+}
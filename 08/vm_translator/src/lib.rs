@@ -0,0 +1,195 @@
+// Docs can be opened with "cargo doc --open"
+//! Translates the VM language (projects 07 and 08) into HACK assembly.
+//! The translator can be used in-process (e.g. by the jack-compiler or tests)
+//! through `translate_str`, `translate_file` and `translate_dir`
+//! (`translate_file_with_map` and `translate_dir_with_map` also map every line
+//! of the assembly to the vm-file and line it comes from).
+//! `emulator::Vm` runs vm-files directly, without translating them.
+
+pub mod arg_handler;
+pub mod emulator;
+pub mod error;
+pub mod source_map;
+pub mod translator;
+
+use error::VmError;
+use source_map::{ Origin, SourceMap };
+use std::collections::BTreeSet;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{ Path, PathBuf };
+use translator::code_writer::{ write_asm, write_bootstrap, write_routines };
+use translator::labels::Labels;
+use translator::parser::{ parse_line, Com };
+
+/// Whether the program starts with the bootstrap code, that sets SP to 256 and calls `Sys.init`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Bootstrap {
+    /// Only if one of the translated files defines `Sys.init`.
+    Auto,
+    Always,
+    Never,
+}
+
+/// How `call`, `return`, `eq`, `gt` and `lt` are translated.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Routines {
+    /// Every command is translated on it's own (the fastest code).
+    Inline,
+    /// The commands jump into routines like `$$CALL`, that exist once at the end
+    /// of the program, and the other commands are written in their shortest form,
+    /// so large programs (like a game with the OS of project 12) still fit into the ROM.
+    Shared,
+}
+
+/// Translates the content of a single vm-file (without bootstrap code).
+/// `file_stem` is the name of the file without extension, it prefixes the static variables.
+/// All commands that can not be parsed are reported (with `<file_stem>.vm` and their line).
+///
+/// # Examples
+/// ```
+/// use vm_translator::translate_str;
+///
+/// let asm = translate_str("push constant 7\npush static 2", "Main").unwrap();
+/// assert!(asm.starts_with("\n// push constant 7\n@7\nD=A"));
+/// assert!(asm.contains("@Main.2\nD=M"));
+///
+/// let errors = translate_str("push constant 7\npop temp 8", "Main").unwrap_err();
+/// assert_eq!(errors[0].location(), "Main.vm:2");
+/// ```
+pub fn translate_str(vm_code: &str, file_stem: &str) -> Result<String, Vec<VmError>> {
+    let commands = parse_lines(vm_code, file_stem)?;
+    Ok(write_commands(&commands, file_stem, Routines::Inline).into_iter().map(|(_, asm)| asm).collect())
+}
+
+/// Reads and translates a single vm-file.
+pub fn translate_file(path: &Path, bootstrap: Bootstrap, routines: Routines) -> Result<String, Vec<VmError>> {
+    Ok(translate_files(&[path.to_path_buf()], bootstrap, routines)?.0)
+}
+
+/// Translates a single vm-file like `translate_file` and maps the lines of the
+/// assembly to the lines of the file.
+pub fn translate_file_with_map(path: &Path, bootstrap: Bootstrap, routines: Routines) -> Result<(String, SourceMap), Vec<VmError>> {
+    translate_files(&[path.to_path_buf()], bootstrap, routines)
+}
+
+/// Translates all vm-files of a directory (in alphabetical order) into one program.
+/// The errors of all files are collected.
+pub fn translate_dir(dir: &Path, bootstrap: Bootstrap, routines: Routines) -> Result<String, Vec<VmError>> {
+    Ok(translate_dir_with_map(dir, bootstrap, routines)?.0)
+}
+
+/// Translates a directory like `translate_dir` and maps the lines of the
+/// assembly to the vm-files and lines they come from.
+pub fn translate_dir_with_map(dir: &Path, bootstrap: Bootstrap, routines: Routines) -> Result<(String, SourceMap), Vec<VmError>> {
+    let mut paths = vm_files(dir).map_err(|e| vec![io_error(dir, e)])?;
+    paths.sort();
+    translate_files(&paths, bootstrap, routines)
+}
+
+fn translate_files(paths: &[PathBuf], bootstrap: Bootstrap, routines: Routines) -> Result<(String, SourceMap), Vec<VmError>> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let stem = file_stem(path);
+        let parsed = read_to_string(path)
+            .map_err(|e| vec![io_error(path, e)])
+            .and_then(|content| parse_lines(&content, &stem));
+        match parsed {
+            Ok(commands) => files.push((stem, commands)),
+            Err(file_errors) => errors.extend(file_errors),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let has_sys_init = files
+        .iter()
+        .any(|(_, commands)| commands.iter().any(|(_, command)| matches!(command, Com::Function(name, _) if name == "Sys.init")));
+    // Every file is a list of translated commands with their origin.
+    let mut asm_files: Vec<Vec<(Option<Origin>, String)>> = files
+        .iter()
+        .map(|(stem, commands)| {
+            let file = format!("{}.vm", stem);
+            write_commands(commands, stem, routines)
+                .into_iter()
+                .map(|(line, asm)| (Some(Origin::new(&file, line)), asm))
+                .collect()
+        })
+        .collect();
+    if bootstrap == Bootstrap::Always || (bootstrap == Bootstrap::Auto && has_sys_init) {
+        asm_files.insert(0, vec![(None, write_bootstrap())]);
+    }
+    if routines == Routines::Shared {
+        let calls: BTreeSet<(&str, u32)> = files
+            .iter()
+            .flat_map(|(_, commands)| commands.iter())
+            .filter_map(|(_, command)| match command {
+                Com::Call(name, args) => Some((name.as_str(), *args)),
+                _ => None,
+            })
+            .collect();
+        asm_files.push(vec![(None, write_routines(&calls.into_iter().collect::<Vec<_>>()))]);
+    }
+
+    let mut asm = String::new();
+    let mut map = SourceMap::new();
+    let mut line = 1;
+    for (idx, asm_file) in asm_files.into_iter().enumerate() {
+        let separator = if idx == 0 { None } else { Some((None, "\n".to_string())) };
+        for (origin, piece) in separator.into_iter().chain(asm_file) {
+            // The pieces start with a new line, their first line belongs to the last piece.
+            let leading = piece.len() - piece.trim_start_matches('\n').len();
+            map.add(line + leading, origin);
+            line += piece.matches('\n').count();
+            asm.push_str(&piece);
+        }
+    }
+    Ok((asm, map))
+}
+
+// Parses all lines of a file into the commands and their line numbers,
+// the errors of all lines are collected.
+pub(crate) fn parse_lines(vm_code: &str, file_stem: &str) -> Result<Vec<(usize, Com)>, Vec<VmError>> {
+    let file = format!("{}.vm", file_stem);
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    for (idx, line) in vm_code.split('\n').enumerate() {
+        match parse_line(line, &file, idx + 1) {
+            Ok(Com::Empty) => {},
+            Ok(command) => commands.push((idx + 1, command)),
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() { Ok(commands) } else { Err(errors) }
+}
+
+// Translates the commands of a file, every translation keeps the line of it's command.
+fn write_commands(commands: &[(usize, Com)], file_stem: &str, routines: Routines) -> Vec<(usize, String)> {
+    let mut labels = Labels::new(file_stem);
+    commands
+        .iter()
+        .map(|(line, command)| (*line, write_asm(command, file_stem, &mut labels, routines)))
+        .collect()
+}
+
+/// All files with a `.vm` extension inside of `dir`.
+pub fn vm_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "vm") {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+pub(crate) fn file_stem(path: &Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+pub(crate) fn io_error(path: &Path, error: io::Error) -> VmError {
+    VmError::Io { path: path.display().to_string(), message: error.to_string() }
+}
@@ -0,0 +1,88 @@
+// COMPILATION-INSTRUCTIONS (for Coursera, so python script can call it):
+// - Create a ".cargo" folder within your project-directory
+// - Create a "config.txt"
+// - Write into config.txt:
+//       [target.x86_64-unknown-linux-musl]
+//       linker = "x86_64-linux-musl-gcc"
+// - build project with "cargo build --target x86_64-unknown-linux-musl"
+
+use std::env::args;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use vm_translator::arg_handler::path_builder;
+use vm_translator::error::report;
+use vm_translator::translator::optimizer::optimize_with_map;
+use vm_translator::{ translate_dir_with_map, translate_file_with_map, Bootstrap, Routines };
+
+fn main() {
+
+    // Get file-name from command-line, arguments starting with "--" are flags.
+    // Without a flag the bootstrap code is only written, if the program defines Sys.init.
+    // "--size" makes calls, returns and comparisons share one routine each (and writes the other commands short).
+    // "--source-map" also writes "<output>.asm.map", which maps the assembly to the vm-files.
+    let (flags, args): (Vec<String>, Vec<String>) = args().skip(1).partition(|arg| arg.starts_with("--"));
+    let input_path = if args.len() == 1 {
+            Path::new(&args[0])
+        } else {
+            panic!("Please specify input file or folder! (optionally with '--bootstrap', '--no-bootstrap', '--size', '--optimize' or '--source-map')")
+        };
+    let mut bootstrap = Bootstrap::Auto;
+    let mut routines = Routines::Inline;
+    let mut optimized = false;
+    let mut source_map = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--bootstrap" => bootstrap = Bootstrap::Always,
+            "--no-bootstrap" => bootstrap = Bootstrap::Never,
+            "--size" => routines = Routines::Shared,
+            "--optimize" => optimized = true,
+            "--source-map" => source_map = true,
+            flag => panic!("Unknown flag '{}'", flag),
+        }
+    }
+
+    // A single file gets translated on it's own, a directory as a whole program.
+    let output_path = path_builder(input_path);
+    let translation = if input_path.is_dir() {
+        translate_dir_with_map(input_path, bootstrap, routines)
+    } else {
+        translate_file_with_map(input_path, bootstrap, routines)
+    };
+    let (mut file_content_str, mut asm_map) = match translation {
+        Ok(translation) => translation,
+        Err(errors) => {
+            eprintln!("{}", report(&errors));
+            std::process::exit(1);
+        },
+    };
+
+    // The peephole optimizer reports how much shorter the program became.
+    if optimized {
+        let (asm, map, optimization) = optimize_with_map(&file_content_str, &asm_map);
+        println!("{}", optimization);
+        file_content_str = asm;
+        asm_map = map;
+    }
+
+    if source_map {
+        let map_path = output_path.with_extension("asm.map");
+        match std::fs::write(&map_path, asm_map.to_string()) {
+            Ok(_) => println!("Source map with path: '{:?}' has been created successfully.", map_path),
+            Err(m) => panic!("Could not write the source map because: {}", m),
+        }
+    }
+
+    // Create the output file.
+    let mut asm_file = match File::create(&output_path) {
+        Ok(file) => file,
+        Err(m) => panic!("Could not create file because: {}", m),
+    };
+
+    // Write the final ASM-String to the file.
+    match asm_file.write_all(file_content_str.as_bytes()) {
+        Ok(_) => println!("HackFile with path: '{:?}' has been created successfully.", output_path),
+        Err(m) => panic!("Coulnd not write to file because: {}", m),
+    }
+}
@@ -0,0 +1,181 @@
+//! Source maps connect the lines of a generated file with the file and line they
+//! come from. The jack-compiler maps vm-lines to Jack, the translator maps
+//! asm-lines to vm-files and the assembler maps ROM addresses to asm-lines,
+//! so `trace` can follow an address of the ROM back to the Jack source.
+//!
+//! A map is written with one entry per line: `<line> <file>:<line>`. An entry
+//! holds for all lines up to the next entry, `<line> -` starts lines without an
+//! origin (like the bootstrap code).
+
+use crate::error::VmError;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The file and line (starting at 1) a generated line comes from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Origin {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Origin {
+    pub fn new(file: &str, line: usize) -> Self {
+        Origin { file: file.to_string(), line }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SourceMap {
+    // Ordered by the generated line.
+    entries: Vec<(usize, Option<Origin>)>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// The generated lines from `line` on come from `origin`.
+    /// Entries have to be added in the order of their lines.
+    pub fn add(&mut self, line: usize, origin: Option<Origin>) {
+        match self.entries.last_mut() {
+            // Lines before the first entry have no origin anyway.
+            None if origin.is_none() => {},
+            Some((_, last)) if *last == origin => {},
+            Some((last_line, last)) if *last_line == line => *last = origin,
+            _ => self.entries.push((line, origin)),
+        }
+    }
+
+    /// The origin of a generated line.
+    ///
+    /// # Examples
+    /// ```
+    /// use vm_translator::source_map::{ Origin, SourceMap };
+    ///
+    /// let mut map = SourceMap::new();
+    /// map.add(5, Some(Origin::new("Main.vm", 3)));
+    /// map.add(8, None);
+    /// assert_eq!(map.lookup(4), None);
+    /// assert_eq!(map.lookup(7), Some(&Origin::new("Main.vm", 3)));
+    /// assert_eq!(map.lookup(9), None);
+    /// assert_eq!(map.to_string(), "5 Main.vm:3\n8 -");
+    /// ```
+    pub fn lookup(&self, line: usize) -> Option<&Origin> {
+        let idx = self.entries.partition_point(|(start, _)| *start <= line);
+        self.entries[..idx].last()?.1.as_ref()
+    }
+
+    pub fn entries(&self) -> &[(usize, Option<Origin>)] {
+        &self.entries
+    }
+
+    /// Reads a written map, `file` is the name of the map for the errors.
+    pub fn parse(map: &str, file: &str) -> Result<SourceMap, Vec<VmError>> {
+        let mut source_map = SourceMap::new();
+        let mut errors = Vec::new();
+        for (idx, text) in map.split('\n').enumerate() {
+            if text.trim().is_empty() {
+                continue;
+            }
+            match parse_entry(text.trim()) {
+                Some((line, origin)) => source_map.add(line, origin),
+                None => errors.push(VmError::InvalidSourceMap { file: file.to_string(), line: idx + 1, text: text.to_string() }),
+            }
+        }
+        if errors.is_empty() { Ok(source_map) } else { Err(errors) }
+    }
+}
+
+fn parse_entry(text: &str) -> Option<(usize, Option<Origin>)> {
+    let mut fields = text.split(' ');
+    let line = fields.next()?.parse().ok()?;
+    let origin = match (fields.next()?, fields.next()) {
+        ("-", None) => None,
+        (origin, None) => {
+            let (file, line) = origin.rsplit_once(':')?;
+            Some(Origin::new(file, line.parse().ok()?))
+        },
+        _ => return None,
+    };
+    Some((line, origin))
+}
+
+impl fmt::Display for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|(line, origin)| match origin {
+                Some(origin) => format!("{} {}", line, origin),
+                None => format!("{} -", line),
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Follows a line through the maps of the generated files (named like the files
+/// they belong to, e.g. `Pong.hack`, `Pong.asm` and `Main.vm`), until there is
+/// no map for the origin anymore. Returns every origin on the way.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashMap;
+/// use vm_translator::source_map::{ trace, Origin, SourceMap };
+///
+/// let mut maps = HashMap::new();
+/// maps.insert("Main.asm".to_string(), SourceMap::parse("10 Main.vm:4", "Main.asm.map").unwrap());
+/// maps.insert("Main.vm".to_string(), SourceMap::parse("3 Main.jack:7", "Main.vm.map").unwrap());
+/// assert_eq!(trace(&maps, "Main.asm", 12), vec![Origin::new("Main.vm", 4), Origin::new("Main.jack", 7)]);
+/// ```
+pub fn trace(maps: &HashMap<String, SourceMap>, file: &str, line: usize) -> Vec<Origin> {
+    let mut origins: Vec<Origin> = Vec::new();
+    let mut current = Origin::new(file, line);
+    while let Some(origin) = maps.get(&current.file).and_then(|map| map.lookup(current.line)) {
+        // A map of a file, that points into itself, would never end.
+        if origins.contains(origin) {
+            break;
+        }
+        origins.push(origin.clone());
+        current = origin.clone();
+    }
+    origins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_with_the_same_origin_are_merged() {
+        let mut map = SourceMap::new();
+        map.add(1, Some(Origin::new("Main.vm", 1)));
+        map.add(4, Some(Origin::new("Main.vm", 1)));
+        map.add(6, Some(Origin::new("Main.vm", 2)));
+        map.add(6, Some(Origin::new("Main.vm", 3)));
+        assert_eq!(map.to_string(), "1 Main.vm:1\n6 Main.vm:3");
+    }
+    #[test]
+    fn written_maps_can_be_read() {
+        let text = "3 Main.vm:2\n7 -\n9 Sys.vm:1";
+        assert_eq!(SourceMap::parse(text, "Prog.asm.map").unwrap().to_string(), text);
+    }
+    #[test]
+    fn reports_invalid_entries() {
+        let errors = SourceMap::parse("1 Main.vm:1\nfoo\n3 Main.vm", "Prog.asm.map").unwrap_err();
+        assert_eq!(errors.iter().map(|error| error.location()).collect::<Vec<String>>(), vec!["Prog.asm.map:2", "Prog.asm.map:3"]);
+    }
+    #[test]
+    fn lines_before_the_first_entry_have_no_origin() {
+        let map = SourceMap::parse("3 Main.vm:2", "Prog.asm.map").unwrap();
+        assert_eq!(map.lookup(2), None);
+        assert_eq!(map.lookup(3), Some(&Origin::new("Main.vm", 2)));
+    }
+}
@@ -0,0 +1,576 @@
+use super::labels::Labels;
+use super::parser::{ ArithOp, BranchKind, Com, Segment };
+use super::parser::Com::*;
+use crate::Routines;
+
+// Function gets called from main on every line.
+// `labels` belong to the file and keep track of the current function.
+pub fn write_asm(command: &Com, file_stem: &str, labels: &mut Labels, routines: Routines) -> String {
+    if routines == Routines::Shared {
+        match command {
+            Arith(com @ (ArithOp::Eq | ArithOp::Gt | ArithOp::Lt)) => return write_shared_compare(*com, &labels.next("CMP")),
+            Call(name, args) => return write_shared_call(name, *args, &labels.next("ret")),
+            Return => return write_shared_return(),
+            Arith(com) => return write_short_arithmetic(*com),
+            Push(segment, position) => return write_short_push(*segment, *position, file_stem),
+            Pop(segment, position) if pointed_address(*segment, *position).is_some() => return write_short_pop(*segment, *position),
+            Function(name, locals) => {
+                labels.enter(name);
+                return write_short_function(name, *locals);
+            },
+            _ => {},
+        }
+    }
+    match command {
+        Arith(com) => write_arithmetic(*com, labels),
+        Push(segment, position) => write_push(*segment, *position, file_stem),
+        Pop(segment, position) => write_pop(*segment, *position, file_stem),
+        Label(name) => write_label(&labels.user(name)),
+        Branch(condition, label) => write_branch(*condition, &labels.user(label)),
+        Function(name, locals) => {
+            labels.enter(name);
+            write_function(name, *locals)
+        },
+        Call(name, args) => write_call(name, *args, &labels.next("ret")),
+        Return => write_return(),
+        Empty => panic!("An Empty Line was assembled in the writing process. I should have been dropped before.")
+    }
+}
+
+// Bootstrap-Code: sets @SP to 256 and calls Sys.init
+// through a normal call-frame (which moves SP to 261).
+pub fn write_bootstrap() -> String {
+    let comment = "\n// Set SP to 256 and call Sys.init()".to_string();
+    let asm = "@256 D=A @SP M=D";
+    comment + &asm_new_line_concat(asm) + &write_call("Sys.init", 0, &Labels::new("Bootstrap").next("ret"))
+}
+
+// ASM-code-generator-functions
+fn sp_down() -> String {
+    "@SP AM=M-1".to_string()
+}
+fn sp_up() -> String {
+    "@SP M=M+1".to_string()
+}
+
+
+// Translates parsed Arithmetic commands (Com::Arith) into HACK-ASM
+fn write_arithmetic(method: ArithOp, labels: &mut Labels) -> String {
+
+    let comment = format!("\n// {}", method);
+
+    let arith_method = match method {
+        ArithOp::Add | ArithOp::Sub | ArithOp::Or | ArithOp::And => add_sub_or_and(method),
+        ArithOp::Eq | ArithOp::Gt | ArithOp::Lt => eq_gt_lt(method, &labels.next("CMP")),
+        ArithOp::Neg => "D=0 M=D-M".to_string(),
+        ArithOp::Not => "M=!M".to_string(),
+    };
+
+    let asm_string = format!("{} {} {}", sp_down(), arith_method, sp_up());
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+
+// Constructs the middle-part of an "add, sub, or, and" command.
+fn add_sub_or_and(method: ArithOp) -> String {
+    format!("D=M {} {}", sp_down(), binary_operation(method))
+}
+
+// Combines the second operand in D with the first one in M.
+fn binary_operation(method: ArithOp) -> &'static str {
+    match method {
+        ArithOp::Add => "M=D+M",
+        ArithOp::Sub => "M=M-D",
+        ArithOp::Or => "M=D|M",
+        _ => "M=D&M",
+    }
+}
+
+// Gets an "eg, gt or lt" command and a unique label which is used to create
+// the label-names for the jmp-instructions.
+fn eq_gt_lt(method: ArithOp, label: &str) -> String {
+    let front = format!("D=M @SP AM=M-1 D=M-D @{}.TRUE", label);
+    let tail = format!("@SP A=M M=0 @{}.NEXT 0;JMP ({}.TRUE) @SP A=M D=0 M=D-1 ({}.NEXT)", label, label, label);
+    let middle = match method {
+        ArithOp::Eq => "D;JEQ",
+        ArithOp::Gt => "D;JGT",
+        _ => "D;JLT",
+    };
+    format!("{} {} {}", front, middle, tail)
+}
+
+// Translates parsed push-commands (Com::Push) into HACK-ASM
+fn write_push(segment: Segment, position: u32, file: &str) -> String {
+    let comment = format!("\n// push {} {}", segment, position);
+
+    // Load the value into D (the parser made sure, that pointer is only 0 or 1)
+    let load = match segment {
+        Segment::Constant => format!("@{} D=A", position),
+        Segment::Local => format!("@{} D=A @LCL A=D+M D=M", position),
+        Segment::Argument => format!("@{} D=A @ARG A=D+M D=M", position),
+        Segment::This => format!("@{} D=A @THIS A=D+M D=M", position),
+        Segment::That => format!("@{} D=A @THAT A=D+M D=M", position),
+        Segment::Temp => format!("@{} D=A @5 A=D+A D=M", position),
+        Segment::Pointer if position == 0 => "@THIS D=M".to_string(),
+        Segment::Pointer => "@THAT D=M".to_string(),
+        Segment::Static => format!("@{}.{} D=M", file, position),
+    };
+
+    let asm_string = format!("{} @SP A=M M=D {}", load, sp_up());
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+// Translates parsed pop-commands (Com::Pop) into HACK-ASM
+// (the parser does not allow to pop into constant).
+// Fixed addresses are written directly, the address inside of
+// local, argument, this and that is kept in R13 while popping.
+fn write_pop(segment: Segment, position: u32, file: &str) -> String {
+    let comment = format!("\n// pop {} {}", segment, position);
+
+    let base = match base_pointer(segment) {
+        Some(base) => base,
+        None => {
+            let asm_string = format!("{} D=M @{} M=D", sp_down(), fixed_address(segment, position, file));
+            return comment + &asm_new_line_concat(&asm_string);
+        },
+    };
+
+    let asm_string = format!("@{} D=A @{} D=D+M @R13 M=D {} D=M @R13 A=M M=D", position, base, sp_down());
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+// The pointer to the base of local, argument, this and that.
+fn base_pointer(segment: Segment) -> Option<&'static str> {
+    match segment {
+        Segment::Local => Some("LCL"),
+        Segment::Argument => Some("ARG"),
+        Segment::This => Some("THIS"),
+        Segment::That => Some("THAT"),
+        _ => None,
+    }
+}
+
+// The address of temp, pointer and static, that is known while translating.
+fn fixed_address(segment: Segment, position: u32, file: &str) -> String {
+    match segment {
+        Segment::Pointer if position == 0 => "THIS".to_string(),
+        Segment::Pointer => "THAT".to_string(),
+        Segment::Static => format!("{}.{}", file, position),
+        _ => (5 + position).to_string(),
+    }
+}
+
+fn write_label(label: &str) -> String {
+    format!("\n({})", label)
+}
+fn write_branch(condition: BranchKind, label: &str) -> String {
+    let comment = format!("\n// JMP to LABEL: {}", label);
+    let condition_asm = match condition {
+        BranchKind::Goto => format!("@{} 0;JMP", label),
+        BranchKind::IfGoto => format!("{} D=M @{} D;JNE", sp_down(), label),
+    };
+    comment + &asm_new_line_concat(&condition_asm)
+}
+
+fn write_function(name: &str, locals: u32) -> String {
+    let comment = format!("\n// Function '{}' with {} local variables", name, locals);
+    let label = format!("({})", name);
+    // Set n-locals to 0
+    let set_locals: String = (0..locals).fold("".to_string(), |zeros, l| format!("{} @{:?} D=A @LCL A=D+M M=0", zeros, l));
+    let set_sp_after_lcl = format!("@{} D=A @SP M=D+M", locals);
+
+    comment
+        + &asm_new_line_concat(&label)
+        + &asm_new_line_concat(&set_locals)
+        + &asm_new_line_concat(&set_sp_after_lcl)
+}
+
+fn write_return() -> String {
+    let comment = "\n// RETURN".to_string();
+    // Put last value at position of ARG, move SP right this position after.
+    let restore_sp = format!("{} @SP A=M D=M @ARG A=M M=D @ARG D=M @SP M=D {}", sp_down(), sp_up());
+    // Restore rest of saved frame values.
+    // (By substracting 1-5 from LCL)
+    let store_return_temp = "@5 D=A @LCL A=M-D D=M @R15 M=D".to_string();
+    let restore_that = "@1 D=A @LCL A=M-D D=M @THAT M=D".to_string();
+    let restore_this = "@2 D=A @LCL A=M-D D=M @THIS M=D".to_string();
+    let restore_arg = "@3 D=A @LCL A=M-D D=M @ARG M=D".to_string();
+    let restore_lcl = "@4 D=A @LCL A=M-D D=M @LCL M=D".to_string();
+    let goto_return = "@R15 A=M 0;JMP".to_string();
+
+    comment
+        + &asm_new_line_concat(&store_return_temp)
+        + &asm_new_line_concat(&restore_sp)
+        + &asm_new_line_concat(&restore_that) 
+        + &asm_new_line_concat(&restore_this) 
+        + &asm_new_line_concat(&restore_arg) 
+        + &asm_new_line_concat(&restore_lcl)
+        + &asm_new_line_concat(&goto_return)
+
+}
+
+// `return_label` marks the return-address and has to be unique.
+fn write_call(name: &str, args: u32, return_label: &str) -> String {
+    let comment = format!("\n// Call '{}' with {} args", name, args);
+    // Save callers frame.
+    let push_return_add = format!("@{} D=A @SP A=M M=D {}", return_label, sp_up());
+    let push_lcl = format!("@LCL D=M @SP A=M M=D {}", sp_up());
+    let push_arg = format!("@ARG D=M @SP A=M M=D {}", sp_up());
+    let push_this = format!("@THIS D=M @SP A=M M=D {}", sp_up());
+    let push_that = format!("@THAT D=M @SP A=M M=D {}", sp_up());
+    let set_lcl = "@SP D=M @LCL M=D".to_string();
+    let set_arg = format!("@5 D=A @{} D=D+A @LCL D=M-D @ARG M=D", args);
+    let call = format!("@{} 0;JMP", name);
+    let return_label = format!("({})", return_label);
+    comment
+    + &asm_new_line_concat(&push_return_add)
+    + &asm_new_line_concat(&push_lcl)
+    + &asm_new_line_concat(&push_arg)
+    + &asm_new_line_concat(&push_this)
+    + &asm_new_line_concat(&push_that)
+    + &asm_new_line_concat(&set_lcl)
+    + &asm_new_line_concat(&set_arg)
+    + &asm_new_line_concat(&call)
+    + &asm_new_line_concat(&return_label)
+}
+
+// Shared routines: `$$CALL`, `$$RETURN`, `$$EQ`, `$$GT` and `$$LT` exist once at the end of the
+// program and the commands jump there. The return address of a routine is passed in D.
+// A call jumps to the stub of the called function (`$$CALL.<function>.<args>`, one for
+// every function and number of arguments in `calls`), that pushes the return address
+// and hands the number of arguments in R13 and the function in D on to `$$CALL`.
+// An endless loop in front of them ends a program, that runs past it's last command.
+pub fn write_routines(calls: &[(&str, u32)]) -> String {
+    let comment = "\n// Shared routines (the program ends in front of them)".to_string();
+    let end = "($$END) @$$END 0;JMP";
+
+    // Like write_call, after the return address has been pushed.
+    let push_pointers = ["LCL", "ARG", "THIS", "THAT"]
+        .iter()
+        .map(|pointer| format!("@{} D=M @SP A=M M=D {}", pointer, sp_up()))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let set_pointers = "@SP D=M @LCL M=D @R13 D=M @5 D=D+A @SP D=M-D @ARG M=D";
+    let call = format!("($$CALL) @R14 M=D {} {} @R14 A=M 0;JMP", push_pointers, set_pointers);
+
+    let compares: String = [ArithOp::Eq, ArithOp::Gt, ArithOp::Lt]
+        .iter()
+        .map(|op| {
+            let routine = format!("$${}", op.to_string().to_uppercase());
+            let asm = format!("({}) @R15 M=D {} {} {} @R15 A=M 0;JMP", routine, sp_down(), eq_gt_lt(*op, &routine), sp_up());
+            asm_new_line_concat(&asm)
+        })
+        .collect();
+
+    let stubs: String = calls
+        .iter()
+        .map(|(name, args)| {
+            let asm = format!("({}) {} M=D @{} D=A @R13 M=D @{} D=A @$$CALL 0;JMP", call_stub(name, *args), short_push(), args, name);
+            asm_new_line_concat(&asm)
+        })
+        .collect();
+
+    comment
+        + &asm_new_line_concat(end)
+        + &asm_new_line_concat(&call)
+        + "\n($$RETURN)" + &write_return()
+        + &compares
+        + &stubs
+}
+
+fn call_stub(name: &str, args: u32) -> String {
+    format!("$$CALL.{}.{}", name, args)
+}
+
+fn write_shared_call(name: &str, args: u32, return_label: &str) -> String {
+    let comment = format!("\n// Call '{}' with {} args", name, args);
+    let asm = format!("@{} D=A @{} 0;JMP ({})", return_label, call_stub(name, args), return_label);
+    comment + &asm_new_line_concat(&asm)
+}
+
+fn write_shared_return() -> String {
+    "\n// RETURN".to_string() + &asm_new_line_concat("@$$RETURN 0;JMP")
+}
+
+// `label` marks the return-address and has to be unique.
+fn write_shared_compare(method: ArithOp, label: &str) -> String {
+    let comment = format!("\n// {}", method);
+    let asm = format!("@{} D=A @$${} 0;JMP ({})", label, method.to_string().to_uppercase(), label);
+    comment + &asm_new_line_concat(&asm)
+}
+
+// With shared routines the other commands are written in their shortest form, too
+// (the inline translation keeps the longer one, the peephole optimizer relies on it).
+// A push increments SP first and writes the value below it, the address of the first
+// two elements of local, argument, this and that is taken from their pointer directly.
+fn short_push() -> String {
+    "@SP M=M+1 A=M-1".to_string()
+}
+
+// Loads the address of the first two elements of local, argument, this and that into A.
+fn pointed_address(segment: Segment, position: u32) -> Option<String> {
+    let base = base_pointer(segment)?;
+    match position {
+        0 => Some(format!("@{} A=M", base)),
+        1 => Some(format!("@{} A=M+1", base)),
+        _ => None,
+    }
+}
+
+fn write_short_push(segment: Segment, position: u32, file: &str) -> String {
+    let comment = format!("\n// push {} {}", segment, position);
+    let asm_string = match (segment, base_pointer(segment), pointed_address(segment, position)) {
+        (Segment::Constant, ..) if position <= 1 => format!("{} M={}", short_push(), position),
+        (Segment::Constant, ..) => format!("@{} D=A {} M=D", position, short_push()),
+        (_, _, Some(address)) => format!("{} D=M {} M=D", address, short_push()),
+        (_, Some(base), None) => format!("@{} D=M @{} A=D+A D=M {} M=D", base, position, short_push()),
+        _ => format!("@{} D=M {} M=D", fixed_address(segment, position, file), short_push()),
+    };
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+// Only for the segments and positions with a `pointed_address`.
+fn write_short_pop(segment: Segment, position: u32) -> String {
+    let comment = format!("\n// pop {} {}", segment, position);
+    let address = pointed_address(segment, position).expect("the address of the first two elements is pointed at");
+    let asm_string = format!("{} D=M {} M=D", sp_down(), address);
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+// The operations work on the top of the stack in place.
+fn write_short_arithmetic(method: ArithOp) -> String {
+    let comment = format!("\n// {}", method);
+    let asm_string = match method {
+        ArithOp::Neg => "@SP A=M-1 M=-M".to_string(),
+        ArithOp::Not => "@SP A=M-1 M=!M".to_string(),
+        _ => format!("{} D=M A=A-1 {}", sp_down(), binary_operation(method)),
+    };
+    comment + &asm_new_line_concat(&asm_string)
+}
+
+// The locals start at SP, they are set to 0 in a run.
+fn write_short_function(name: &str, locals: u32) -> String {
+    let comment = format!("\n// Function '{}' with {} local variables", name, locals);
+    let label = format!("({})", name);
+    if locals == 0 {
+        return comment + &asm_new_line_concat(&label);
+    }
+    let set_locals = format!("@SP A=M{} D=A @SP M=D", " M=0 A=A+1".repeat(locals as usize));
+    comment
+        + &asm_new_line_concat(&label)
+        + &asm_new_line_concat(&set_locals)
+}
+
+// Helper to split a string (on whitespace) and concat it again with \n .
+fn asm_new_line_concat(asm_string: &str) -> String {
+    asm_string.split(" ").fold("".to_string(), |asm, inst| format!("{}\n{}", asm, inst))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test bootstrap-code
+    #[test]
+    fn write_bootstrap_code_works() {
+        let bootstrap = write_bootstrap();
+        assert!(bootstrap.starts_with("\n// Set SP to 256 and call Sys.init()\n@256\nD=A\n@SP\nM=D\n"));
+        assert!(bootstrap.ends_with(&write_call("Sys.init", 0, "Bootstrap$$ret.0")));
+    }
+
+    // test translation of arithmetic commands to ASM
+    #[test]
+    fn add_com() {
+        assert_eq!(write_arithmetic(ArithOp::Add, &mut Labels::new("Test")), "\n// add\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nM=D+M\n@SP\nM=M+1");
+    }
+    #[test]
+    fn sub_com() {
+        assert_eq!(write_arithmetic(ArithOp::Sub, &mut Labels::new("Test")), "\n// sub\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nM=M-D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn neg_com() {
+        assert_eq!(write_arithmetic(ArithOp::Neg, &mut Labels::new("Test")), "\n// neg\n@SP\nAM=M-1\nD=0\nM=D-M\n@SP\nM=M+1");
+    }
+    #[test]
+    fn eq_com() {
+        assert_eq!(write_arithmetic(ArithOp::Eq, &mut Labels::new("Test")), "\n// eq\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nD=M-D\n@Test$$CMP.0.TRUE\nD;JEQ\n@SP\nA=M\nM=0\n@Test$$CMP.0.NEXT\n0;JMP\n(Test$$CMP.0.TRUE)\n@SP\nA=M\nD=0\nM=D-1\n(Test$$CMP.0.NEXT)\n@SP\nM=M+1");
+    }
+    #[test]
+    fn gt_com() {
+        assert_eq!(write_arithmetic(ArithOp::Gt, &mut Labels::new("Test")), "\n// gt\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nD=M-D\n@Test$$CMP.0.TRUE\nD;JGT\n@SP\nA=M\nM=0\n@Test$$CMP.0.NEXT\n0;JMP\n(Test$$CMP.0.TRUE)\n@SP\nA=M\nD=0\nM=D-1\n(Test$$CMP.0.NEXT)\n@SP\nM=M+1");
+    }
+    #[test]
+    fn lt_com() {
+        assert_eq!(write_arithmetic(ArithOp::Lt, &mut Labels::new("Test")), "\n// lt\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nD=M-D\n@Test$$CMP.0.TRUE\nD;JLT\n@SP\nA=M\nM=0\n@Test$$CMP.0.NEXT\n0;JMP\n(Test$$CMP.0.TRUE)\n@SP\nA=M\nD=0\nM=D-1\n(Test$$CMP.0.NEXT)\n@SP\nM=M+1");
+    }
+    #[test]
+    fn or_com() {
+        assert_eq!(write_arithmetic(ArithOp::Or, &mut Labels::new("Test")), "\n// or\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nM=D|M\n@SP\nM=M+1");
+    }
+    #[test]
+    fn and_com() {
+        assert_eq!(write_arithmetic(ArithOp::And, &mut Labels::new("Test")), "\n// and\n@SP\nAM=M-1\nD=M\n@SP\nAM=M-1\nM=D&M\n@SP\nM=M+1");
+    }
+    #[test]
+    fn not_com() {
+        assert_eq!(write_arithmetic(ArithOp::Not, &mut Labels::new("Test")), "\n// not\n@SP\nAM=M-1\nM=!M\n@SP\nM=M+1");
+    }
+
+    // Tests push-commands
+    #[test]
+    fn push_static_works() {
+        let comment = "\n// push constant 99".to_string();
+        let push_static_99_string = asm_new_line_concat("@99 D=A @SP A=M M=D @SP M=M+1");
+        assert_eq!(write_push(Segment::Constant, 99, ""), comment + &push_static_99_string);
+    }
+    #[test]
+    fn push_local_works() {
+        assert_eq!(write_push(Segment::Local, 0, ""), "\n// push local 0\n@0\nD=A\n@LCL\nA=D+M\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_temp_works() {
+        assert_eq!(write_push(Segment::Temp, 6, ""), "\n// push temp 6\n@6\nD=A\n@5\nA=D+A\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_arg_works() {
+        assert_eq!(write_push(Segment::Argument, 1, ""), "\n// push argument 1\n@1\nD=A\n@ARG\nA=D+M\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_this_works() {
+        assert_eq!(write_push(Segment::This, 6, ""), "\n// push this 6\n@6\nD=A\n@THIS\nA=D+M\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_that_works() {
+        assert_eq!(write_push(Segment::That, 5, ""), "\n// push that 5\n@5\nD=A\n@THAT\nA=D+M\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_pointer_0_works() {
+        assert_eq!(write_push(Segment::Pointer, 0, ""), "\n// push pointer 0\n@THIS\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_pointer_1_works() {
+        assert_eq!(write_push(Segment::Pointer, 1, ""), "\n// push pointer 1\n@THAT\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+    #[test]
+    fn push_static_88_works() {
+        assert_eq!(write_push(Segment::Static, 88, "halloele"), "\n// push static 88\n@halloele.88\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1");
+    }
+
+    // Tests pop-commands
+    #[test]
+    fn pop_to_local() {
+        assert_eq!(write_pop(Segment::Local, 0, ""), "\n// pop local 0\n@0\nD=A\n@LCL\nD=D+M\n@R13\nM=D\n@SP\nAM=M-1\nD=M\n@R13\nA=M\nM=D");
+    }
+    #[test]
+    fn pop_to_arg() {
+        assert_eq!(write_pop(Segment::Argument, 1, ""), "\n// pop argument 1\n@1\nD=A\n@ARG\nD=D+M\n@R13\nM=D\n@SP\nAM=M-1\nD=M\n@R13\nA=M\nM=D");
+    }
+    #[test]
+    fn pop_to_temp() {
+        assert_eq!(write_pop(Segment::Temp, 6, ""), "\n// pop temp 6\n@SP\nAM=M-1\nD=M\n@11\nM=D");
+    }
+    #[test]
+    fn pop_to_this() {
+        assert_eq!(write_pop(Segment::This, 6, ""), "\n// pop this 6\n@6\nD=A\n@THIS\nD=D+M\n@R13\nM=D\n@SP\nAM=M-1\nD=M\n@R13\nA=M\nM=D");
+    }
+    #[test]
+    fn pop_to_that() {
+        assert_eq!(write_pop(Segment::That, 5, ""), "\n// pop that 5\n@5\nD=A\n@THAT\nD=D+M\n@R13\nM=D\n@SP\nAM=M-1\nD=M\n@R13\nA=M\nM=D");
+    }
+    #[test]
+    fn pop_pointer_0() {
+        assert_eq!(write_pop(Segment::Pointer, 0, ""), "\n// pop pointer 0\n@SP\nAM=M-1\nD=M\n@THIS\nM=D");
+    }
+    #[test]
+    fn pop_pointer_1() {
+        assert_eq!(write_pop(Segment::Pointer, 1, ""), "\n// pop pointer 1\n@SP\nAM=M-1\nD=M\n@THAT\nM=D");
+    }
+    #[test]
+    fn pop_static_5() {
+        assert_eq!(write_pop(Segment::Static, 5, "bla"), "\n// pop static 5\n@SP\nAM=M-1\nD=M\n@bla.5\nM=D");
+    }
+
+    // Test Branch-commands
+    #[test]
+    fn if_goto_label_works() {
+        assert_eq!(write_branch(BranchKind::IfGoto, "LABEL_BAMBI"), "\n// JMP to LABEL: LABEL_BAMBI\n@SP\nAM=M-1\nD=M\n@LABEL_BAMBI\nD;JNE");
+    }
+    #[test]
+    fn goto_label_works() {
+        assert_eq!(write_branch(BranchKind::Goto, "LABEL_BAMBI"), "\n// JMP to LABEL: LABEL_BAMBI\n@LABEL_BAMBI\n0;JMP");
+    }
+    // Test Label-command
+    #[test]
+    fn label_my_label_works() {
+        assert_eq!(write_label("A_GREAT_LABEL"), "\n(A_GREAT_LABEL)");
+    }
+    // Test Function-command
+    #[test]
+    fn write_function_works() {
+        assert_eq!(write_function("cals_some_stuff.0", 3), "\n// Function \'cals_some_stuff.0\' with 3 local variables\n(cals_some_stuff.0)\n\n@0\nD=A\n@LCL\nA=D+M\nM=0\n@1\nD=A\n@LCL\nA=D+M\nM=0\n@2\nD=A\n@LCL\nA=D+M\nM=0\n@3\nD=A\n@SP\nM=D+M");
+    }
+    // Test Return-command
+    #[test]
+    fn write_return_works() {
+        assert_eq!(write_return(), "\n// RETURN\n@5\nD=A\n@LCL\nA=M-D\nD=M\n@R15\nM=D\n@SP\nAM=M-1\n@SP\nA=M\nD=M\n@ARG\nA=M\nM=D\n@ARG\nD=M\n@SP\nM=D\n@SP\nM=M+1\n@1\nD=A\n@LCL\nA=M-D\nD=M\n@THAT\nM=D\n@2\nD=A\n@LCL\nA=M-D\nD=M\n@THIS\nM=D\n@3\nD=A\n@LCL\nA=M-D\nD=M\n@ARG\nM=D\n@4\nD=A\n@LCL\nA=M-D\nD=M\n@LCL\nM=D\n@R15\nA=M\n0;JMP");
+    }
+    // Test Call-command
+    #[test]
+    fn write_call_works() {
+        assert_eq!(write_call("theGreatFunc", 4, "Main.main$ret.11"), "\n// Call \'theGreatFunc\' with 4 args\n@Main.main$ret.11\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@LCL\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@ARG\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THIS\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@THAT\nD=M\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@SP\nD=M\n@LCL\nM=D\n@5\nD=A\n@4\nD=D+A\n@LCL\nD=M-D\n@ARG\nM=D\n@theGreatFunc\n0;JMP\n(Main.main$ret.11)");
+    }
+
+    // Test shared routines
+    #[test]
+    fn shared_call_jumps_to_the_stub() {
+        assert_eq!(write_shared_call("Main.foo", 2, "Main.main$$ret.0"), "\n// Call 'Main.foo' with 2 args\n@Main.main$$ret.0\nD=A\n@$$CALL.Main.foo.2\n0;JMP\n(Main.main$$ret.0)");
+    }
+    #[test]
+    fn shared_commands_replace_inline_ones() {
+        let mut labels = Labels::new("Main");
+        assert_eq!(write_asm(&Arith(ArithOp::Gt), "Main", &mut labels, Routines::Shared), "\n// gt\n@Main$$CMP.0\nD=A\n@$$GT\n0;JMP\n(Main$$CMP.0)");
+        assert_eq!(write_asm(&Return, "Main", &mut labels, Routines::Shared), "\n// RETURN\n@$$RETURN\n0;JMP");
+        assert!(write_asm(&Arith(ArithOp::Add), "Main", &mut labels, Routines::Shared).starts_with("\n// add"));
+    }
+    #[test]
+    fn shared_commands_are_short() {
+        let mut labels = Labels::new("Main");
+        let mut shared = |command: Com| write_asm(&command, "Main", &mut labels, Routines::Shared).replace('\n', " ");
+        assert_eq!(shared(Arith(ArithOp::Sub)), " // sub @SP AM=M-1 D=M A=A-1 M=M-D");
+        assert_eq!(shared(Arith(ArithOp::Neg)), " // neg @SP A=M-1 M=-M");
+        assert_eq!(shared(Push(Segment::Constant, 1)), " // push constant 1 @SP M=M+1 A=M-1 M=1");
+        assert_eq!(shared(Push(Segment::Constant, 7)), " // push constant 7 @7 D=A @SP M=M+1 A=M-1 M=D");
+        assert_eq!(shared(Push(Segment::Argument, 1)), " // push argument 1 @ARG A=M+1 D=M @SP M=M+1 A=M-1 M=D");
+        assert_eq!(shared(Push(Segment::That, 5)), " // push that 5 @THAT D=M @5 A=D+A D=M @SP M=M+1 A=M-1 M=D");
+        assert_eq!(shared(Push(Segment::Temp, 6)), " // push temp 6 @11 D=M @SP M=M+1 A=M-1 M=D");
+        assert_eq!(shared(Pop(Segment::Local, 0)), " // pop local 0 @SP AM=M-1 D=M @LCL A=M M=D");
+        // The other positions are popped like inline
+        assert_eq!(shared(Pop(Segment::Local, 2)), write_pop(Segment::Local, 2, "Main").replace('\n', " "));
+        assert_eq!(shared(Function("Main.f".to_string(), 0)), " // Function 'Main.f' with 0 local variables (Main.f)");
+        assert_eq!(shared(Function("Main.g".to_string(), 2)), " // Function 'Main.g' with 2 local variables (Main.g) @SP A=M M=0 A=A+1 M=0 A=A+1 D=A @SP M=D");
+    }
+    #[test]
+    fn routines_start_behind_an_endless_loop() {
+        let routines = write_routines(&[("Main.foo", 2)]);
+        assert!(routines.starts_with("\n// Shared routines (the program ends in front of them)\n($$END)\n@$$END\n0;JMP\n($$CALL)"));
+        assert!(routines.contains("\n($$RETURN)\n// RETURN\n"));
+        assert!(routines.contains("\n($$LT.NEXT)\n@SP\nM=M+1\n@R15\nA=M\n0;JMP"));
+        assert!(routines.ends_with("\n($$CALL.Main.foo.2)\n@SP\nM=M+1\nA=M-1\nM=D\n@2\nD=A\n@R13\nM=D\n@Main.foo\nD=A\n@$$CALL\n0;JMP"));
+    }
+
+    #[test]
+    fn labels_are_scoped_by_function() {
+        let mut labels = Labels::new("Main");
+        write_asm(&Function("Main.main".to_string(), 0), "Main", &mut labels, Routines::Inline);
+        assert_eq!(write_asm(&Label("LOOP".to_string()), "Main", &mut labels, Routines::Inline), "\n(Main.main$LOOP)");
+        assert!(write_asm(&Call("Main.foo".to_string(), 0), "Main", &mut labels, Routines::Inline).ends_with("\n(Main.main$$ret.0)"));
+        assert!(write_asm(&Arith(ArithOp::Eq), "Main", &mut labels, Routines::Inline).contains("\n(Main.main$$CMP.1.TRUE)"));
+    }
+
+    // Helper-functions
+    #[test]
+    fn concat_asm_string_with_new_line() {
+        assert_eq!(asm_new_line_concat("@sp test works just fine"), "\n@sp\ntest\nworks\njust\nfine");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,81 @@
+//! Generates the labels of a translated file. Every label is scoped by the
+//! function it appears in (or by the file, before the first function), so
+//! `label LOOP` inside of `Foo.bar` becomes `Foo.bar$LOOP` and the labels
+//! the translator needs itself (comparisons, return addresses) are numbered
+//! per function like `Foo.bar$$ret.0`. As function names are unique in a
+//! program, the labels of different files can not collide. A label of the
+//! vm-file can not start with `$` (see the parser), so it can not collide
+//! with the labels of the translator either.
+
+#[derive(Debug, Clone)]
+pub struct Labels {
+    scope: String,
+    counter: usize,
+}
+
+impl Labels {
+    /// Starts the labels of a file, `file_stem` is the scope until the first function.
+    pub fn new(file_stem: &str) -> Self {
+        Labels { scope: file_stem.to_string(), counter: 0 }
+    }
+
+    /// All following labels belong to `function`.
+    pub fn enter(&mut self, function: &str) {
+        self.scope = function.to_string();
+        self.counter = 0;
+    }
+
+    /// The label of a `label`, `goto` or `if-goto` command.
+    ///
+    /// # Examples
+    /// ```
+    /// use vm_translator::translator::labels::Labels;
+    ///
+    /// let mut labels = Labels::new("Main");
+    /// assert_eq!(labels.user("LOOP"), "Main$LOOP");
+    /// labels.enter("Main.main");
+    /// assert_eq!(labels.user("LOOP"), "Main.main$LOOP");
+    /// ```
+    pub fn user(&self, label: &str) -> String {
+        format!("{}${}", self.scope, label)
+    }
+
+    /// A new label, that has not been handed out before in this scope.
+    ///
+    /// # Examples
+    /// ```
+    /// use vm_translator::translator::labels::Labels;
+    ///
+    /// let mut labels = Labels::new("Main");
+    /// labels.enter("Main.main");
+    /// assert_eq!(labels.next("ret"), "Main.main$$ret.0");
+    /// assert_eq!(labels.next("CMP"), "Main.main$$CMP.1");
+    /// ```
+    pub fn next(&mut self, kind: &str) -> String {
+        let label = format!("{}$${}.{}", self.scope, kind, self.counter);
+        self.counter += 1;
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_restarts_in_every_function() {
+        let mut labels = Labels::new("Main");
+        assert_eq!(labels.next("CMP"), "Main$$CMP.0");
+        labels.enter("Main.foo");
+        assert_eq!(labels.next("CMP"), "Main.foo$$CMP.0");
+        labels.enter("Main.bar");
+        assert_eq!(labels.next("ret"), "Main.bar$$ret.0");
+    }
+    #[test]
+    fn user_labels_do_not_collide_with_generated_ones() {
+        let mut labels = Labels::new("Main");
+        labels.enter("Foo.bar");
+        assert_ne!(labels.user("ret.0"), labels.next("ret"));
+        assert_ne!(labels.user("CMP.1.TRUE"), format!("{}.TRUE", labels.next("CMP")));
+    }
+}
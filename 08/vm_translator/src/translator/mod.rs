@@ -0,0 +1,4 @@
+pub mod parser;
+pub mod code_writer;
+pub mod labels;
+pub mod optimizer;
\ No newline at end of file
@@ -0,0 +1,261 @@
+//! A peephole optimizer for the translated assembly. Every command of the
+//! translation starts from scratch (it loads `@SP`, pops it's operands and
+//! pushes it's result), so neighbouring commands undo each other a lot.
+//! The optimizer replaces these windows of instructions by shorter ones:
+//! - an `SP` increment followed by a decrement only addresses the top,
+//! - `push x` followed by a pop keeps the value in `D` instead of the stack,
+//! - `neg`, `not` and the second half of `add`, `sub`, `and`, `or` work on the top in place,
+//! - the locals of a function are set to 0 with a run of `M=0 A=A+1`.
+//!
+//! The windows end with the instruction, that has just been added, so a replacement
+//! can complete the window of the next rule and the whole program takes one pass.
+//! Labels are never part of a window, so a jump can not land inside of one.
+//! Comments stay where they are. The rules rely on every command loading the
+//! address it needs itself, and the stack above `SP` is not kept up to date
+//! (a fused push does not write it's value), nothing below `SP` changes.
+
+use crate::source_map::SourceMap;
+use std::fmt;
+
+/// The number of instructions before and after the optimization (labels are no instructions).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Report {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let saved = self.before - self.after;
+        let percent = (saved * 100).checked_div(self.before).unwrap_or(0);
+        write!(f, "{} instructions before, {} after the optimization ({} less, -{}%)", self.before, self.after, saved, percent)
+    }
+}
+
+/// Optimizes translated assembly and reports how many instructions it saved.
+///
+/// # Examples
+/// ```
+/// use vm_translator::translate_str;
+/// use vm_translator::translator::optimizer::optimize;
+///
+/// let asm = translate_str("push constant 7\npop temp 0", "Main").unwrap();
+/// let (optimized, report) = optimize(&asm);
+/// assert_eq!(optimized, "\n// push constant 7\n@7\nD=A\n// pop temp 0\n@5\nM=D");
+/// assert_eq!((report.before, report.after), (12, 4));
+/// ```
+pub fn optimize(asm: &str) -> (String, Report) {
+    let optimizer = Optimizer::run(asm);
+    let report = optimizer.report(asm);
+    (optimizer.lines.join("\n"), report)
+}
+
+/// Optimizes translated assembly like `optimize` and moves the source map of
+/// the assembly along. A replacement keeps the origin of the instructions it replaces.
+///
+/// # Examples
+/// ```
+/// use vm_translator::translate_str;
+/// use vm_translator::source_map::{ Origin, SourceMap };
+/// use vm_translator::translator::optimizer::optimize_with_map;
+///
+/// let asm = translate_str("push constant 7\npop temp 0", "Main").unwrap();
+/// let mut map = SourceMap::new();
+/// map.add(2, Some(Origin::new("Main.vm", 1)));
+/// map.add(9, Some(Origin::new("Main.vm", 2)));
+/// let (optimized, map, _) = optimize_with_map(&asm, &map);
+/// assert_eq!(optimized, "\n// push constant 7\n@7\nD=A\n// pop temp 0\n@5\nM=D");
+/// assert_eq!(map.to_string(), "2 Main.vm:1\n5 Main.vm:2");
+/// ```
+pub fn optimize_with_map(asm: &str, map: &SourceMap) -> (String, SourceMap, Report) {
+    let optimizer = Optimizer::run(asm);
+    let report = optimizer.report(asm);
+    let mut optimized_map = SourceMap::new();
+    for (idx, origin) in optimizer.origins.iter().enumerate() {
+        optimized_map.add(idx + 1, map.lookup(*origin).cloned());
+    }
+    (optimizer.lines.join("\n"), optimized_map, report)
+}
+
+// The longest window a rule looks at.
+const WINDOW: usize = 8;
+
+// A rule looks at the last instructions. If it matches, it returns how many
+// of them it replaces and the shorter replacement.
+type Rule = fn(&[&str]) -> Option<(usize, Vec<String>)>;
+
+const RULES: [Rule; 6] = [cancel_sp, reuse_address, fuse_push_pop, in_place, pop_in_place, zero_locals];
+
+struct Optimizer {
+    lines: Vec<String>,
+    // The indices of the lines, that are code (no comments or empty lines).
+    code: Vec<usize>,
+    // The line (starting at 1) of the unoptimized assembly every line comes from.
+    origins: Vec<usize>,
+}
+
+impl Optimizer {
+    fn run(asm: &str) -> Self {
+        let mut optimizer = Optimizer { lines: Vec::new(), code: Vec::new(), origins: Vec::new() };
+        for (idx, line) in asm.split('\n').enumerate() {
+            optimizer.add(line, idx + 1);
+        }
+        optimizer
+    }
+
+    fn report(&self, asm: &str) -> Report {
+        let before = asm.split('\n').filter(|line| is_instruction(line)).count();
+        let after = self.lines.iter().filter(|line| is_instruction(line)).count();
+        Report { before, after }
+    }
+
+    fn add(&mut self, line: &str, origin: usize) {
+        if is_code(line) {
+            self.code.push(self.lines.len());
+        }
+        self.lines.push(line.to_string());
+        self.origins.push(origin);
+        while let Some((length, replacement)) = self.matching_rule() {
+            self.replace(length, replacement);
+        }
+    }
+
+    fn matching_rule(&self) -> Option<(usize, Vec<String>)> {
+        let first = self.code.len().saturating_sub(WINDOW);
+        let window: Vec<&str> = self.code[first..].iter().map(|idx| self.lines[*idx].as_str()).collect();
+        RULES.iter().find_map(|rule| rule(&window))
+    }
+
+    // The replacement takes the place of the first replaced instructions.
+    fn replace(&mut self, length: usize, replacement: Vec<String>) {
+        let first = self.code[self.code.len() - length];
+        let tail = self.lines.split_off(first);
+        let origins = self.origins.split_off(first);
+        self.code.truncate(self.code.len() - length);
+        let mut replacement = replacement.into_iter();
+        for (line, origin) in tail.into_iter().zip(origins) {
+            if !is_code(&line) {
+                self.lines.push(line);
+                self.origins.push(origin);
+            } else if let Some(instruction) = replacement.next() {
+                self.code.push(self.lines.len());
+                self.lines.push(instruction);
+                self.origins.push(origin);
+            }
+        }
+    }
+}
+
+fn is_code(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with("//")
+}
+
+fn is_instruction(line: &str) -> bool {
+    is_code(line) && !line.starts_with('(')
+}
+
+// A computation, that only writes into M (and does not jump).
+fn writes_only_m(instruction: &str) -> bool {
+    instruction.starts_with("M=") && !instruction.contains(';')
+}
+
+fn to_strings(instructions: &[&str]) -> Vec<String> {
+    instructions.iter().map(|instruction| instruction.to_string()).collect()
+}
+
+// Incrementing and decrementing SP leaves SP as it was and A pointing at the top.
+fn cancel_sp(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    if window.ends_with(&["@SP", "M=M+1", "@SP", "AM=M-1"]) { Some((4, to_strings(&["@SP", "A=M"]))) } else { None }
+}
+
+// A still points at the address of SP, after only M has been written.
+fn reuse_address(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    match window {
+        [.., "@SP", address @ ("A=M" | "AM=M-1"), computation, "@SP", "A=M"] if writes_only_m(computation) => {
+            Some((5, to_strings(&["@SP", address, computation])))
+        },
+        _ => None,
+    }
+}
+
+// What is left of `push x` and the first pop of the next command: the value is still in D.
+fn fuse_push_pop(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    if window.ends_with(&["@SP", "A=M", "M=D", "D=M"]) { Some((4, Vec::new())) } else { None }
+}
+
+// Popping, computing and pushing again works on the top of the stack.
+fn in_place(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    match window {
+        [.., "@SP", "AM=M-1", computation, "@SP", "M=M+1"] if writes_only_m(computation) && !computation[2..].contains('A') => {
+            Some((5, to_strings(&["@SP", "A=M-1", computation])))
+        },
+        _ => None,
+    }
+}
+
+// A computation in place, that is popped right away.
+fn pop_in_place(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    match window {
+        [.., "@SP", "A=M-1", computation, "@SP", "AM=M-1", "D=M"] if writes_only_m(computation) => Some((6, to_strings(&["@SP", "AM=M-1", computation, "D=M"]))),
+        _ => None,
+    }
+}
+
+// The locals of a function start at LCL (which is SP at this point) and follow each other,
+// the translation sets them with `@i D=A @LCL A=D+M M=0` (push local reads them with `D=M`).
+fn zero_locals(window: &[&str]) -> Option<(usize, Vec<String>)> {
+    match window {
+        [.., "@0", "D=A", "@LCL", "A=D+M", "M=0"] => Some((5, to_strings(&["@LCL", "A=M", "M=0"]))),
+        [.., "A=M" | "A=A+1", "M=0", local, "D=A", "@LCL", "A=D+M", "M=0"] if local.starts_with('@') => {
+            Some((5, to_strings(&["A=A+1", "M=0"])))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optimize_code(asm: &str) -> String {
+        optimize(&asm.replace(' ', "\n")).0.replace('\n', " ")
+    }
+
+    #[test]
+    fn fuses_push_and_pop() {
+        assert_eq!(optimize_code("@7 D=A @SP A=M M=D @SP M=M+1 @SP AM=M-1 D=M @5 M=D"), "@7 D=A @5 M=D");
+    }
+    #[test]
+    fn pushes_into_arithmetic() {
+        // push constant 8, add
+        let asm = "@8 D=A @SP A=M M=D @SP M=M+1 @SP AM=M-1 D=M @SP AM=M-1 M=D+M @SP M=M+1";
+        assert_eq!(optimize_code(asm), "@8 D=A @SP A=M-1 M=D+M");
+    }
+    #[test]
+    fn pops_the_result_of_arithmetic() {
+        // not, pop temp 0
+        let asm = "@SP AM=M-1 M=!M @SP M=M+1 @SP AM=M-1 D=M @5 M=D";
+        assert_eq!(optimize_code(asm), "@SP AM=M-1 M=!M D=M @5 M=D");
+    }
+    #[test]
+    fn labels_end_windows() {
+        let asm = "@SP M=M+1 (LOOP) @SP AM=M-1";
+        assert_eq!(optimize_code(asm), asm);
+    }
+    #[test]
+    fn comments_stay() {
+        let asm = "\n// push constant 7\n@7\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n// pop temp 0\n@SP\nAM=M-1\nD=M\n@5\nM=D";
+        assert_eq!(optimize(asm).0, "\n// push constant 7\n@7\nD=A\n// pop temp 0\n@5\nM=D");
+    }
+    #[test]
+    fn zeroes_locals_in_a_run() {
+        let asm = "(Main.f) @0 D=A @LCL A=D+M M=0 @1 D=A @LCL A=D+M M=0 @2 D=A @LCL A=D+M M=0 @3 D=A @SP M=D+M";
+        assert_eq!(optimize_code(asm), "(Main.f) @LCL A=M M=0 A=A+1 M=0 A=A+1 M=0 @3 D=A @SP M=D+M");
+    }
+    #[test]
+    fn reports_the_instructions() {
+        let report = optimize("(LOOP)\n@SP\nM=M+1\n@SP\nAM=M-1").1;
+        assert_eq!(report, Report { before: 4, after: 2 });
+        assert_eq!(report.to_string(), "4 instructions before, 2 after the optimization (2 less, -50%)");
+    }
+}
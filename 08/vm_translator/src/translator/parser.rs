@@ -0,0 +1,355 @@
+use crate::error::VmError;
+use std::fmt;
+
+/// The memory segments of the VM.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Segment {
+    Constant,
+    Local,
+    Argument,
+    This,
+    That,
+    Pointer,
+    Temp,
+    Static,
+}
+
+impl Segment {
+    fn parse(segment: &str) -> Option<Segment> {
+        match segment {
+            "constant" => Some(Segment::Constant),
+            "local" => Some(Segment::Local),
+            "argument" => Some(Segment::Argument),
+            "this" => Some(Segment::This),
+            "that" => Some(Segment::That),
+            "pointer" => Some(Segment::Pointer),
+            "temp" => Some(Segment::Temp),
+            "static" => Some(Segment::Static),
+            _ => None,
+        }
+    }
+
+    /// The biggest index the segment has (if it is limited).
+    pub fn max_index(&self) -> Option<u32> {
+        match self {
+            Segment::Constant => Some(32767),
+            Segment::Pointer => Some(1),
+            Segment::Temp => Some(7),
+            _ => None,
+        }
+    }
+
+    /// The name of the segment in the VM language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Segment::Constant => "constant",
+            Segment::Local => "local",
+            Segment::Argument => "argument",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Pointer => "pointer",
+            Segment::Temp => "temp",
+            Segment::Static => "static",
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The arithmetic and logical commands.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+}
+
+impl ArithOp {
+    fn parse(command: &str) -> Option<ArithOp> {
+        match command {
+            "add" => Some(ArithOp::Add),
+            "sub" => Some(ArithOp::Sub),
+            "neg" => Some(ArithOp::Neg),
+            "eq" => Some(ArithOp::Eq),
+            "gt" => Some(ArithOp::Gt),
+            "lt" => Some(ArithOp::Lt),
+            "and" => Some(ArithOp::And),
+            "or" => Some(ArithOp::Or),
+            "not" => Some(ArithOp::Not),
+            _ => None,
+        }
+    }
+
+    /// The name of the command in the VM language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArithOp::Add => "add",
+            ArithOp::Sub => "sub",
+            ArithOp::Neg => "neg",
+            ArithOp::Eq => "eq",
+            ArithOp::Gt => "gt",
+            ArithOp::Lt => "lt",
+            ArithOp::And => "and",
+            ArithOp::Or => "or",
+            ArithOp::Not => "not",
+        }
+    }
+}
+
+impl fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// `goto` jumps always, `if-goto` only if the popped value is not 0.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BranchKind {
+    Goto,
+    IfGoto,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+// Enum with com-Variantss
+pub enum Com {
+    Empty,
+    Push(Segment, u32),
+    Pop(Segment, u32),
+    Arith(ArithOp),
+    Label(String),
+    Branch(BranchKind, String),
+    Function(String, u32),
+    Call(String, u32),
+    Return,
+}
+
+/// Writes the command in the VM language (`Empty` is an empty string).
+///
+/// # Examples
+/// ```
+/// use vm_translator::translator::parser::{Com, Segment};
+///
+/// assert_eq!(Com::Pop(Segment::Temp, 3).to_string(), "pop temp 3");
+/// ```
+impl fmt::Display for Com {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Com::Empty => Ok(()),
+            Com::Push(segment, index) => write!(f, "push {} {}", segment, index),
+            Com::Pop(segment, index) => write!(f, "pop {} {}", segment, index),
+            Com::Arith(op) => write!(f, "{}", op),
+            Com::Label(label) => write!(f, "label {}", label),
+            Com::Branch(BranchKind::Goto, label) => write!(f, "goto {}", label),
+            Com::Branch(BranchKind::IfGoto, label) => write!(f, "if-goto {}", label),
+            Com::Function(name, locals) => write!(f, "function {} {}", name, locals),
+            Com::Call(name, args) => write!(f, "call {} {}", name, args),
+            Com::Return => write!(f, "return"),
+        }
+    }
+}
+
+// Cleanes a given line (e.g. from comments)
+fn clean_line(line: &str) -> String {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with("//") {
+        return "".to_string();
+    }
+
+    let line: Vec<&str> = trimmed.split("//").collect(); 
+    line[0].trim().to_string()
+}
+
+/// Parses a given line into a `Com`. An invalid command results in
+/// a `VmError` located at `file` and `line_nr`.
+///
+/// # Examples
+/// ```
+/// use vm_translator::translator::parser::{parse_line, Com, Segment};
+///
+/// assert_eq!(parse_line("push local 2 // x", "Main.vm", 1), Ok(Com::Push(Segment::Local, 2)));
+/// assert!(parse_line("push temp 8", "Main.vm", 2).is_err());
+/// ```
+pub fn parse_line(line: &str, file: &str, line_nr: usize) -> Result<Com, VmError> {
+    let cleaned_line = clean_line(line);
+    let com = cleaned_line.trim();
+
+    if com.is_empty() {
+        return Ok(Com::Empty);
+    }
+
+    let text = com.to_string();
+    let unknown_command = || VmError::UnknownCommand { file: file.to_string(), line: line_nr, text: text.to_string() };
+    let number = |arg: &str| arg.parse::<u32>().map_err(|_| VmError::InvalidNumber {
+        file: file.to_string(), line: line_nr, text: text.to_string(), number: arg.to_string()
+    });
+    let label = |arg: &str| if is_label(arg) {
+        Ok(arg.to_string())
+    } else {
+        Err(VmError::InvalidLabel { file: file.to_string(), line: line_nr, text: text.to_string(), label: arg.to_string() })
+    };
+    let com_fields: Vec<&str> = com.split_whitespace().collect();
+
+    match com_fields[..] {
+        // Only 1 arg means Arithmetic or Return command
+        [arg0] => {
+            match arg0 {
+                "return" => Ok(Com::Return),
+                arith => ArithOp::parse(arith).map(Com::Arith).ok_or_else(unknown_command),
+            }
+        },
+        // Two args means Label or Branch
+        [arg0, arg1] => {
+            match arg0 {
+                "label" => Ok(Com::Label(label(arg1)?)),
+                "goto" => Ok(Com::Branch(BranchKind::Goto, label(arg1)?)),
+                "if-goto" => Ok(Com::Branch(BranchKind::IfGoto, label(arg1)?)),
+                _ => Err(unknown_command()),
+            }
+        },
+        // Three args means Push, Pop, Function or Call command
+        [arg0, arg1, arg2] => {
+            match arg0 {
+                "function" => Ok(Com::Function(arg1.to_string(), number(arg2)?)),
+                "call" => Ok(Com::Call(arg1.to_string(), number(arg2)?)),
+                "push" | "pop" => {
+                    let segment = Segment::parse(arg1).ok_or_else(|| VmError::UnknownSegment {
+                        file: file.to_string(), line: line_nr, text: text.to_string(), segment: arg1.to_string()
+                    })?;
+                    let index = number(arg2)?;
+                    if let Some(max) = segment.max_index().filter(|max| index > *max) {
+                        return Err(VmError::IndexOutOfRange { file: file.to_string(), line: line_nr, text, max });
+                    }
+                    match (arg0, segment) {
+                        ("push", _) => Ok(Com::Push(segment, index)),
+                        (_, Segment::Constant) => Err(VmError::PopConstant { file: file.to_string(), line: line_nr, text }),
+                        _ => Ok(Com::Pop(segment, index)),
+                    }
+                },
+                _ => Err(unknown_command()),
+            }
+        },
+        _ => Err(unknown_command()),
+    }
+}
+
+// A symbol of the assembler, that does not start with `$` (see labels)
+fn is_label(label: &str) -> bool {
+    let starts_right = label.chars().next().is_some_and(|first| !first.is_ascii_digit() && first != '$');
+    starts_right && label.chars().all(|c| c.is_ascii_alphanumeric() || "_.:$".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tests clean_line()
+    #[test]
+    fn removes_comment_line() {
+        assert_eq!(clean_line("// some nasty comment"), "".to_string());
+    }
+    #[test]
+    fn removes_comment_behind_com() {
+        assert_eq!(clean_line("push 2   // some nasty comment behind"), "push 2".to_string());
+    }
+    #[test]
+    fn removes_comment_behind_com2() {
+        assert_eq!(clean_line("pop local 0         // initializes sum = 0"), "pop local 0".to_string());
+    }
+    #[test]
+    fn removes_empty_line() {
+        assert_eq!(clean_line("\n"), "".to_string());
+    }
+
+    // Test parse_line()
+    #[test]
+    fn returns_push_com() {
+        assert_eq!(parse_line("push local 2", "Test.vm", 1), Ok(Com::Push(Segment::Local, 2)));
+    }
+    #[test]
+    fn returns_pop_com() {
+        assert_eq!(parse_line("pop static 3", "Test.vm", 1), Ok(Com::Pop(Segment::Static, 3)));
+    }
+    #[test]
+    fn returns_arithmetic_com() {
+        assert_eq!(parse_line("add", "Test.vm", 1), Ok(Com::Arith(ArithOp::Add)));
+    }
+    #[test]
+    fn returns_label_com() {
+        assert_eq!(parse_line("label MY_COOL_LABEL", "Test.vm", 1), Ok(Com::Label("MY_COOL_LABEL".to_string())));
+        assert_eq!(parse_line("goto Main.main$0.IFEND", "Test.vm", 1), Ok(Com::Branch(BranchKind::Goto, "Main.main$0.IFEND".to_string())));
+    }
+    #[test]
+    fn labels_are_symbols_without_a_leading_dollar() {
+        assert!(matches!(parse_line("label $ret.0", "Test.vm", 1), Err(VmError::InvalidLabel { .. })));
+        assert!(matches!(parse_line("goto 1LOOP", "Test.vm", 1), Err(VmError::InvalidLabel { .. })));
+        assert_eq!(parse_line("if-goto a-b", "Test.vm", 3).unwrap_err().to_string(), "Test.vm:3: 'a-b' is no valid label in 'if-goto a-b'");
+    }
+    #[test]
+    fn returns_if_goto_com() {
+        assert_eq!(parse_line("if-goto MY_COOL_LABEL", "Test.vm", 1), Ok(Com::Branch(BranchKind::IfGoto, "MY_COOL_LABEL".to_string())));
+    }
+    #[test]
+    fn returns_goto_com() {
+        assert_eq!(parse_line("goto MY_COOL_LABEL", "Test.vm", 1), Ok(Com::Branch(BranchKind::Goto, "MY_COOL_LABEL".to_string())));
+    }
+    #[test]
+    fn returns_function_com() {
+        assert_eq!(parse_line("function crazy_calc.3 2", "Test.vm", 1), Ok(Com::Function("crazy_calc.3".to_string(), 2)));
+    }
+    #[test]
+    fn returns_return_com() {
+        assert_eq!(parse_line("return", "Test.vm", 1), Ok(Com::Return));
+    }
+    #[test]
+    fn returns_call_com() {
+        assert_eq!(parse_line("call myFunc.main 4", "Test.vm", 1), Ok(Com::Call("myFunc.main".to_string(), 4)));
+    }
+
+    #[test]
+    fn returns_empty_for_comment() {
+        assert_eq!(parse_line("   // comment", "Test.vm", 1), Ok(Com::Empty));
+    }
+    #[test]
+    fn rejects_unknown_arithmetic() {
+        assert_eq!(
+            parse_line("blbla", "Test.vm", 4),
+            Err(VmError::UnknownCommand { file: "Test.vm".to_string(), line: 4, text: "blbla".to_string() }));
+    }
+    #[test]
+    fn rejects_unknown_segment() {
+        assert_eq!(
+            parse_line("push locale 1", "Test.vm", 2),
+            Err(VmError::UnknownSegment { file: "Test.vm".to_string(), line: 2, text: "push locale 1".to_string(), segment: "locale".to_string() }));
+    }
+    #[test]
+    fn rejects_invalid_index() {
+        assert!(matches!(parse_line("push local -1", "Test.vm", 1), Err(VmError::InvalidNumber { .. })));
+    }
+    #[test]
+    fn checks_ranges() {
+        assert!(parse_line("push pointer 1", "Test.vm", 1).is_ok());
+        assert!(matches!(parse_line("pop pointer 2", "Test.vm", 1), Err(VmError::IndexOutOfRange { max: 1, .. })));
+        assert!(parse_line("pop temp 7", "Test.vm", 1).is_ok());
+        assert!(matches!(parse_line("push temp 8", "Test.vm", 1), Err(VmError::IndexOutOfRange { max: 7, .. })));
+        assert!(parse_line("push constant 32767", "Test.vm", 1).is_ok());
+        assert!(matches!(parse_line("push constant 32768", "Test.vm", 1), Err(VmError::IndexOutOfRange { max: 32767, .. })));
+    }
+    #[test]
+    fn rejects_pop_constant() {
+        assert!(matches!(parse_line("pop constant 1", "Test.vm", 1), Err(VmError::PopConstant { .. })));
+    }
+    #[test]
+    fn rejects_unknown_branch() {
+        assert!(parse_line("jump LOOP", "Test.vm", 1).is_err());
+    }
+}
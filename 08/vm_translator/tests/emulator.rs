@@ -0,0 +1,36 @@
+use std::path::Path;
+use vm_translator::emulator::{ Stop, Vm };
+use vm_translator::translator::parser::Segment;
+
+#[test]
+fn stack_test_like_the_translation() {
+    let mut vm = Vm::from_file(Path::new("../../07/StackArithmetic/StackTest/StackTest.vm")).unwrap();
+    assert_eq!(vm.run(1000), Ok(Stop::Halted));
+    assert_eq!(vm.stack(), &[-1, 0, 0, 0, -1, 0, -1, 0, 0, -91]);
+}
+
+#[test]
+fn static_test() {
+    let mut vm = Vm::from_dir(Path::new("../../07/MemoryAccess/StaticTest")).unwrap();
+    vm.run(1000).unwrap();
+    assert_eq!(vm.stack(), &[1110]);
+}
+
+#[test]
+fn sys_init_is_the_entry_point() {
+    let mut vm = Vm::from_file(Path::new("tests/programs/SysInit.vm")).unwrap();
+    assert_eq!(vm.current_function(), Some("Sys.init"));
+    assert_eq!(vm.run(10_000), Ok(Stop::TightLoop));
+    assert_eq!(vm.segment(Segment::Local, 0), Some(12));
+    assert_eq!(vm.ram(0), 262);
+}
+
+#[test]
+fn inspects_the_call_stack() {
+    let mut vm = Vm::from_file(Path::new("tests/programs/SysInit.vm")).unwrap();
+    while vm.current_function() != Some("SysInit.mult") {
+        vm.step().unwrap();
+    }
+    assert_eq!(vm.call_stack(), vec!["Sys.init", "SysInit.mult"]);
+    assert_eq!((vm.segment(Segment::Argument, 0), vm.segment(Segment::Argument, 1)), (Some(3), Some(4)));
+}
@@ -0,0 +1,216 @@
+use hack_assembler::assembler::{ assemble, assemble_with_symbols };
+use hack_emulator::Machine;
+use std::path::Path;
+use vm_translator::error::VmError;
+use vm_translator::source_map::SourceMap;
+use vm_translator::translator::optimizer::{ optimize, optimize_with_map };
+use vm_translator::{ translate_dir, translate_dir_with_map, translate_file, translate_str, Bootstrap, Routines };
+
+// Assembles the translation and runs it with the stack starting at 256 (like the 07 test scripts).
+fn run(asm: &str, cycles: u64) -> Machine {
+    let program = assemble(asm).unwrap_or_else(|errors| panic!("translation does not assemble: {:?}", errors));
+    let mut machine = Machine::with_program(&program);
+    machine.set_ram(0, 256);
+    machine.run(cycles);
+    machine
+}
+
+#[test]
+fn simple_add() {
+    let asm = translate_file(Path::new("../../07/StackArithmetic/SimpleAdd/SimpleAdd.vm"), Bootstrap::Auto, Routines::Inline).unwrap();
+    let machine = run(&asm, 60);
+    assert_eq!(machine.ram(0), 257);
+    assert_eq!(machine.ram(256), 15);
+}
+
+#[test]
+fn stack_test() {
+    let asm = translate_file(Path::new("../../07/StackArithmetic/StackTest/StackTest.vm"), Bootstrap::Auto, Routines::Inline).unwrap();
+    let machine = run(&asm, 1000);
+    let stack: Vec<i16> = (256..265).map(|address| machine.ram(address)).collect();
+    assert_eq!(stack, vec![-1, 0, 0, 0, -1, 0, -1, 0, 0]);
+    assert_eq!(machine.ram(265), -91);
+}
+
+#[test]
+fn static_test_uses_file_name() {
+    let asm = translate_file(Path::new("../../07/MemoryAccess/StaticTest/StaticTest.vm"), Bootstrap::Auto, Routines::Inline).unwrap();
+    assert!(asm.contains("@StaticTest.8"));
+    assert_eq!(run(&asm, 200).ram(256), 1110);
+}
+
+#[test]
+fn program_without_sys_init_has_no_bootstrap() {
+    let asm = translate_dir(Path::new("../../07/StackArithmetic/SimpleAdd"), Bootstrap::Auto, Routines::Inline).unwrap();
+    assert_eq!(asm, translate_str("push constant 7\npush constant 8\nadd", "SimpleAdd").unwrap());
+}
+
+#[test]
+fn errors_cite_file_and_line() {
+    let errors = translate_str("push constant 1\n// comment\npush temp 9\nfoo", "Main").unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], VmError::IndexOutOfRange { file: "Main.vm".to_string(), line: 3, text: "push temp 9".to_string(), max: 7 });
+    assert_eq!(errors[1].location(), "Main.vm:4");
+}
+
+#[test]
+fn bootstrap_calls_sys_init_with_a_frame() {
+    let asm = translate_file(Path::new("tests/programs/SysInit.vm"), Bootstrap::Auto, Routines::Inline).unwrap();
+    let program = assemble(&asm).unwrap();
+    let mut machine = Machine::with_program(&program);
+    machine.run(5000);
+    // The frame of Sys.init starts at 256, it's local behind the saved frame at 261.
+    assert_eq!(machine.ram(0), 262);
+    assert_eq!(machine.ram(1), 261);
+    assert_eq!(machine.ram(261), 12);
+}
+
+#[test]
+fn bootstrap_can_be_forced_and_suppressed() {
+    let path = Path::new("../../07/StackArithmetic/SimpleAdd/SimpleAdd.vm");
+    assert!(translate_file(path, Bootstrap::Always, Routines::Inline).unwrap().contains("@Sys.init\n0;JMP"));
+    let without = translate_file(Path::new("tests/programs/SysInit.vm"), Bootstrap::Never, Routines::Inline).unwrap();
+    assert!(!without.contains("@256\nD=A"));
+}
+
+#[test]
+fn files_with_the_same_layout_have_unique_labels() {
+    let asm = translate_dir(Path::new("tests/programs/TwoFiles"), Bootstrap::Never, Routines::Inline).unwrap();
+    assert!(asm.contains("(First$LOOP)") && asm.contains("(Second$LOOP)"));
+    assert!(asm.contains("(First$$CMP.0.TRUE)") && asm.contains("(Second$$CMP.0.TRUE)"));
+    assert!(assemble(&asm).is_ok());
+}
+
+#[test]
+fn labels_of_the_file_do_not_collide_with_the_ones_of_the_translator() {
+    let vm_code = "function Foo.bar 0\ncall Foo.baz 0\nlabel ret.0\neq\nlabel CMP.1.TRUE\nreturn\nfunction Foo.baz 0\npush constant 0\nreturn";
+    let asm = translate_str(vm_code, "Foo").unwrap();
+    assert!(asm.contains("(Foo.bar$ret.0)") && asm.contains("(Foo.bar$$ret.0)"));
+    assert!(asm.contains("(Foo.bar$CMP.1.TRUE)") && asm.contains("(Foo.bar$$CMP.1.TRUE)"));
+    assert!(assemble(&asm).is_ok());
+    let path = std::env::temp_dir().join("Foo.vm");
+    std::fs::write(&path, vm_code).unwrap();
+    assert!(assemble(&translate_file(&path, Bootstrap::Never, Routines::Shared).unwrap()).is_ok());
+}
+
+// The only variables of a translation are the statics `File.N`.
+fn assert_only_statics(asm: &str, files: &[&str]) {
+    let assembly = assemble_with_symbols(asm).unwrap();
+    for (name, _) in &assembly.variables {
+        let is_static = name
+            .split_once('.')
+            .is_some_and(|(file, index)| files.contains(&file) && index.parse::<u32>().is_ok());
+        assert!(is_static, "'{}' is not a static variable", name);
+    }
+}
+
+#[test]
+fn pop_allocates_no_variables() {
+    let path = Path::new("../../07/MemoryAccess/BasicTest/BasicTest.vm");
+    assert_only_statics(&translate_file(path, Bootstrap::Auto, Routines::Inline).unwrap(), &[]);
+    let path = Path::new("../../07/MemoryAccess/StaticTest/StaticTest.vm");
+    assert_only_statics(&translate_file(path, Bootstrap::Auto, Routines::Inline).unwrap(), &["StaticTest"]);
+
+    // A program of the size of Pong: hundreds of pops into every segment.
+    let segments = ["local", "argument", "this", "that", "temp", "pointer", "static"];
+    let vm_code: String = (0..350)
+        .map(|idx| format!("push constant {}\npop {} {}\n", idx, segments[idx % 7], idx % 2))
+        .collect();
+    let asm = translate_str(&vm_code, "Pong").unwrap();
+    assert_only_statics(&asm, &["Pong"]);
+}
+
+#[test]
+fn optimized_programs_compute_the_same() {
+    let programs = [
+        ("../../07/StackArithmetic/StackTest/StackTest.vm", 1000),
+        ("../../07/MemoryAccess/PointerTest/PointerTest.vm", 1000),
+        ("../../07/MemoryAccess/StaticTest/StaticTest.vm", 1000),
+        ("tests/programs/SysInit.vm", 5000),
+    ];
+    for (path, cycles) in programs.iter() {
+        let asm = translate_file(Path::new(path), Bootstrap::Auto, Routines::Inline).unwrap();
+        let (optimized, report) = optimize(&asm);
+        assert!(report.after < report.before, "{}: {}", path, report);
+        let (expected, actual) = (run(&asm, *cycles), run(&optimized, *cycles));
+        // Everything below SP is the same (the optimized code does not write what it does not push),
+        // except for R13-R15, that hold addresses of the code.
+        let sp = expected.ram(0) as usize;
+        assert_eq!(actual.ram(0) as usize, sp, "{}", path);
+        let ram = |machine: &Machine| (0..sp).filter(|address| !(13..16).contains(address)).map(|address| machine.ram(address)).collect::<Vec<i16>>();
+        assert_eq!(ram(&actual), ram(&expected), "{}", path);
+    }
+}
+
+#[test]
+fn shared_routines_compute_the_same() {
+    let programs = [
+        ("../../07/StackArithmetic/StackTest/StackTest.vm", 2000),
+        ("tests/programs/SysInit.vm", 5000),
+    ];
+    for (path, cycles) in programs.iter() {
+        let inline = translate_file(Path::new(path), Bootstrap::Auto, Routines::Inline).unwrap();
+        let shared = translate_file(Path::new(path), Bootstrap::Auto, Routines::Shared).unwrap();
+        let (expected, actual) = (run(&inline, *cycles), run(&shared, *cycles));
+        // R13-R15 hold addresses of the code, that differ.
+        let sp = expected.ram(0) as usize;
+        assert_eq!(actual.ram(0) as usize, sp, "{}", path);
+        let ram = |machine: &Machine| (0..sp).filter(|address| !(13..16).contains(address)).map(|address| machine.ram(address)).collect::<Vec<i16>>();
+        assert_eq!(ram(&actual), ram(&expected), "{}", path);
+    }
+}
+
+#[test]
+fn shared_routines_fit_large_programs_into_the_rom() {
+    // A program of the size of Pong: 80 functions, that call and compare a lot.
+    let vm_code: String = (0..80)
+        .map(|idx| {
+            let body = format!("push argument 0\ncall Game.f{} 1\npush constant {}\nlt\npop temp 0\n", (idx + 1) % 80, idx);
+            format!("function Game.f{} 0\n{}push constant 0\nreturn\n", idx, body.repeat(8))
+        })
+        .collect();
+    let dir = std::env::temp_dir().join("vm_translator_pong");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Game.vm"), vm_code).unwrap();
+
+    let instructions = |asm: &str| asm.split('\n').filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('(')).count();
+    let inline = translate_dir(&dir, Bootstrap::Never, Routines::Inline).unwrap();
+    let shared = translate_dir(&dir, Bootstrap::Never, Routines::Shared).unwrap();
+    assert!(instructions(&inline) > 32768);
+    assert!(instructions(&shared) < 32768);
+}
+
+// The translations of push, pop and arithmetic start with a comment of their command,
+// it has to come from the line of the vm-file with that command (12 in TwoFiles).
+fn assert_comments_map_to_their_commands(asm: &str, map: &SourceMap, dir: &str) {
+    let mut commands = 0;
+    for (idx, line) in asm.split('\n').enumerate() {
+        // The other comments start with capitals (like "// Call" or "// JMP to LABEL").
+        let command = match line.strip_prefix("// ") {
+            Some(command) if !command.contains(char::is_uppercase) => command,
+            _ => continue,
+        };
+        let origin = map.lookup(idx + 1).unwrap_or_else(|| panic!("line {} has no origin", idx + 1));
+        let vm_code = std::fs::read_to_string(Path::new(dir).join(&origin.file)).unwrap();
+        assert_eq!(vm_code.split('\n').nth(origin.line - 1).unwrap().trim(), command, "line {}", idx + 1);
+        commands += 1;
+    }
+    assert_eq!(commands, 12);
+}
+
+#[test]
+fn source_maps_point_at_the_commands() {
+    let dir = "tests/programs/TwoFiles";
+    let (asm, map) = translate_dir_with_map(Path::new(dir), Bootstrap::Always, Routines::Shared).unwrap();
+    assert_eq!(asm, translate_dir(Path::new(dir), Bootstrap::Always, Routines::Shared).unwrap());
+    assert_comments_map_to_their_commands(&asm, &map, dir);
+    // The bootstrap code and the shared routines have no origin.
+    assert_eq!(map.lookup(1), None);
+    assert_eq!(map.lookup(asm.split('\n').count()), None);
+
+    // The optimizer fuses the inline commands, their comments stay.
+    let (asm, map) = translate_dir_with_map(Path::new(dir), Bootstrap::Never, Routines::Inline).unwrap();
+    let (optimized, optimized_map, report) = optimize_with_map(&asm, &map);
+    assert!(report.after < report.before);
+    assert_comments_map_to_their_commands(&optimized, &optimized_map, dir);
+}
@@ -0,0 +1,243 @@
+// The syntax tree of a jack-class, as the parser builds it.
+// Every node knows the span of the tokens it comes from, so the passes
+// over the tree (like the code generation) can point at them in their errors.
+use crate::tokenizer::token::Span;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Class {
+    pub name: Ident,
+    pub vars: Vec<ClassVarDec>,
+    pub subroutines: Vec<Subroutine>,
+}
+
+// A name (of a class, subroutine or variable) where it appears in the code
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ClassVarKind {
+    Static,
+    Field,
+}
+
+// `static int x, y;` or `field Point p;`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClassVarDec {
+    pub kind: ClassVarKind,
+    pub typ: Type,
+    pub names: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Int,
+    Char,
+    Boolean,
+    Class(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SubroutineKind {
+    Constructor,
+    Function,
+    Method,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum ReturnType {
+    #[default]
+    Void,
+    Type(Type),
+}
+
+// The span of a subroutine is the one of it's keyword (constructor, function or method)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Subroutine {
+    pub kind: SubroutineKind,
+    pub return_type: ReturnType,
+    pub name: Ident,
+    pub parameters: Vec<Parameter>,
+    pub locals: Vec<VarDec>,
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Parameter {
+    pub typ: Type,
+    pub name: Ident,
+}
+
+// `var int i, sum;`
+#[derive(Debug, PartialEq, Clone)]
+pub struct VarDec {
+    pub typ: Type,
+    pub names: Vec<Ident>,
+    pub span: Span,
+}
+
+// The span of a statement is the one of it's keyword
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Let { target: Ident, index: Option<Box<Expression>>, value: Expression, span: Span },
+    If { condition: Expression, statements: Vec<Statement>, else_statements: Option<Vec<Statement>>, span: Span },
+    While { condition: Expression, statements: Vec<Statement>, span: Span },
+    Do { call: SubroutineCall, span: Span },
+    Return { value: Option<Expression>, span: Span },
+}
+impl Statement {
+    pub fn span(&self) -> &Span {
+        match self {
+            Statement::Let { span, .. }
+            | Statement::If { span, .. }
+            | Statement::While { span, .. }
+            | Statement::Do { span, .. }
+            | Statement::Return { span, .. } => span,
+        }
+    }
+}
+
+// `term (op term)*`, the operations are kept in the order of the code.
+// The span covers all terms.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Expression {
+    pub term: Term,
+    pub operations: Vec<(Op, Term)>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Term {
+    pub kind: TermKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TermKind {
+    Integer(u16),
+    String(String),
+    Keyword(KeywordConstant),
+    Var(Ident),
+    // `a[i]`
+    Index(Ident, Box<Expression>),
+    Call(SubroutineCall),
+    Parenthesized(Box<Expression>),
+    Unary(UnaryOp, Box<Term>),
+}
+
+// `f(x)`, `Class.f(x)` or `object.f(x)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct SubroutineCall {
+    pub receiver: Option<Ident>,
+    pub name: Ident,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Lt,
+    Gt,
+    Eq,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeywordConstant {
+    True,
+    False,
+    Null,
+    This,
+}
+
+impl Op {
+    pub fn from_symbol(symbol: &str) -> Option<Op> {
+        match symbol {
+            "+" => Some(Op::Add),
+            "-" => Some(Op::Sub),
+            "*" => Some(Op::Mul),
+            "/" => Some(Op::Div),
+            "&" => Some(Op::And),
+            "|" => Some(Op::Or),
+            "<" => Some(Op::Lt),
+            ">" => Some(Op::Gt),
+            "=" => Some(Op::Eq),
+            _ => None,
+        }
+    }
+    // How strong the operation binds with --precedence (like in C):
+    // `*` and `/` before `+` and `-` before the comparisons before `&` before `|`,
+    // so `x < 10 & y > 0` compares first.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Op::Mul | Op::Div => 4,
+            Op::Add | Op::Sub => 3,
+            Op::Lt | Op::Gt | Op::Eq => 2,
+            Op::And => 1,
+            Op::Or => 0,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Char => write!(f, "char"),
+            Type::Boolean => write!(f, "boolean"),
+            Type::Class(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnaryOp::Neg => write!(f, "-"),
+            UnaryOp::Not => write!(f, "~"),
+        }
+    }
+}
+
+impl fmt::Display for SubroutineKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubroutineKind::Constructor => write!(f, "constructor"),
+            SubroutineKind::Function => write!(f, "function"),
+            SubroutineKind::Method => write!(f, "method"),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::And => "&",
+            Op::Or => "|",
+            Op::Lt => "<",
+            Op::Gt => ">",
+            Op::Eq => "=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
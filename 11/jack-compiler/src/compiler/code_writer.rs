@@ -1,5 +1,4 @@
-use crate::tokenizer::token::{ Token };
-use crate::compiler::{ Subroutine, ReturnType };
+use crate::ast::{ Op, ReturnType, UnaryOp };
 
 pub fn write_push(kind: &str, idx_or_value: u32) -> String {
     match kind {
@@ -22,34 +21,32 @@ pub fn write_pop(kind: &str, idx: u32) -> String {
     }
 }
 
-pub fn write_op(operator: &Token) -> String {
-    match operator.value.as_ref() {
-        "+" => String::from("add"),
-        "-" => String::from("sub"),
-        "*" => String::from("call Math.multiply 2"),
-        "/" => String::from("call Math.divide 2"),
-        "<" => String::from("lt"),
-        ">" => String::from("gt"),
-        "=" => String::from("eq"),
-        "&" => String::from("and"),
-        "|" => String::from("or"),
-        op => format!("Operator '{}' is not implemented.", op),
+pub fn write_op(operator: &Op) -> String {
+    match operator {
+        Op::Add => String::from("add"),
+        Op::Sub => String::from("sub"),
+        Op::Mul => String::from("call Math.multiply 2"),
+        Op::Div => String::from("call Math.divide 2"),
+        Op::Lt => String::from("lt"),
+        Op::Gt => String::from("gt"),
+        Op::Eq => String::from("eq"),
+        Op::And => String::from("and"),
+        Op::Or => String::from("or"),
     }
 }
 
-pub fn write_unary_op(token: &Token) -> String {
-    match token.value.as_ref() {
-        "-" => String::from("neg"),
-        "~" => String::from("not"),
-        op => panic!("Expected unary-operator but got '{}'", op),
+pub fn write_unary_op(operator: &UnaryOp) -> String {
+    match operator {
+        UnaryOp::Neg => String::from("neg"),
+        UnaryOp::Not => String::from("not"),
     }
 }
 
-pub fn write_return(subroutine: &Subroutine) -> String {
-    match &subroutine.return_type {
-        ReturnType::Void => String::from("push constant 0"),
-        _ => String::from("// non_void_no_dummy_0"),
-        // rt => format!("RETURN_TYPE of '{:?}' IS NOT IMPLEMENTED YET", rt),
+// The value of a subroutine has been pushed before it returns, void subroutines return 0
+pub fn write_return(return_type: &ReturnType) -> Vec<String> {
+    match return_type {
+        ReturnType::Void => vec!["push constant 0".to_string(), "return\n".to_string()],
+        ReturnType::Type(_) => vec!["return\n".to_string()],
     }
 }
 
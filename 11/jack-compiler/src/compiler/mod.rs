@@ -1,741 +1,572 @@
 pub mod tables;
 pub mod code_writer;
-
-use crate::tokenizer::token::{ Token, TokenType, TokenStream };
-use tables::{ Var, ClassTable, SubroutineTable, lookup, get_object_type, is_object };
+pub mod registry;
+pub mod types;
+
+use crate::ast::*;
+use crate::diagnostic::{ Diagnostic, Level };
+use crate::parser::Parser;
+use crate::tokenizer::token::{ Span, Token };
+use tables::{ Var, ClassTable, SubroutineTable, lookup, is_object };
+use registry::{ Registry, Signature };
+use types::check_types;
 use code_writer::*;
+use std::borrow::Cow;
 
-static OPERATORS: &[&str] = &["+", "-", "*", "/", "&", "|", "<", ">", "=", "~"];
-// static UNARY_OP: &[&str] = &["-", "~"];
+// Marks the jack-line of the following byte code, the marks are removed
+// (and turned into the source map) when the class has been compiled.
+static ORIGIN_MARK: &str = "\0origin ";
 
+// Generates the byte code of a class in a pass over it's syntax tree (see parser).
 pub struct Compiler<'a> {
-    token_tail: TokenStream<'a>,
+    tokens: &'a [Token],
     class_name: &'a str,
     class_table: ClassTable,
     subroutine_table: SubroutineTable,
-    current_subroutine: Subroutine<'a>,
+    subroutine_name: String,
+    subroutine_kind: SubroutineKind,
+    return_type: ReturnType,
     label_count: u32,
-}
-#[derive(Default)]
-pub struct Subroutine<'a> {
-    name: &'a str,
-    return_type: ReturnType<'a>,
-}
-#[derive(Debug)]
-enum ReturnType<'a> {
-    Void,
-    Int,
-    Char,
-    Boolean,
-    Class(&'a str),
-}
-impl<'a> Default for ReturnType<'a> {
-    fn default() -> Self {
-        ReturnType::Void
-    }
+    // The subroutines, that can be called (without a registry of the program,
+    // the ones of the OS and of the class itself)
+    registry: Cow<'a, Registry>,
+    // The vm-lines of the byte code (joined with new lines) and the jack-lines they come from.
+    origins: Vec<(usize, usize)>,
+    // The jack-code of the tokens, to show the lines of errors
+    source: &'a str,
+    // Errors of statements, that have been skipped, and warnings
+    diagnostics: Vec<Diagnostic>,
+    // Multiplications (and divisions) before additions before comparisons before `&` before `|`
+    // (otherwise all operations are applied from the left to the right).
+    precedence: bool,
+    // Check the types of the values (mismatches are warnings)
+    type_check: bool,
+    // Warnings are errors
+    strict: bool,
 }
 
 impl<'a> Compiler<'a> {
-    pub fn new(tokens: &'a Vec<Token>, class_name: &'a str) -> Self {
+    pub fn new(tokens: &'a [Token], class_name: &'a str) -> Self {
         Compiler {
-            token_tail: tokens.iter().peekable(),
-            class_name: class_name,
+            tokens,
+            class_name,
             class_table: ClassTable::default(),
             subroutine_table: SubroutineTable::default(),
-            current_subroutine: Subroutine::default(),
+            subroutine_name: String::new(),
+            subroutine_kind: SubroutineKind::Function,
+            return_type: ReturnType::Void,
             label_count: 0,
+            registry: Cow::Owned(Registry::with_os()),
+            origins: Vec::new(),
+            source: "",
+            diagnostics: Vec::new(),
+            precedence: false,
+            type_check: false,
+            strict: false,
         }
     }
-
-    fn get_size(&self) -> usize {
-        self.class_table.get_field_count()
+    // The jack-code, the tokens come from. Errors show the line of the token with it.
+    pub fn with_source(mut self, source: &'a str) -> Self {
+        self.source = source;
+        self
     }
-    fn set_subroutine(&mut self, name: &'a str, return_type_str: &'a str) {
-        let return_type = match return_type_str {
-            "void" => ReturnType::Void,
-            "int" => ReturnType::Int,
-            "boolean" => ReturnType::Boolean,
-            "char" => ReturnType::Char,
-            class => ReturnType::Class(class),
-        };
-        self.current_subroutine = Subroutine {
-            name,
-            return_type,
-        };
+    pub fn with_precedence(mut self, precedence: bool) -> Self {
+        self.precedence = precedence;
+        self
     }
-    fn get_subroutine(&self) -> &Subroutine<'a> {
-        &self.current_subroutine
+    pub fn with_type_check(mut self, type_check: bool) -> Self {
+        self.type_check = type_check;
+        self
     }
-    fn get_subroutine_name(&self) -> &'a str {
-        self.current_subroutine.name
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
     }
-
-    pub fn analyze_tokens(&mut self) -> Vec<String> {
-        let class_keyword = self.token_tail.next().unwrap();
-        if class_keyword.value != "class" {
-            panic!("class files need to start with class decleration");
+    // The subroutines of all classes of the program
+    pub fn with_registry(mut self, registry: &'a Registry) -> Self {
+        self.registry = Cow::Borrowed(registry);
+        self
+    }
+    // The errors and warnings, that did not stop the compilation
+    // (all of them are returned by analyze_tokens on errors).
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    // Keeps a diagnostic, the same error is only reported once
+    fn report(&mut self, diagnostic: Diagnostic) {
+        if self.diagnostics.last() != Some(&diagnostic) {
+            self.diagnostics.push(diagnostic);
         }
-        
-        let class_name = self.token_tail.next().unwrap();
-        if class_name.token_type != TokenType::Identifier {
-            panic!("classes need a valid Class-Identifier");
+    }
+    // Looks up a variable in the tables, fields only exist in methods and constructors
+    fn lookup(&self, name: &Ident) -> Result<Var, Diagnostic> {
+        let var = lookup(name, &self.class_table, &self.subroutine_table)?;
+        if var.kind == "field" && self.subroutine_kind == SubroutineKind::Function {
+            return Err(Diagnostic::error(&name.span, format!("The field '{}' can not be used in the function '{}'", name.name, self.subroutine_name))
+                .with_note("functions have no object ('this'), only methods and constructors have fields".to_string()));
+        }
+        Ok(var)
+    }
+    // The signature of a called subroutine, that has to exist
+    // The signature of a called subroutine, None for a class, whose file could not be parsed.
+    fn signature(&self, class_name: &str, call: &SubroutineCall) -> Result<Option<Signature>, Diagnostic> {
+        if self.registry.is_unchecked(class_name) {
+            return Ok(None);
         }
-        
-        let mut class_byte_code = Vec::new();
-        class_byte_code.push(format!("// ByteCode for class '{}'\n", class_name.value));
-    
-        // skip opening curly-brace
-        self.token_tail.next();
-        
-        // parse the body
-        let body = self.build_class_body();
-        class_byte_code.extend(body);
-        
-        // after body has finished, check for closing curly
-        let closing_curly = &self.token_tail.next().unwrap().value;
-        if closing_curly != "}" {
-            panic!("Class-Files should end with closing curly bracket, but token was '{}'", closing_curly);
-        };
-    
-        match self.token_tail.next() {
-            None => class_byte_code,
-            Some(token) => panic!("Expected eof, but got token {:?}", token), 
+        match self.registry.get(class_name, &call.name.name) {
+            Some(signature) => Ok(Some(signature.clone())),
+            None => Err(Diagnostic::error(&call.name.span, format!("Class '{}' has no subroutine '{}'", class_name, call.name.name))),
         }
     }
-    // Compilse class-body
-    fn build_class_body(&mut self) -> Vec<String> {
-        // let mut class_table = ClassTable::default();
-        let mut class_body_byte_code = Vec::new();
-    
-        loop {
-            let next_token = self.token_tail.peek();
-            if !is_class_var_start(next_token) {
-                break;
+
+    // The source map of the compiled class: one `<vm-line> <class>.jack:<line>`
+    // per statement and subroutine (in the format of the vm-translator source maps).
+    pub fn source_map(&self) -> String {
+        self.origins
+            .iter()
+            .map(|(vm_line, jack_line)| format!("{} {}.jack:{}", vm_line, self.class_name, jack_line))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+    // Removes the origin-marks from the byte code and stores the vm-line,
+    // the byte code following each mark starts on.
+    fn take_origins(&mut self, byte_code: Vec<String>) -> Vec<String> {
+        let mut code = Vec::new();
+        let mut vm_line = 1;
+        let mut jack_line = None;
+        for element in byte_code {
+            if let Some(line) = element.strip_prefix(ORIGIN_MARK) {
+                jack_line = line.parse().ok();
+                continue;
             }
-            // Has no output, but registers the class-vars
-            self.compile_class_vars();
-            class_body_byte_code.push(
-                format!("// Class-Var-Dec Fields: #{}, Statics: #{}\n",
-                    self.class_table.get_next_idx("field"), self.class_table.get_next_idx("static")));
+            if let Some(jack_line) = jack_line.take() {
+                let leading_new_lines = element.len() - element.trim_start_matches('\n').len();
+                self.origins.push((vm_line + leading_new_lines, jack_line));
+            }
+            vm_line += element.matches('\n').count() + 1;
+            code.push(element);
         }
-    
-        // Add subroutines
-        while self.token_tail.peek().unwrap().value != "}" {
-            class_body_byte_code.extend(self.compile_subroutine());
+        code
+    }
+
+    fn get_size(&self) -> usize {
+        self.class_table.get_field_count()
+    }
+
+    // Parses the tokens and compiles the class. Broken statements (and subroutines)
+    // are reported and skipped, so all errors of the class are found at once.
+    pub fn analyze_tokens(&mut self) -> Result<Vec<String>, Vec<Diagnostic>> {
+        let mut parser = Parser::new(self.tokens, &format!("{}.jack", self.class_name));
+        let class = parser.parse_class();
+        self.compile_parsed(class.as_ref(), parser.into_diagnostics())
+    }
+    // Compiles the class, the parser has built from the tokens (with it's syntax errors)
+    pub fn compile_parsed(&mut self, class: Option<&Class>, syntax_errors: Vec<Diagnostic>) -> Result<Vec<String>, Vec<Diagnostic>> {
+        self.diagnostics.extend(syntax_errors);
+        let class_byte_code = match class {
+            Some(class) => {
+                let class_byte_code = self.compile_class(class);
+                if self.type_check {
+                    let warnings = check_types(class, &self.registry, self.precedence);
+                    self.diagnostics.extend(warnings);
+                }
+                class_byte_code
+            },
+            None => Vec::new(),
+        };
+        // The syntax errors and the errors of the code generation in the order of the code
+        self.diagnostics.sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.column));
+        if self.strict {
+            for warning in self.diagnostics.iter_mut().filter(|diagnostic| !diagnostic.is_error()) {
+                warning.level = Level::Error;
+                warning.notes.push("warnings are errors in strict mode".to_string());
+            }
+        }
+        if self.diagnostics.iter().any(Diagnostic::is_error) {
+            return Err(std::mem::take(&mut self.diagnostics));
         }
-        class_body_byte_code
+        Ok(self.take_origins(class_byte_code))
     }
-    // Compilation Helpers
-    // calles itself until a semicolon (;) appears in the TokenStream
-    // Registers Class-Vars in ClassVarTable
-    fn compile_class_vars(&mut self) {
-    
-        let var_kind = &self.token_tail.next().unwrap().value;
-        let var_typ = &self.token_tail.next().unwrap().value;
-        let var_name = &self.token_tail.next().unwrap().value;
-    
-        // Construct VAR
-        let var = Var::new(&var_kind, &var_typ, self.class_table.get_next_idx(&var_kind));
-        // Add Var to Class-Table
-        self.class_table.add(&var_name, var);
-    
-        loop {
-            if self.token_tail.peek().unwrap().value == ";" {
-                break;
+    // Compiles a single (let) statement, the tokens are only the statement
+    pub fn compile_let(&mut self) -> Result<Vec<String>, Diagnostic> {
+        let statement = Parser::new(self.tokens, "").parse_statement()?;
+        self.compile_statement(&statement)
+    }
+
+    // Compiles a parsed class (errors are reported, see analyze_tokens)
+    pub fn compile_class(&mut self, class: &Class) -> Vec<String> {
+        if let Cow::Owned(registry) = &mut self.registry {
+            registry.add_class(class);
+        }
+        let mut class_byte_code = Vec::new();
+        class_byte_code.push(format!("// ByteCode for class '{}'\n", class.name.name));
+
+        // Has no output, but registers the class-vars
+        for var_dec in &class.vars {
+            let kind = match var_dec.kind {
+                ClassVarKind::Static => "static",
+                ClassVarKind::Field => "field",
+            };
+            for name in &var_dec.names {
+                let var = Var::new(kind, &var_dec.typ.to_string(), self.class_table.get_next_idx(kind));
+                self.class_table.add(&name.name, var);
             }
-            // ignore Comma
-            self.token_tail.next();
-            let var_token = self.token_tail.next().unwrap();
-            // Create Var and add data to xml
-            let var = Var::new(&var_kind, &var_typ, self.class_table.get_next_idx(&var_kind));
-            // Add to Class-Table
-            self.class_table.add(&var_token.value, var);
+            class_byte_code.push(
+                format!("// Class-Var-Dec Fields: #{}, Statics: #{}\n",
+                    self.class_table.get_next_idx("field"), self.class_table.get_next_idx("static")));
         }
-        // ignore Semicolon (end of decleration)
-        let semicolon = &self.token_tail.next().unwrap().value;
-        if semicolon != ";" {
-            panic!("Semicolon expected as end of ClassVarDec, but '{:?}' was found.", semicolon);
+
+        for subroutine in &class.subroutines {
+            class_byte_code.extend(self.compile_subroutine(subroutine));
         }
+        class_byte_code
     }
     // soubroutine-compiler
-    fn compile_subroutine(&mut self) -> Vec<String> {
+    fn compile_subroutine(&mut self, subroutine: &Subroutine) -> Vec<String> {
         // Reset SubroutineTable
         self.subroutine_table = SubroutineTable::default();
-        
+        self.subroutine_name = subroutine.name.name.to_string();
+        self.subroutine_kind = subroutine.kind;
+        self.return_type = subroutine.return_type.clone();
+
         let mut subroutine_byte_code = Vec::new();
-        // Add suroutine-keyword, type and subroutine name/identifier
-        let routine_keyword = self.token_tail.next().unwrap();
-        let return_type = self.token_tail.next().unwrap();
-        let routine_name = self.token_tail.next().unwrap();
-        // Update/set subroutine (name & type)
-        self.set_subroutine(&routine_name.value, &return_type.value);
-        
-        let is_method = routine_keyword.value == "method";
-        if is_method {
+        subroutine_byte_code.push(format!("{}{}", ORIGIN_MARK, subroutine.span.line));
+        if subroutine.kind == SubroutineKind::Method {
             // Create this-arg
             let this = Var::new("argument", self.class_name, 0);
-            // Add Var to Subrroutine-Table
             self.subroutine_table.add("this", this);
         }
-        // Add parameters to Subroutine-Table (no code-creation)
-        self.compile_paramlist();
-        
-        // Ignore opening curly-brace
-        self.token_tail.next();
-        // Start subroutine-body
-        // Add code in subroutine-body
-        let subroutine_body = self.compile_subroutine_body();
-        // Now the local-var-count is known. So first add the function label, then the body-statements
-        let local_var_count = self.subroutine_table.get_local_var_count();
-        subroutine_byte_code.push(
-            format!("\nfunction {}.{} {}\n", self.class_name, self.get_subroutine_name(), local_var_count));
-        // If Function is a constructor, allocate space for fields and anchor this to pointer 0
-        if routine_keyword.value == "constructor" {
-            let size = self.get_size();
-            subroutine_byte_code.push(format!("push constant {}", size));
-            subroutine_byte_code.push("call Memory.alloc 1".to_string());
-            subroutine_byte_code.push("pop pointer 0".to_string());
-        // If Function is method anchor this to pointer 0
-        } else if is_method {
-            subroutine_byte_code.push("push argument 0".to_string());
-            subroutine_byte_code.push("pop pointer 0".to_string())
+        // Add parameters and local vars to Subroutine-Table (no code-creation)
+        for parameter in &subroutine.parameters {
+            let arg = Var::new("argument", &parameter.typ.to_string(), self.subroutine_table.get_next_idx("argument"));
+            self.subroutine_table.add(&parameter.name.name, arg);
         }
-        subroutine_byte_code.extend(subroutine_body);
-        // End subroutine
-        let closing_curly = self.token_tail.next().unwrap();
-        if closing_curly.value != "}" {
-            panic!("Expected closing curly at end of SubroutineBody, but got '{:?}'", closing_curly);
-        }
-        subroutine_byte_code
-    }
-    // Compile PARAM (part of subroutine)
-    fn compile_paramlist(&mut self) {
-    
-        loop {
-            let token = self.token_tail.next().unwrap();
-    
-            if token.value  == ")" { break; }
-    
-            // If opening paranthese -> ignore it
-            if ["(", ","].contains(&token.value.as_ref()) { continue; }
-    
-            // add param type and name
-            let typ_token = token;
-            let name_token = self.token_tail.next().unwrap();
-            // Create arg-var and add it to Subroutine-Table
-            let arg = Var::new("argument", &typ_token.value, self.subroutine_table.get_next_idx("argument"));
-            // Add arg to Subroutine-Table
-            self.subroutine_table.add(&name_token.value, arg);
-        }
-    }
-    // Compile SOUBROUTINE-BODY (part of subroutine)
-    fn compile_subroutine_body(&mut self) -> Vec<String> {
-        let mut subroutine_body_byte_code = Vec::new();
-        // add var-decleration if there are any
-        loop {
-            let next_token = self.token_tail.peek().unwrap();
-            if next_token.value != "var" {
-                break;
-            }
-            // Registers local Vars (no byte code)
-            self.compile_var_dec();
-        }
-    
-        // If closing curly appears, subroutine has no statements and can return early
-        if self.token_tail.peek().unwrap().value == "}" {
-            return subroutine_body_byte_code;
-        }
-    
-        // add statements
-        loop {
-            let next_token = self.token_tail.peek().unwrap().value.as_str();
-            if next_token == "}" || next_token == ";" {
-                break;
+        for var_dec in &subroutine.locals {
+            for name in &var_dec.names {
+                let var = Var::new("local", &var_dec.typ.to_string(), self.subroutine_table.get_next_idx("local"));
+                self.subroutine_table.add(&name.name, var);
             }
-            subroutine_body_byte_code.extend(self.compile_statement());
         }
-        subroutine_body_byte_code
-    }
-    // Compile VAR-DECLERATION (part of subroutine-body)
-    fn compile_var_dec(&mut self) {
-        let _var_keyword_token = self.token_tail.next().unwrap();
-        let var_type_token = self.token_tail.next().unwrap();
-        let var_name_token = self.token_tail.next().unwrap();
-        
-        // Construct first Var and add it to Subroutine-Table
-        let var = Var::new("local", &var_type_token.value, self.subroutine_table.get_next_idx("local"));
-        self.subroutine_table.add(&var_name_token.value, var);
-    
-        loop {
-            let next_token = self.token_tail.next().unwrap();
-            // If no more Local-Var decs -> quit
-            if next_token.value == ";" { break; }
-            // Otherwise register more Var(s) of same type (ignore the comma)
-            if next_token.value == "," {
-                let next_var_name = self.token_tail.next().unwrap();
-                // construct var behind comma and add it to Subroutine-Table
-                let var = Var::new("local", &var_type_token.value, self.subroutine_table.get_next_idx("local"));
-                self.subroutine_table.add(&next_var_name.value, var);
-            }
+
+        let local_var_count = self.subroutine_table.get_local_var_count();
+        subroutine_byte_code.push(
+            format!("\nfunction {}.{} {}\n", self.class_name, self.subroutine_name, local_var_count));
+        match subroutine.kind {
+            // Allocate space for fields and anchor this to pointer 0
+            SubroutineKind::Constructor => {
+                let size = self.get_size();
+                subroutine_byte_code.push(format!("push constant {}", size));
+                subroutine_byte_code.push("call Memory.alloc 1".to_string());
+                subroutine_byte_code.push("pop pointer 0".to_string());
+            },
+            // Anchor this to pointer 0
+            SubroutineKind::Method => {
+                subroutine_byte_code.push("push argument 0".to_string());
+                subroutine_byte_code.push("pop pointer 0".to_string())
+            },
+            SubroutineKind::Function => {},
+        }
+        subroutine_byte_code.extend(self.compile_statements(&subroutine.statements));
+        // Otherwise the VM would run into the code behind the function
+        if !returns_on_every_path(&subroutine.statements) {
+            let message = match &subroutine.return_type {
+                ReturnType::Void => format!("'{}.{}' does not return on every path", self.class_name, self.subroutine_name),
+                ReturnType::Type(typ) => format!("'{}.{}' does not return {} on every path", self.class_name, self.subroutine_name, typ),
+            };
+            self.report(Diagnostic::error(&subroutine.name.span, message)
+                .with_note("the end of the subroutine can be reached without a return".to_string()));
         }
+        subroutine_byte_code
     }
     // Compile STATEMENTS
-    fn compile_statement(&mut self) -> Vec<String> {
+    // A statement with an error is reported and left out, the statements behind it are still compiled.
+    fn compile_statements(&mut self, statements: &[Statement]) -> Vec<String> {
         let mut statement_byte_code = Vec::new();
-        
-        loop {
-            if self.token_tail.peek() == None {
-                panic!("token_tail has no next value in compile_statement. But should either see have } of sourrounding subroutine-body or next statement or else")
+        // Why the statements end (a return or an if, that returns on every path)
+        let mut end = None;
+        for statement in statements {
+            let span = statement.span();
+            if let Some(end) = end.take() {
+                self.report(Diagnostic::warning(span, "Unreachable statement".to_string()).with_note(end));
             }
-            // Check if no more subroutines?
-            if self.token_tail.peek().unwrap().value == "}" {
-                break;
+            match statement {
+                Statement::Return { .. } => end = Some(format!("it follows the return in line {}", span.line)),
+                Statement::If { .. } if returns_on_every_path(std::slice::from_ref(statement)) => {
+                    end = Some(format!("it follows the if in line {}, that returns on every path", span.line));
+                },
+                _ => {},
             }
-            let statement_token_value = self.token_tail.peek().unwrap().value.to_string();
-            match statement_token_value.as_str() {
-                "let" => statement_byte_code.extend(self.compile_let()),
-                "if" => statement_byte_code.extend(self.compile_if_statement()),
-                "while" => statement_byte_code.extend(self.compile_while_statement()),
-                "do" => statement_byte_code.extend(self.compile_do()),
-                "return" => statement_byte_code.extend(self.compile_return()),
-                s => panic!("unexpected statement-keyword of: {:?}", s),
+            statement_byte_code.push(format!("{}{}", ORIGIN_MARK, span.line));
+            match self.compile_statement(statement) {
+                Ok(byte_code) => statement_byte_code.extend(byte_code),
+                Err(diagnostic) => self.report(diagnostic),
             }
         }
         statement_byte_code
     }
-    // Compile Statement body
-    fn compile_statement_body(&mut self) -> Vec<String> {
-        let mut statement_body_byte_code = Vec::new();
-        // If body is not empty, get more statements
-        if self.token_tail.peek() == None {
-            panic!("no next value available in compile_statement_body. Either } of this statement should be there or more statements");
+    fn compile_statement(&mut self, statement: &Statement) -> Result<Vec<String>, Diagnostic> {
+        match statement {
+            Statement::Let { target, index, value, .. } => self.compile_let_statement(target, index.as_deref(), value),
+            Statement::If { condition, statements, else_statements, .. } => self.compile_if_statement(condition, statements, else_statements.as_deref()),
+            Statement::While { condition, statements, .. } => self.compile_while_statement(condition, statements),
+            Statement::Do { call, .. } => {
+                let mut do_byte_code = self.compile_subroutine_call(call)?;
+                // Dump the return value, do-calls return void
+                do_byte_code.push("pop temp 0".to_string());
+                Ok(do_byte_code)
+            },
+            Statement::Return { value, span } => {
+                self.check_return(value.as_ref(), span)?;
+                let mut return_byte_code = Vec::new();
+                if let Some(value) = value {
+                    return_byte_code.extend(self.compile_expression(value)?);
+                }
+                return_byte_code.extend(write_return(&self.return_type));
+                Ok(return_byte_code)
+            },
         }
-        if self.token_tail.peek().unwrap().value != "}" {
-            statement_body_byte_code.extend(self.compile_statement());
+    }
+    // The value of a return has to match the subroutine:
+    // void ones return nothing, constructors `this` and the others a value.
+    fn check_return(&self, value: Option<&Expression>, span: &Span) -> Result<(), Diagnostic> {
+        let subroutine = format!("{}.{}", self.class_name, self.subroutine_name);
+        if self.subroutine_kind == SubroutineKind::Constructor {
+            let returns_this = value.is_some_and(|value| value.operations.is_empty() && value.term.kind == TermKind::Keyword(KeywordConstant::This));
+            if !returns_this {
+                return Err(Diagnostic::error(value.map_or(span, |value| &value.span), format!("The constructor '{}' has to return 'this'", subroutine))
+                    .with_note("constructors return the object, they have created".to_string()));
+            }
+            return Ok(());
         }
-        // Check for closing curly and then ignore it
-        let closing_curly = self.token_tail.next().unwrap();
-        if closing_curly.value != "}" {
-            panic!("End of StatementBody expects closing }} but got '{:?}' ", closing_curly);
+        match (&self.return_type, value) {
+            (ReturnType::Void, Some(value)) => Err(Diagnostic::error(&value.span, format!("'{}' is void, it can not return a value", subroutine))
+                .with_note("declare the type of the value as the return type of the subroutine".to_string())),
+            (ReturnType::Type(typ), None) => Err(Diagnostic::error(span, format!("'{}' has to return {}, but the return has no value", subroutine, typ))),
+            _ => Ok(()),
         }
-        statement_body_byte_code
     }
     // Compile LET
-    pub fn compile_let(&mut self) -> Vec<String> {
+    fn compile_let_statement(&mut self, target: &Ident, index: Option<&Expression>, value: &Expression) -> Result<Vec<String>, Diagnostic> {
         let mut let_byte_code = Vec::new();
-        // Dump let keyword
-        self.token_tail.next();
-        // Get identifier kind, type and index
-        let identifier_token = self.token_tail.next().unwrap();
-        let Var {kind, typ:_, idx} = lookup(identifier_token, &self.class_table, &self.subroutine_table);
-    
-        // check if array-indexing occurs
-        let is_array = self.token_tail.peek().unwrap().value == "[";
-        if is_array {
-            // Anchor Array inside variable
-            let_byte_code.push(format!("push {} {}", kind, idx));
-            // Dump opening square-bracket
-            self.token_tail.next();
-            // Add expression inside square-brackets (offset)
-            let_byte_code.extend(self.compile_expression());
-            // Dump closing square-bracket
-            self.token_tail.next();
-            // Add offset to Array-Base-Address
-            let_byte_code.push("add".to_string());
-        }
-    
-        // Dump equal sign
-        let equal_sign = self.token_tail.next().unwrap();
-        if equal_sign.value != "=" {
-            panic!("Expected = in let assignment but got {}.", equal_sign.value);
-        }
-        // Handle Expression on right sight of assignment (push onto stack)
-        let_byte_code.extend(self.compile_expression());
-        // If Array-Assigment avoid stack-collision
-        if is_array {
-            let_byte_code.extend(write_array_assignment());
-        } else {
-            // Assign expression to identifier on left side
-            let_byte_code.push(write_pop(&kind, idx));
-        }
-    
-        // Dump semicolon and return result 
-        let semicolon = self.token_tail.next().unwrap();
-        if semicolon.value != ";" {
-            panic!("End of let-statement expects ; but got {}", semicolon.value);
+        let Var {kind, typ:_, idx} = self.lookup(target)?;
+        match index {
+            Some(index) => {
+                // Anchor Array inside variable, add the offset
+                let_byte_code.push(write_push(&kind, idx));
+                let_byte_code.extend(self.compile_expression(index)?);
+                let_byte_code.push("add".to_string());
+                // Avoid stack-collision with the expression on the right side
+                let_byte_code.extend(self.compile_expression(value)?);
+                let_byte_code.extend(write_array_assignment());
+            },
+            None => {
+                // Assign expression to identifier on left side
+                let_byte_code.extend(self.compile_expression(value)?);
+                let_byte_code.push(write_pop(&kind, idx));
+            },
         }
-        let_byte_code
+        Ok(let_byte_code)
     }
     // Compile if-statement
-    fn compile_if_statement(&mut self) -> Vec<String> {
+    fn compile_if_statement(&mut self, condition: &Expression, statements: &[Statement], else_statements: Option<&[Statement]>) -> Result<Vec<String>, Diagnostic> {
         let mut if_byte_code = Vec::new();
         // Construct start-label (e.g. Myclass.Routine.$1)
-        let base_label = format!("{}.{}${}", self.class_name, self.get_subroutine_name(), self.label_count);
+        let base_label = format!("{}.{}${}", self.class_name, self.subroutine_name, self.label_count);
         if_byte_code.push(format!("label {}.IFSTART", base_label));
         self.label_count += 1;
 
-        // get keyword
-        let _if_keyword = self.token_tail.next().unwrap();
-
-        // Dump open paranthese
-        self.token_tail.next();
-        // Add all expression
-        if_byte_code.extend(self.compile_expression());
-        // Dump close paranthese
-        self.token_tail.next();
-
-        // Negate expression
+        if_byte_code.extend(self.compile_expression(condition)?);
+        // Jump to else if condition is true after "not" (so if condition resolves to false)
         if_byte_code.push("not".to_string());
-        // Construct else-label
         let else_label = format!("{}.ELSESTART", base_label);
-        // Jump to else if condition is true after "neg" (so if condition resolves to false)
         if_byte_code.push(format!("if-goto {}", else_label));
-        
-        // Dump opening curly-brace
-        self.token_tail.next();
-        // add statement-body (includes closing curly brace)
-        if_byte_code.extend(self.compile_statement_body());
 
+        if_byte_code.extend(self.compile_statements(statements));
         // If it got here -> if has been used and else must NOT be performed
         let end_label = format!("{}.IFEND", base_label);
         if_byte_code.push(format!("goto {}", end_label));
 
-        // Insert else-Label (to be able to jump to it)
-        // If it got here -> if must have evaluated to false
-        // So run else
+        // If it got here -> if must have evaluated to false, so run else (if present)
         if_byte_code.push(format!("label {}", else_label));
-        
-        // in case else is following the previous statement add it
-        if self.token_tail.peek().unwrap().value == "else" {
-            if_byte_code.extend(self.compile_else());
+        if let Some(else_statements) = else_statements {
+            if_byte_code.extend(self.compile_statements(else_statements));
         }
-
-        // Insert the end-label (no matter if else is present or not)
-        // If no else is present -> else-label is immediately followed by the end-label
         if_byte_code.push(format!("label {}", end_label));
-
-        // Return ruslting byte code
-        if_byte_code
+        Ok(if_byte_code)
     }
-    // Compile CONDITION statement "if, while"
-    fn compile_while_statement(&mut self) -> Vec<String> {
+    // Compile while-statement
+    fn compile_while_statement(&mut self, condition: &Expression, statements: &[Statement]) -> Result<Vec<String>, Diagnostic> {
         let mut while_byte_code = Vec::new();
         // Construct start-label (e.g. Myclass.Routine.$1)
-        let base_label = format!("{}.{}${}", self.class_name, self.get_subroutine_name(), self.label_count);
+        let base_label = format!("{}.{}${}", self.class_name, self.subroutine_name, self.label_count);
         let start_label = format!("{}.WHILESTART", base_label);
         while_byte_code.push(format!("label {}", start_label));
         self.label_count += 1;
 
-        // get keyword
-        let _while_keyword = self.token_tail.next().unwrap();
-
-        // Dump open paranthese
-        self.token_tail.next();
-        // Add all expression
-        while_byte_code.extend(self.compile_expression());
-        // Dump close paranthese
-        self.token_tail.next();
-
-        // Negate expression
-        while_byte_code.push("not".to_string());
-
-        // If true after neg (so condition evaluated to false)
+        while_byte_code.extend(self.compile_expression(condition)?);
+        // If true after not (so condition evaluated to false)
         // -> leave while loop / jump to end-label
+        while_byte_code.push("not".to_string());
         let end_label = format!("{}.WHILEEND", base_label);
         while_byte_code.push(format!("if-goto {}", end_label));
 
-        // Dump opening curly-brace
-        self.token_tail.next();
-        // add statement-body (includes closing curly brace)
-        while_byte_code.extend(self.compile_statement_body());
-
-        // If it got here -> loop statements are performed, so jump back
-        // to start of the while loop
+        while_byte_code.extend(self.compile_statements(statements));
+        // Jump back to start of the while loop
         while_byte_code.push(format!("goto {}", start_label));
-
-        // Insert the end-label (gets jumped to if while condition evaluates to false)
         while_byte_code.push(format!("label {}", end_label));
-
-        // Return ruslting condition as xml
-        while_byte_code
-    }
-    // Compile ELSE
-    fn compile_else(&mut self) -> Vec<String> {
-        let mut else_byte_code = Vec::new();
-        // Dump else-keyword
-        self.token_tail.next();
-        // Dump opening curly
-        self.token_tail.next();
-        // add else body
-        else_byte_code.extend(self.compile_statement_body());
-        
-        else_byte_code
-    }
-    // Compile DO
-    fn compile_do(&mut self) -> Vec<String> {
-        
-        let mut do_byte_code = Vec::new();
-    
-        // Add do, [className,.,] subroutine-call (which is some name and a expression-list)
-        let _do_keyword_token = self.token_tail.next().unwrap();
-    
-        do_byte_code.extend(self.compile_subroutine_call());
-    
-        // Next Token must be Semicolon -> dump it
-        let semicolon = self.token_tail.next().unwrap();
-        if  semicolon.value != ";" {
-            panic!("Do-Statement must be followed by ; but got '{}'", semicolon.value);
-        }
-        // Return zero since do-calls return void
-        do_byte_code.push("pop temp 0".to_string());
-        do_byte_code
+        Ok(while_byte_code)
     }
     // Compile Call to a Subroutine
-    fn compile_subroutine_call(&mut self) -> Vec<String> {
+    // The called subroutine has to exist and has to be called the way it's declared:
+    // methods on an object, functions and constructors on their class.
+    // Calls into a class, whose file could not be parsed, are compiled the way they are written.
+    fn compile_subroutine_call(&mut self, call: &SubroutineCall) -> Result<Vec<String>, Diagnostic> {
         let mut subroutine_call_byte_code = Vec::new();
-        let identifier_token = self.token_tail.next().unwrap();
-        let mut function_name = identifier_token.value.to_string();
-    
-        if self.token_tail.peek().unwrap().value == "." {
-            // Add dot and second-function-part to function_name
-            let dot_token = self.token_tail.next().unwrap();
-            let is_object = is_object(&function_name, &self.class_table, &self.subroutine_table);
-            if is_object {
-                // Override function-name with Class/Object-type, if it's an object where the method get's called on
-                function_name = get_object_type(&function_name, &self.class_table, &self.subroutine_table);
-                println!("Object_type: {}", function_name);
-            }
-            function_name.push_str(&dot_token.value);
-            let second_identifier_token = self.token_tail.next().unwrap();
-            function_name.push_str(&second_identifier_token.value);
-            // Dump opening paranthese
-            self.token_tail.next();
-            // Push this as first argument onto the stack if it's a method call
-            if is_object {
-                let Var {kind, typ:_, idx} = lookup(&identifier_token, &self.class_table, &self.subroutine_table);
-                let kind = if kind == "field" { "this".to_string() } else { kind };
-                subroutine_call_byte_code.push(format!("push {} {}", kind, idx));
-            }
-            // Push arguments onto the stack
-            let (args, expression_list_byte_code) = self.compile_expression_list();
-            subroutine_call_byte_code.extend(expression_list_byte_code);
-            // Call the function (add 1 to the args if it's a Method-call for this)
-            let args = args + if is_object { 1 } else { 0 };
-            let function_call = format!("call {} {}", function_name, args);
-            subroutine_call_byte_code.push(function_call);
-            // Dump closing paranthese
-            self.token_tail.next();
-        } else {
-            // Dump opening paranthese
-            self.token_tail.next();
-            // Push this as first argument onto the stack
-            subroutine_call_byte_code.push("push pointer 0".to_string());
-            let (args, expression_list_byte_code) = self.compile_expression_list();
-            subroutine_call_byte_code.extend(expression_list_byte_code);
-            let function_call = format!("call {}.{} {}", self.class_name, function_name, args + 1);
-            subroutine_call_byte_code.push(function_call);
-            // Dump closing paranthese
-            self.token_tail.next();
-        }
-        // Dump top value on the stack
-        // Do NOT Dump semicolon
-        subroutine_call_byte_code
-    }
-    // Compile EXPRESSION
-    fn compile_expression(&mut self) -> Vec<String> {
-        if self.token_tail.peek() == None {
-            panic!("compile_expression received a TokenStream with no next value. ")
-        }
-        let mut expression_byte_code = Vec::new();
-
-        // If no term, just return empty Vec
-        let next_token = self.token_tail.peek().unwrap().value.to_string();
-        if [")", ",", ";", "]"].contains(&next_token.as_ref())  {
-            return expression_byte_code;
-        }
-
-        // add term
-        expression_byte_code.extend(self.compile_term());
-        
-        expression_byte_code
+        let (class_name, signature, is_method) = match &call.receiver {
+            // A method called on an object: the object is the first argument
+            Some(object) if is_object(&object.name, &self.class_table, &self.subroutine_table) => {
+                let Var {kind, typ, idx} = self.lookup(object)?;
+                if ["int", "char", "boolean"].contains(&typ.as_str()) {
+                    return Err(Diagnostic::error(&object.span, format!("'{}' is of the type {}, that has no subroutines", object.name, typ)));
+                }
+                if !self.registry.has_class(&typ) {
+                    return Err(Diagnostic::error(&object.span, format!("The class '{}' of '{}' has not been declared", typ, object.name)));
+                }
+                let signature = self.signature(&typ, call)?;
+                if let Some(signature) = signature.as_ref().filter(|signature| signature.kind != SubroutineKind::Method) {
+                    return Err(Diagnostic::error(&call.span, format!("'{}.{}' is a {}, it can not be called on an object", typ, call.name.name, signature.kind))
+                        .with_note(format!("call it on the class: '{}.{}(...)'", typ, call.name.name)));
+                }
+                subroutine_call_byte_code.push(write_push(&kind, idx));
+                (typ, signature, true)
+            },
+            Some(class) => {
+                if !self.registry.has_class(&class.name) {
+                    return Err(Diagnostic::error(&class.span, format!("'{}' is neither a variable nor a class", class.name)));
+                }
+                let signature = self.signature(&class.name, call)?;
+                if signature.as_ref().is_some_and(|signature| signature.kind == SubroutineKind::Method) {
+                    return Err(Diagnostic::error(&call.span, format!("'{}.{}' is a method, it has to be called on an object", class.name, call.name.name))
+                        .with_note(format!("call it on a variable of the class: 'x.{}(...)'", call.name.name)));
+                }
+                (class.name.to_string(), signature, false)
+            },
+            // A subroutine of this class, methods are called on this
+            None => {
+                let signature = self.signature(self.class_name, call)?;
+                let is_method = signature.as_ref().is_some_and(|signature| signature.kind == SubroutineKind::Method);
+                if is_method {
+                    if self.subroutine_kind == SubroutineKind::Function {
+                        return Err(Diagnostic::error(&call.name.span, format!("The method '{}' can not be called in the function '{}'", call.name.name, self.subroutine_name))
+                            .with_note("functions have no object ('this') to call methods on".to_string()));
+                    }
+                    subroutine_call_byte_code.push("push pointer 0".to_string());
+                }
+                (self.class_name.to_string(), signature, is_method)
+            },
+        };
+        let args = call.arguments.len();
+        if let Some(signature) = signature.filter(|signature| signature.parameters.len() != args) {
+            let expected = match signature.parameters.len() {
+                1 => "1 argument".to_string(),
+                count => format!("{} arguments", count),
+            };
+            return Err(Diagnostic::error(&call.span, format!("'{}.{}' takes {}, but got {}", class_name, call.name.name, expected, args)));
+        }
+        // Push arguments onto the stack (after the object of a method)
+        subroutine_call_byte_code.extend(self.compile_expression_list(&call.arguments)?);
+        let this = if is_method { 1 } else { 0 };
+        subroutine_call_byte_code.push(format!("call {}.{} {}", class_name, call.name.name, args + this));
+        Ok(subroutine_call_byte_code)
     }
-
-    fn compile_expression_list(&mut self) -> (u32, Vec<String>) {
+    // Pushes the arguments onto the stack
+    fn compile_expression_list(&mut self, arguments: &[Expression]) -> Result<Vec<String>, Diagnostic> {
         let mut expression_list_byte_code = Vec::new();
-        if self.token_tail.peek().unwrap().value == ")" {
-            return (0, expression_list_byte_code);
-        }
-        let mut var_count = 0;
-        loop {
-            expression_list_byte_code.extend(self.compile_expression());
-            var_count += 1;
-            let next_token = self.token_tail.peek().unwrap();
-            if next_token.value != "," {
-                break;
-            }
-            // Dump Comma
-            self.token_tail.next();
+        for argument in arguments {
+            expression_list_byte_code.extend(self.compile_expression(argument)?);
         }
-        (var_count, expression_list_byte_code)
+        Ok(expression_list_byte_code)
     }
-
-    // Compile RETURN
-    fn compile_return(&mut self) -> Vec<String> {
-        
-        let mut return_byte_code = Vec::new();
-        // add return-keyword
-        let maybe_return_token = self.token_tail.next();
-        // add expressions (if present)
-        return_byte_code.extend(self.compile_expression());
-
-        return_byte_code.push(write_return(&self.get_subroutine()));
-        if let Some(return_token) = maybe_return_token {
-            match return_token.value.as_ref() {
-                "return" => return_byte_code.push(String::from("return\n")),
-                other => panic!("Expected return-keyword but got '{}'", other),
+    // Compile EXPRESSION
+    // The operations are applied from the left to the right: `a - b - c` is `(a - b) - c`.
+    // With precedence an operation waits for the following ones, that bind stronger.
+    fn compile_expression(&mut self, expression: &Expression) -> Result<Vec<String>, Diagnostic> {
+        let mut expression_byte_code = self.compile_term(&expression.term)?;
+        let mut waiting_ops: Vec<Op> = Vec::new();
+        for (op, term) in &expression.operations {
+            while let Some(waiting_op) = waiting_ops.last().copied().filter(|waiting_op| self.precedence(waiting_op) >= self.precedence(op)) {
+                waiting_ops.pop();
+                expression_byte_code.push(write_op(&waiting_op));
             }
-        } else {
-            panic!("Expected return-keyword but Token was NONE!");
+            waiting_ops.push(*op);
+            expression_byte_code.extend(self.compile_term(term)?);
         }
-        
-        // Ignore semicolon after return-statement
-        let semicolon = self.token_tail.next().unwrap();
-        if semicolon.value != ";" {
-            panic!("Expected ; at the end of return but got '{:?}", semicolon);
+        for op in waiting_ops.iter().rev() {
+            expression_byte_code.push(write_op(op));
         }
-        return_byte_code
+        Ok(expression_byte_code)
+    }
+    fn precedence(&self, op: &Op) -> u8 {
+        if self.precedence { op.precedence() } else { 0 }
     }
     // Compile term
-    fn compile_term(&mut self) -> Vec<String> {
+    fn compile_term(&mut self, term: &Term) -> Result<Vec<String>, Diagnostic> {
         let mut term_byte_code = Vec::new();
-        // add subunits of term if present
-        let token = self.token_tail.peek().unwrap();
-        // Handle boolean-values
-        match token.token_type {
-            TokenType::StringConstant => {
-                let string_token = self.token_tail.next().unwrap();
-                term_byte_code.extend(write_string(&string_token .value));
+        match &term.kind {
+            TermKind::Integer(value) => term_byte_code.push(write_push("constant", u32::from(*value))),
+            TermKind::String(string) => term_byte_code.extend(write_string(string)),
+            TermKind::Keyword(KeywordConstant::True) => {
+                term_byte_code.push("push constant 1".to_string());
+                term_byte_code.push("neg".to_string());
             },
-            TokenType::Keyword => {
-                let keyword_token = self.token_tail.next().unwrap();
-                match keyword_token.value.as_ref() {
-                    "true" => {
-                        term_byte_code.push("push constant 1".to_string());
-                        term_byte_code.push("neg".to_string());
-                    },
-                    "false" => {
-                        term_byte_code.push("push constant 0".to_string());
-                    },
-                    "this" => {
-                        term_byte_code.push("push pointer 0".to_string());
-                    },
-                    "null" => {
-                        term_byte_code.push("push constant 0".to_string());
-                    },
-                    _ => panic!("Expected keyword true, false or this but '{}' was passed", keyword_token.value),
-                }
+            TermKind::Keyword(KeywordConstant::False) | TermKind::Keyword(KeywordConstant::Null) => {
+                term_byte_code.push("push constant 0".to_string());
             },
-            TokenType::Symbol => {
-                match token.value.as_ref() {
-                    // If Term ends -> return
-                    ")" => {}, // !!! is never reached !!!!
-                    ";" => {
-                        // Do NOT Dump Semicolon
-                        println!("Semicolon break happened in compile_term {}", token.value);
-                    },
-                    // Handle Term in parantheses
-                    "(" => {
-                        // Dump open paranthese
-                        self.token_tail.next();
-                        // Add Expression inside parantheses
-                        term_byte_code.extend(self.compile_expression());
-                        // Dump closing paranthese
-                        self.token_tail.next();
-                        term_byte_code.extend(self.handle_maybe_op());
-                    },
-                    // Handle unary-operators
-                    "-" | "~" => {
-                        let unaray_op = self.token_tail.next().unwrap();
-                        term_byte_code.extend(self.compile_term());
-                        term_byte_code.push(write_unary_op(unaray_op));
-                    },
-                    _ => panic!("Symbol '{}' should not have landed in compile_term", token.value),
+            TermKind::Keyword(KeywordConstant::This) => {
+                if self.subroutine_kind == SubroutineKind::Function {
+                    return Err(Diagnostic::error(&term.span, format!("'this' can not be used in the function '{}'", self.subroutine_name))
+                        .with_note("functions are called without an object, only methods and constructors have 'this'".to_string()));
                 }
+                term_byte_code.push("push pointer 0".to_string());
             },
-            // Must be single Term (IntegerConstant or Variable)
-            // So resolve the term and check for possibly more Operators, followed by more term(s)
-            TokenType::Identifier | TokenType::IntegerConstant => {
-                // Save current State of tokens withouth moving cursor
-                let tokens_cloned = self.token_tail.clone();
-                // Get next token
-                let term = self.token_tail.next().unwrap();
-                // Peek one token further ahead
-                let next_token = self.token_tail.peek().unwrap().clone();
-                if [".", "("].contains(&next_token.value.as_ref()) {
-                    // Reassign the Token-Clone as token_tail, so that the first part
-                    // of the call statement is not picked off already
-                    self.token_tail = tokens_cloned;
-                    term_byte_code.extend(self.compile_subroutine_call());
-                    // EXIT the function
-                    return term_byte_code;
-                }
-                // Here it is not a function call, but could be an Array-access
-                let Var {kind, typ:_, idx} = lookup(&term, &self.class_table, &self.subroutine_table);
-                if next_token.value == "[" {
-                    // Anchor Array
-                    term_byte_code.push(format!("push {} {}", kind, idx));
-                    // Dump opening bracket
-                    self.token_tail.next();
-                    // Push expression inside brackets (offset) onto stack
-                    term_byte_code.extend(self.compile_expression());
-                    // Dump closing bracket()
-                    self.token_tail.next();
-                    // Add offset to Array-Base-Address
-                    term_byte_code.push("add".to_string());
-                    // Access the specific Array-index
-                    term_byte_code.push("pop pointer 1".to_string());
-                    term_byte_code.push("push that 0".to_string());
-                    //return term_byte_code;
-                } else {
-                    // It's not an array so just push the
-                    // variable/IntegerConstant onto the stack
-                    term_byte_code.push(write_push(&kind, idx));
-                }
-                // There might be an op and then more terms -> handle that case
-                // (Must peek again because the cursor might have moved during
-                // Array-indexing part above)
-                let maybe_op = self.token_tail.peek().unwrap();
-                if OPERATORS.contains(&maybe_op.value.as_ref()) {
-                    // Save the operator to use as postfix
-                    let op = self.token_tail.next().unwrap();
-                    // Add next term after op
-                    term_byte_code.extend(self.compile_term());
-                    // Add op as postfix
-                    term_byte_code.push(write_op(op));
-                }
+            TermKind::Var(name) => {
+                let Var {kind, typ:_, idx} = self.lookup(name)?;
+                term_byte_code.push(write_push(&kind, idx));
+            },
+            TermKind::Index(name, index) => {
+                // Anchor Array and add the offset
+                let Var {kind, typ:_, idx} = self.lookup(name)?;
+                term_byte_code.push(write_push(&kind, idx));
+                term_byte_code.extend(self.compile_expression(index)?);
+                term_byte_code.push("add".to_string());
+                // Access the specific Array-index
+                term_byte_code.push("pop pointer 1".to_string());
+                term_byte_code.push("push that 0".to_string());
+            },
+            TermKind::Call(call) => term_byte_code.extend(self.compile_subroutine_call(call)?),
+            TermKind::Parenthesized(expression) => term_byte_code.extend(self.compile_expression(expression)?),
+            TermKind::Unary(op, term) => {
+                term_byte_code.extend(self.compile_term(term)?);
+                term_byte_code.push(write_unary_op(op));
             },
         }
-        term_byte_code
-    }
-
-    fn handle_maybe_op(&mut self) -> Vec<String> {
-        let mut op_byte_code = Vec::new();
-        let maybe_op = self.token_tail.peek().unwrap();
-        if OPERATORS.contains(&maybe_op.value.as_ref()) {
-            let op = self.token_tail.next().unwrap();
-            op_byte_code.extend(self.compile_term());
-            op_byte_code.push(write_op(op));
-        }
-        op_byte_code
+        Ok(term_byte_code)
     }
 }
 
-// Check if valid class, right in the beginning
-fn is_class_var_start(maybe_token: Option<&&Token>) -> bool {
-    let maybe_class_var = &maybe_token.unwrap().value;
-    maybe_class_var == "static" || maybe_class_var == "field"
+
+// If the statements can not be left without a return (a while can always be skipped)
+fn returns_on_every_path(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Return { .. } => true,
+        Statement::If { statements, else_statements: Some(else_statements), .. } => {
+            returns_on_every_path(statements) && returns_on_every_path(else_statements)
+        },
+        _ => false,
+    })
 }
 
+
 // Helper to debut VM-Code
 fn _debug() -> Vec::<String> {
     let mut debug_calls = Vec::new();
@@ -747,6 +578,219 @@ fn _debug() -> Vec::<String> {
 }
 
 
+// Compiles a class, that has errors, and renders them
+#[cfg(test)]
+fn compile_errors(source: &str) -> Vec<Diagnostic> {
+    let tokens = crate::tokenizer::tokenize_file(source, "Main.jack").unwrap();
+    Compiler::new(&tokens, "Main").with_source(source).analyze_tokens().unwrap_err()
+}
+
+#[test]
+fn source_map_points_at_subroutines_and_statements() {
+    let tokens = crate::tokenizer::tokenize("class Main {\n  function void main() {\n    do Output.printInt(1);\n    return;\n  }\n}");
+    let mut compiler = Compiler::new(&tokens, "Main");
+    let vm_code = compiler.analyze_tokens().unwrap().join("\n");
+    let vm_lines: Vec<&str> = vm_code.split('\n').collect();
+    assert_eq!(compiler.source_map(), "4 Main.jack:2\n6 Main.jack:3\n9 Main.jack:4");
+    assert_eq!(vm_lines[3], "function Main.main 0");
+    assert_eq!(vm_lines[5], "push constant 1");
+    assert_eq!(vm_lines[8], "push constant 0");
+}
+
+#[test]
+fn errors_show_the_line_of_the_token() {
+    let source = "class Main {\n    function void main() {\n        var int x;\n        let x = y;\n        return;\n    }\n}";
+    let errors = compile_errors(source);
+    assert_eq!(crate::diagnostic::render_all(&errors, source), "error: Variable 'y' has not been declared
+ --> Main.jack:4:17
+  |
+4 |         let x = y;
+  |                 ^
+  = note: variables are declared with 'var', 'field' or 'static', or as a parameter");
+}
+
+#[test]
+fn missing_semicolons_are_reported_at_the_next_token() {
+    let source = "class Main {\n    function void main() {\n        do Main.main()\n        return;\n    }\n}";
+    let errors = compile_errors(source);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].render(source).starts_with("error: Expected ';' at the end of the do statement, but got 'return'\n --> Main.jack:4:9"));
+}
+
+#[test]
+fn end_of_file_is_reported_after_the_last_token() {
+    let source = "class Main {\n    function void main() {";
+    let errors = compile_errors(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].render(source), "error: Unexpected end of file\n --> Main.jack:2:27\n  |\n2 |     function void main() {\n  |                           ^");
+}
+
+#[test]
+fn compilation_continues_behind_broken_statements() {
+    let source = "class Main {
+    function void main() {
+        var int x;
+        let x = 1
+        let x = y;
+        if (x) { do Main.g(; } else { let x = 2; }
+        return;
+        let x = 3;
+    }
+    let x = 4;
+    function int f() {
+        return z;
+    }
+}";
+    let diagnostics: Vec<String> = compile_errors(source).iter().map(|diagnostic| format!("{} {}", diagnostic.level, diagnostic)).collect();
+    assert_eq!(diagnostics, vec![
+        "error Main.jack:5:9: Expected ';' at the end of the let statement, but got 'let'",
+        "error Main.jack:5:17: Variable 'y' has not been declared",
+        "error Main.jack:6:28: Expected a term, but got ';'",
+        "warning Main.jack:8:9: Unreachable statement",
+        "error Main.jack:10:5: Expected a subroutine (constructor, function or method), but got 'let'",
+        "error Main.jack:12:16: Variable 'z' has not been declared",
+    ]);
+}
+
+#[test]
+fn warnings_do_not_stop_the_compilation() {
+    let tokens = crate::tokenizer::tokenize("class Main { function void main() { return; do Main.main(); } }");
+    let mut compiler = Compiler::new(&tokens, "Main");
+    assert!(compiler.analyze_tokens().is_ok());
+    assert_eq!(compiler.warnings().len(), 1);
+    assert_eq!(compiler.warnings()[0].notes, vec!["it follows the return in line 1".to_string()]);
+}
+
+#[test]
+fn returns_have_to_match_the_subroutine() {
+    let source = "class Main {
+    field int x;
+    constructor Main new() {
+        let x = 1;
+        return x;
+    }
+    function void main() {
+        return 3;
+    }
+    function int f(boolean b) {
+        if (b) {
+            return;
+        }
+        while (b) {
+            return 1;
+        }
+    }
+    method int g(boolean b) {
+        if (b) {
+            return 1;
+        } else {
+            return 2;
+        }
+        let x = 3;
+    }
+}";
+    let diagnostics: Vec<String> = compile_errors(source).iter().map(|diagnostic| format!("{} {}", diagnostic.level, diagnostic)).collect();
+    assert_eq!(diagnostics, vec![
+        "error Main.jack:5:16: The constructor 'Main.new' has to return 'this'",
+        "error Main.jack:8:16: 'Main.main' is void, it can not return a value",
+        "error Main.jack:10:18: 'Main.f' does not return int on every path",
+        "error Main.jack:12:13: 'Main.f' has to return int, but the return has no value",
+        "warning Main.jack:24:9: Unreachable statement",
+    ]);
+    assert_eq!(compile_errors(source)[4].notes, vec!["it follows the if in line 19, that returns on every path".to_string()]);
+}
+
+#[test]
+fn values_are_returned_on_the_stack() {
+    let tokens = crate::tokenizer::tokenize("class Main { function int f() { return 7; } function void g() { return; } }");
+    let vm_code = Compiler::new(&tokens, "Main").analyze_tokens().unwrap().join("\n");
+    assert!(vm_code.contains("function Main.f 0\n\npush constant 7\nreturn\n"));
+    assert!(vm_code.contains("function Main.g 0\n\npush constant 0\nreturn\n"));
+}
+
+#[test]
+fn strict_mode_makes_warnings_errors() {
+    let tokens = crate::tokenizer::tokenize("class Main { function void main() { var int i; let i = true; return; } }");
+    let mut compiler = Compiler::new(&tokens, "Main").with_type_check(true);
+    assert!(compiler.analyze_tokens().is_ok());
+    assert_eq!(compiler.warnings()[0].message, "Mismatched types: 'i' is int, but the value is boolean");
+    let errors = Compiler::new(&tokens, "Main").with_type_check(true).with_strict(true).analyze_tokens().unwrap_err();
+    assert!(errors[0].is_error());
+    assert_eq!(errors[0].notes, vec!["warnings are errors in strict mode".to_string()]);
+    // Without the type check, there is nothing to warn about
+    assert!(Compiler::new(&tokens, "Main").with_strict(true).analyze_tokens().is_ok());
+}
+
+#[test]
+fn calls_are_checked_against_the_signatures() {
+    let source = "class Main {
+    field int size;
+    function void main() {
+        var Main m;
+        var int i;
+        do Math.cube(2);
+        do Math.multiply(2);
+        do Main.draw();
+        do m.main();
+        do i.draw();
+        do Point.new();
+        do draw();
+        let i = size;
+        let m = this;
+        return;
+    }
+    method void draw() {
+        do draw();
+        do m.draw();
+        return;
+    }
+}";
+    let diagnostics: Vec<String> = compile_errors(source).iter().map(|diagnostic| diagnostic.to_string()).collect();
+    assert_eq!(diagnostics, vec![
+        "Main.jack:6:17: Class 'Math' has no subroutine 'cube'",
+        "Main.jack:7:12: 'Math.multiply' takes 2 arguments, but got 1",
+        "Main.jack:8:12: 'Main.draw' is a method, it has to be called on an object",
+        "Main.jack:9:12: 'Main.main' is a function, it can not be called on an object",
+        "Main.jack:10:12: 'i' is of the type int, that has no subroutines",
+        "Main.jack:11:12: 'Point' is neither a variable nor a class",
+        "Main.jack:12:12: The method 'draw' can not be called in the function 'main'",
+        "Main.jack:13:17: The field 'size' can not be used in the function 'main'",
+        "Main.jack:14:17: 'this' can not be used in the function 'main'",
+        "Main.jack:19:12: 'm' is neither a variable nor a class",
+    ]);
+}
+
+#[test]
+fn calls_to_other_classes_use_the_registry() {
+    let classes = ["class Main { function void main() { var Point p; let p = Point.new(1); do p.show(); return; } }",
+        "class Point { field int x; constructor Point new(int ax) { let x = ax; return this; } method void show() { do Output.printInt(x); return; } }"];
+    let tokens: Vec<Vec<Token>> = classes.iter().map(|class| crate::tokenizer::tokenize(class)).collect();
+    let mut registry = Registry::with_os();
+    for (tokens, file_name) in tokens.iter().zip(&["Main.jack", "Point.jack"]) {
+        registry.add_class(&crate::parser::parse(tokens, file_name).unwrap());
+    }
+    let main = Compiler::new(&tokens[0], "Main").with_registry(&registry).analyze_tokens().unwrap().join("\n");
+    assert!(main.contains("push constant 1\ncall Point.new 1\npop local 0\npush local 0\ncall Point.show 1"));
+    // Without the registry of the program, Point is unknown
+    assert!(Compiler::new(&tokens[0], "Main").analyze_tokens().is_err());
+}
+
+#[test]
+fn arrays_in_fields_are_indexed_through_this() {
+    let tokens = crate::tokenizer::tokenize("class Main { field Array a; method int f() { let a[1] = a[0]; return a[1]; } }");
+    let vm_code = Compiler::new(&tokens, "Main").analyze_tokens().unwrap().join("\n");
+    assert!(vm_code.contains("push this 0\npush constant 1\nadd\npush this 0\npush constant 0\nadd\n"));
+    assert!(!vm_code.contains("field"));
+}
+
+#[test]
+fn methods_on_fields_are_called_through_this() {
+    let tokens = crate::tokenizer::tokenize("class Main { field Main m; method void f() { do m.f(); return; } }");
+    let vm_code = Compiler::new(&tokens, "Main").analyze_tokens().unwrap().join("\n");
+    assert!(vm_code.contains("push this 0\ncall Main.f 1"));
+}
+
+
 // #######################
 // #######  TESTS  #######
 // #######################
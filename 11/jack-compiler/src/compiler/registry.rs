@@ -0,0 +1,140 @@
+use std::collections::{ HashMap, HashSet };
+use lazy_static::lazy_static;
+use crate::ast::{ Class, ReturnType, SubroutineKind, Type };
+use crate::parser::parse;
+use crate::tokenizer::tokenize_file;
+
+// The OS of the computer (project 12), it's classes can be called by every program
+static OS_CLASSES: &[(&str, &str)] = &[
+    ("Array.jack", include_str!("../../../../12/src/Array.jack")),
+    ("Keyboard.jack", include_str!("../../../../12/src/Keyboard.jack")),
+    ("Math.jack", include_str!("../../../../12/src/Math.jack")),
+    ("Memory.jack", include_str!("../../../../12/src/Memory.jack")),
+    ("Output.jack", include_str!("../../../../12/src/Output.jack")),
+    ("Screen.jack", include_str!("../../../../12/src/Screen.jack")),
+    ("String.jack", include_str!("../../../../12/src/String.jack")),
+    ("Sys.jack", include_str!("../../../../12/src/Sys.jack")),
+];
+
+// What the callers of a subroutine need to know about it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+    pub kind: SubroutineKind,
+    pub return_type: ReturnType,
+    pub parameters: Vec<Type>,
+}
+
+// The signatures of the subroutines of every class of a program (and of the OS),
+// they are collected before the classes are compiled, so calls can be checked.
+#[derive(Debug, Default, Clone)]
+pub struct Registry {
+    classes: HashMap<String, HashMap<String, Signature>>,
+    // The classes of files, that could not be parsed: their subroutines are unknown
+    unchecked: HashSet<String>,
+}
+
+lazy_static! {
+    // The OS classes are only parsed once, every registry starts with a copy of them
+    static ref OS_REGISTRY: Registry = {
+        let mut registry = Registry::default();
+        for (file_name, code) in OS_CLASSES {
+            let tokens = tokenize_file(code, file_name).expect("the OS classes can be tokenized");
+            let class = parse(&tokens, file_name).expect("the OS classes can be parsed");
+            registry.insert_class(&class);
+        }
+        registry
+    };
+}
+
+impl Registry {
+    // A registry with the classes of the OS
+    pub fn with_os() -> Self {
+        OS_REGISTRY.clone()
+    }
+    // Registers the subroutines of a class
+    // (a class of the program replaces the OS class of the same name).
+    pub fn add_class(&mut self, class: &Class) {
+        self.insert_class(class);
+        // A program with it's own Sys class is the OS (or a test of it), Sys.init calls
+        // the entry point of the programs, that run on it (a Main class replaces it)
+        if class.name.name == "Sys" && !self.has_class("Main") {
+            let main = Signature { kind: SubroutineKind::Function, return_type: ReturnType::Void, parameters: Vec::new() };
+            self.classes.insert("Main".to_string(), vec![("main".to_string(), main)].into_iter().collect());
+        }
+    }
+    // Registers a class, whose file could not be parsed (by the name of the file),
+    // calls into it are not checked.
+    pub fn add_unchecked_class(&mut self, class_name: &str) {
+        self.classes.remove(class_name);
+        self.unchecked.insert(class_name.to_string());
+    }
+    fn insert_class(&mut self, class: &Class) {
+        let subroutines = class.subroutines
+            .iter()
+            .map(|subroutine| {
+                let signature = Signature {
+                    kind: subroutine.kind,
+                    return_type: subroutine.return_type.clone(),
+                    parameters: subroutine.parameters.iter().map(|parameter| parameter.typ.clone()).collect(),
+                };
+                (subroutine.name.name.to_string(), signature)
+            })
+            .collect();
+        self.classes.insert(class.name.name.to_string(), subroutines);
+    }
+    pub fn has_class(&self, class_name: &str) -> bool {
+        self.classes.contains_key(class_name) || self.is_unchecked(class_name)
+    }
+    pub fn is_unchecked(&self, class_name: &str) -> bool {
+        self.unchecked.contains(class_name)
+    }
+    pub fn get(&self, class_name: &str, subroutine_name: &str) -> Option<&Signature> {
+        self.classes.get(class_name)?.get(subroutine_name)
+    }
+}
+
+// TESTS
+#[test]
+fn os_signatures_are_registered() {
+    let registry = Registry::with_os();
+    let multiply = registry.get("Math", "multiply").unwrap();
+    assert_eq!(multiply.kind, SubroutineKind::Function);
+    assert_eq!(multiply.return_type, ReturnType::Type(Type::Int));
+    assert_eq!(multiply.parameters, vec![Type::Int, Type::Int]);
+    assert_eq!(registry.get("String", "appendChar").unwrap().kind, SubroutineKind::Method);
+    assert_eq!(registry.get("String", "new").unwrap().kind, SubroutineKind::Constructor);
+    assert!(registry.has_class("Keyboard"));
+    assert_eq!(registry.get("Math", "cube"), None);
+}
+
+#[test]
+fn program_classes_replace_os_classes() {
+    let tokens = crate::tokenizer::tokenize("class Sys { function void init() { return; } }");
+    let mut registry = Registry::with_os();
+    registry.add_class(&parse(&tokens, "Sys.jack").unwrap());
+    assert!(registry.get("Sys", "init").is_some());
+    assert_eq!(registry.get("Sys", "halt"), None);
+}
+
+#[test]
+fn only_a_sys_class_of_the_program_registers_main() {
+    assert!(!Registry::with_os().has_class("Main"));
+    let mut registry = Registry::with_os();
+    registry.add_class(&parse(&crate::tokenizer::tokenize("class Sys { function void init() { return; } }"), "Sys.jack").unwrap());
+    assert!(registry.get("Main", "main").is_some());
+    // The Main class of the program is kept
+    let mut registry = Registry::with_os();
+    registry.add_class(&parse(&crate::tokenizer::tokenize("class Main { function int main() { return 0; } }"), "Main.jack").unwrap());
+    registry.add_class(&parse(&crate::tokenizer::tokenize("class Sys { function void init() { return; } }"), "Sys.jack").unwrap());
+    assert_eq!(registry.get("Main", "main").unwrap().return_type, ReturnType::Type(Type::Int));
+}
+
+#[test]
+fn unchecked_classes_have_no_signatures() {
+    let mut registry = Registry::with_os();
+    registry.add_unchecked_class("Point");
+    registry.add_unchecked_class("Math");
+    assert!(registry.has_class("Point") && registry.is_unchecked("Point"));
+    assert_eq!(registry.get("Math", "multiply"), None);
+    assert!(!registry.is_unchecked("Output"));
+}
@@ -1,31 +1,15 @@
 use std::collections::HashMap;
-use crate::tokenizer::token::{ Token, TokenType };
+use crate::diagnostic::Diagnostic;
+use crate::ast::Ident;
 
-pub fn lookup(var: &Token, class_table: &ClassTable, subroutine_table: &SubroutineTable) -> Var {
-    match var.token_type {
-        TokenType::IntegerConstant => {
-            let value = var.value.parse::<u32>().unwrap();
-            Var::new("constant", "_", value)
-        },
-        TokenType::Identifier => {
-            match subroutine_table.get(&var.value) {
-                Some(var) => var,
-                None => match class_table.get(&var.value) {
-                    Some(var) => var,
-                    None => panic!("Variable '{:?}' has not been declared.", var.value),
-                }
-            }
-        },
-        _ => panic!("Lookup for token-type '{:?}' with value '{}' is not implemented", var.token_type, var.value),
-    }
-}
-
-pub fn get_object_type(identifier: &str, class_table: &ClassTable, subroutine_table: &SubroutineTable) -> String {
-    match subroutine_table.get(identifier) {
-        Some(var) => var.typ,
-        None => match class_table.get(identifier) {
-            Some(var) => var.typ,
-            None => panic!("Identifier '{}' has not been declared", identifier),
+// Finds the variable of an identifier, errors point at the identifier
+pub fn lookup(var: &Ident, class_table: &ClassTable, subroutine_table: &SubroutineTable) -> Result<Var, Diagnostic> {
+    match subroutine_table.get(&var.name) {
+        Some(var) => Ok(var),
+        None => match class_table.get(&var.name) {
+            Some(var) => Ok(var),
+            None => Err(Diagnostic::error(&var.span, format!("Variable '{}' has not been declared", var.name))
+                .with_note("variables are declared with 'var', 'field' or 'static', or as a parameter".to_string())),
         }
     }
 }
@@ -196,4 +180,10 @@ fn get_next_idx_on_class_table_works() {
     class_table.add("myType", dummy_var.clone());
     assert_eq!(class_table.get_next_idx("field"), 1);
     assert_eq!(class_table.get_next_idx("static"), 0);
-}
\ No newline at end of file
+}
+#[test]
+fn undeclared_variables_are_errors_at_their_token() {
+    let name = Ident { name: "y".to_string(), span: crate::tokenizer::token::Span::new("Main.jack", 4, 17, 60..61) };
+    let error = lookup(&name, &ClassTable::default(), &SubroutineTable::default()).unwrap_err();
+    assert_eq!(error.to_string(), "Main.jack:4:17: Variable 'y' has not been declared");
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use super::registry::Registry;
+
+// The type of a value, as far as the checker knows it
+#[derive(Debug, PartialEq, Clone)]
+enum Value {
+    Typed(Type),
+    Null,
+    // Array elements (and arrays), undeclared variables and unknown subroutines
+    // (the compiler reports the last two), they fit everywhere.
+    Unknown,
+}
+
+impl Value {
+    // An Array is only an address, it can be used as any type
+    fn of(typ: &Type) -> Value {
+        match typ {
+            Type::Class(name) if name == "Array" => Value::Unknown,
+            typ => Value::Typed(typ.clone()),
+        }
+    }
+    // Characters are their codes, so chars are numbers like ints
+    fn is_number(&self) -> bool {
+        matches!(self, Value::Typed(Type::Int) | Value::Typed(Type::Char) | Value::Unknown)
+    }
+    fn is_boolean(&self) -> bool {
+        matches!(self, Value::Typed(Type::Boolean) | Value::Unknown)
+    }
+    // If a value of the one type can be used as the other (null is an object of every class)
+    fn fits(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Unknown, _) | (_, Value::Unknown) => true,
+            (Value::Null, Value::Typed(Type::Class(_))) | (Value::Typed(Type::Class(_)), Value::Null) => true,
+            (value, other) => value == other || (value.is_number() && other.is_number()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Typed(typ) => write!(f, "{}", typ),
+            Value::Null => write!(f, "null"),
+            Value::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+// Checks the types of the values in a class (opt-in with --check-types).
+// Jack does not need the types to compile, so mismatches are warnings.
+pub fn check_types(class: &Class, registry: &Registry, precedence: bool) -> Vec<Diagnostic> {
+    let mut checker = TypeChecker {
+        registry,
+        precedence,
+        class_name: class.name.name.to_string(),
+        class_vars: HashMap::new(),
+        vars: HashMap::new(),
+        subroutine_name: String::new(),
+        return_type: ReturnType::Void,
+        warnings: Vec::new(),
+    };
+    for var_dec in &class.vars {
+        for name in &var_dec.names {
+            checker.declare(&var_dec.typ, name, format!("of '{}'", name.name));
+            checker.class_vars.insert(name.name.to_string(), var_dec.typ.clone());
+        }
+    }
+    for subroutine in &class.subroutines {
+        checker.check_subroutine(subroutine);
+    }
+    checker.warnings
+}
+
+struct TypeChecker<'a> {
+    registry: &'a Registry,
+    precedence: bool,
+    class_name: String,
+    class_vars: HashMap<String, Type>,
+    // The parameters and local vars of the subroutine
+    vars: HashMap<String, Type>,
+    subroutine_name: String,
+    return_type: ReturnType,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'a> TypeChecker<'a> {
+    fn warn(&mut self, expression: &Expression, message: String) {
+        self.warnings.push(Diagnostic::warning(&expression.span, format!("Mismatched types: {}", message)));
+    }
+    fn var_type(&self, name: &Ident) -> Option<Type> {
+        self.vars.get(&name.name).or_else(|| self.class_vars.get(&name.name)).cloned()
+    }
+    // A class, that has not been declared, is reported at the declaration (see declare),
+    // it's values are unknown.
+    fn value_of(&self, typ: &Type) -> Value {
+        match typ {
+            Type::Class(class_name) if !self.registry.has_class(class_name) => Value::Unknown,
+            typ => Value::of(typ),
+        }
+    }
+    // The type of a declared variable has to be a known class (or int, char or boolean)
+    fn declare(&mut self, typ: &Type, name: &Ident, declaration: String) {
+        if let Type::Class(class_name) = typ {
+            if !self.registry.has_class(class_name) {
+                self.warnings.push(Diagnostic::warning(&name.span, format!("Unknown type: the class '{}' {} has not been declared", class_name, declaration)));
+            }
+        }
+    }
+
+    fn check_subroutine(&mut self, subroutine: &Subroutine) {
+        self.vars = HashMap::new();
+        self.subroutine_name = subroutine.name.name.to_string();
+        self.return_type = subroutine.return_type.clone();
+        if let ReturnType::Type(typ) = &subroutine.return_type {
+            self.declare(typ, &subroutine.name, format!("returned by '{}.{}'", self.class_name, subroutine.name.name));
+        }
+        for parameter in &subroutine.parameters {
+            self.declare(&parameter.typ, &parameter.name, format!("of '{}'", parameter.name.name));
+            self.vars.insert(parameter.name.name.to_string(), parameter.typ.clone());
+        }
+        for var_dec in &subroutine.locals {
+            for name in &var_dec.names {
+                self.declare(&var_dec.typ, name, format!("of '{}'", name.name));
+                self.vars.insert(name.name.to_string(), var_dec.typ.clone());
+            }
+        }
+        self.check_statements(&subroutine.statements);
+    }
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Let { target, index, value, .. } => {
+                    let value_type = self.check_expression(value);
+                    match (index, self.var_type(target)) {
+                        // The elements of an array can be anything
+                        (Some(index), _) => self.check_number(index, "an array index"),
+                        (None, Some(typ)) if !self.value_of(&typ).fits(&value_type) => {
+                            self.warn(value, format!("'{}' is {}, but the value is {}", target.name, typ, value_type));
+                        },
+                        (None, _) => {},
+                    }
+                },
+                Statement::If { condition, statements, else_statements, .. } => {
+                    self.check_condition(condition, "if");
+                    self.check_statements(statements);
+                    if let Some(else_statements) = else_statements {
+                        self.check_statements(else_statements);
+                    }
+                },
+                Statement::While { condition, statements, .. } => {
+                    self.check_condition(condition, "while");
+                    self.check_statements(statements);
+                },
+                Statement::Do { call, .. } => {
+                    self.check_call(call);
+                },
+                Statement::Return { value: Some(value), .. } => {
+                    let value_type = self.check_expression(value);
+                    if let ReturnType::Type(typ) = self.return_type.clone() {
+                        if !self.value_of(&typ).fits(&value_type) {
+                            self.warn(value, format!("'{}.{}' returns {}, but the value is {}", self.class_name, self.subroutine_name, typ, value_type));
+                        }
+                    }
+                },
+                Statement::Return { value: None, .. } => {},
+            }
+        }
+    }
+    fn check_condition(&mut self, condition: &Expression, statement: &str) {
+        let value = self.check_expression(condition);
+        if !value.is_boolean() {
+            self.warn(condition, format!("the condition of the {} statement is {}, not boolean", statement, value));
+        }
+    }
+    fn check_number(&mut self, expression: &Expression, what: &str) {
+        let value = self.check_expression(expression);
+        if !value.is_number() {
+            self.warn(expression, format!("{} has to be a number, but got {}", what, value));
+        }
+    }
+
+    // The operations are grouped like the compiler does it (see compile_expression)
+    fn check_expression(&mut self, expression: &Expression) -> Value {
+        let mut values = vec![self.check_term(&expression.term)];
+        let mut waiting_ops: Vec<Op> = Vec::new();
+        for (op, term) in &expression.operations {
+            while let Some(waiting_op) = waiting_ops.last().copied().filter(|waiting_op| self.precedence(waiting_op) >= self.precedence(op)) {
+                waiting_ops.pop();
+                self.apply(waiting_op, &mut values, expression);
+            }
+            waiting_ops.push(*op);
+            values.push(self.check_term(term));
+        }
+        while let Some(op) = waiting_ops.pop() {
+            self.apply(op, &mut values, expression);
+        }
+        values.pop().unwrap_or(Value::Unknown)
+    }
+    fn precedence(&self, op: &Op) -> u8 {
+        if self.precedence { op.precedence() } else { 0 }
+    }
+    // Replaces the last two values with the result of the operation
+    fn apply(&mut self, op: Op, values: &mut Vec<Value>, expression: &Expression) {
+        let right = values.pop().unwrap_or(Value::Unknown);
+        let left = values.pop().unwrap_or(Value::Unknown);
+        let result = match op {
+            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Lt | Op::Gt => {
+                if !left.is_number() || !right.is_number() {
+                    self.warn(expression, format!("'{}' needs numbers, but got {} and {}", op, left, right));
+                }
+                if op == Op::Lt || op == Op::Gt { Value::Typed(Type::Boolean) } else { Value::Typed(Type::Int) }
+            },
+            // `&` and `|` are logical on booleans and bitwise on numbers
+            Op::And | Op::Or => match (&left, &right) {
+                (Value::Unknown, Value::Unknown) => Value::Unknown,
+                (left, right) if left.is_boolean() && right.is_boolean() => Value::Typed(Type::Boolean),
+                (left, right) if left.is_number() && right.is_number() => Value::Typed(Type::Int),
+                (left, right) => {
+                    self.warn(expression, format!("'{}' needs two booleans or two numbers, but got {} and {}", op, left, right));
+                    Value::Unknown
+                },
+            },
+            Op::Eq => {
+                if !left.fits(&right) {
+                    self.warn(expression, format!("'=' compares {} with {}", left, right));
+                }
+                Value::Typed(Type::Boolean)
+            },
+        };
+        values.push(result);
+    }
+    fn check_term(&mut self, term: &Term) -> Value {
+        match &term.kind {
+            TermKind::Integer(_) => Value::Typed(Type::Int),
+            TermKind::String(_) => Value::Typed(Type::Class("String".to_string())),
+            TermKind::Keyword(KeywordConstant::True) | TermKind::Keyword(KeywordConstant::False) => Value::Typed(Type::Boolean),
+            TermKind::Keyword(KeywordConstant::Null) => Value::Null,
+            TermKind::Keyword(KeywordConstant::This) => Value::Typed(Type::Class(self.class_name.to_string())),
+            TermKind::Var(name) => self.var_type(name).map(|typ| self.value_of(&typ)).unwrap_or(Value::Unknown),
+            TermKind::Index(_, index) => {
+                self.check_number(index, "an array index");
+                Value::Unknown
+            },
+            TermKind::Call(call) => self.check_call(call),
+            TermKind::Parenthesized(expression) => self.check_expression(expression),
+            TermKind::Unary(op, operand) => {
+                let value = self.check_term(operand);
+                match (op, &value) {
+                    (_, Value::Unknown) => Value::Unknown,
+                    (UnaryOp::Neg, value) if value.is_number() => Value::Typed(Type::Int),
+                    // `~` is logical on booleans and bitwise on numbers
+                    (UnaryOp::Not, Value::Typed(Type::Boolean)) => Value::Typed(Type::Boolean),
+                    (UnaryOp::Not, value) if value.is_number() => Value::Typed(Type::Int),
+                    (op, _) => {
+                        self.warnings.push(Diagnostic::warning(&term.span, format!("Mismatched types: '{}' can not be applied to {}", op, value)));
+                        Value::Unknown
+                    },
+                }
+            },
+        }
+    }
+    // The arguments have to fit the parameters of the called subroutine
+    // (calls, the compiler can not resolve, are reported by it).
+    fn check_call(&mut self, call: &SubroutineCall) -> Value {
+        let arguments: Vec<Value> = call.arguments.iter().map(|argument| self.check_expression(argument)).collect();
+        let class_name = match &call.receiver {
+            Some(receiver) => match self.var_type(receiver) {
+                Some(Type::Class(class_name)) => class_name,
+                Some(_) => return Value::Unknown,
+                None => receiver.name.to_string(),
+            },
+            None => self.class_name.to_string(),
+        };
+        let registry = self.registry;
+        let signature = match registry.get(&class_name, &call.name.name) {
+            Some(signature) => signature,
+            None => return Value::Unknown,
+        };
+        for (number, ((argument, value), parameter)) in call.arguments.iter().zip(&arguments).zip(&signature.parameters).enumerate() {
+            if !self.value_of(parameter).fits(value) {
+                self.warn(argument, format!("parameter {} of '{}.{}' is {}, but the argument is {}", number + 1, class_name, call.name.name, parameter, value));
+            }
+        }
+        match &signature.return_type {
+            ReturnType::Type(typ) => self.value_of(typ),
+            ReturnType::Void => Value::Unknown,
+        }
+    }
+}
+
+// TESTS
+#[cfg(test)]
+fn check(jack_code: &str) -> Vec<String> {
+    let tokens = crate::tokenizer::tokenize_file(jack_code, "Main.jack").unwrap();
+    let class = crate::parser::parse(&tokens, "Main.jack").unwrap();
+    let mut registry = Registry::with_os();
+    registry.add_class(&class);
+    check_types(&class, &registry, false).iter().map(|warning| warning.to_string()).collect()
+}
+
+#[test]
+fn mismatched_values_are_reported() {
+    let warnings = check("class Main {
+    function int main(boolean b, Array a) {
+        var int i;
+        var char c;
+        var String s;
+        let i = b;
+        let c = 65;
+        let s = null;
+        let s = a[3];
+        let b = i < 3;
+        do Math.multiply(i, s);
+        if (i) { let i = 1; }
+        while (b & (i | 2)) { let i = i + b; }
+        return b;
+    }
+}");
+    assert_eq!(warnings, vec![
+        "Main.jack:6:17: Mismatched types: 'i' is int, but the value is boolean",
+        "Main.jack:11:29: Mismatched types: parameter 2 of 'Math.multiply' is int, but the argument is String",
+        "Main.jack:12:13: Mismatched types: the condition of the if statement is int, not boolean",
+        "Main.jack:13:16: Mismatched types: '&' needs two booleans or two numbers, but got boolean and int",
+        "Main.jack:13:39: Mismatched types: '+' needs numbers, but got int and boolean",
+        "Main.jack:14:16: Mismatched types: 'Main.main' returns int, but the value is boolean",
+    ]);
+}
+
+#[test]
+fn objects_are_checked_by_their_class() {
+    let warnings = check("class Main {
+    field Main next;
+    method Main get(String s) {
+        let next = this;
+        let next = s;
+        do next.get(\"name\");
+        if (next = null) { return null; }
+        return s.length();
+    }
+}");
+    assert_eq!(warnings, vec![
+        "Main.jack:5:20: Mismatched types: 'next' is Main, but the value is String",
+        "Main.jack:8:16: Mismatched types: 'Main.get' returns Main, but the value is int",
+    ]);
+}
+
+#[test]
+fn unknown_classes_are_reported_at_their_declaration() {
+    let warnings = check("class Main {
+    field bool isPlaying;
+    method Point f(Foo x) {
+        var Bar y;
+        let isPlaying = true;
+        let y = 3;
+        let x = Main.g(y);
+        return x;
+    }
+    function int g(Baz z) {
+        return z;
+    }
+}");
+    // The variables are unknown values, they fit everywhere
+    assert_eq!(warnings, vec![
+        "Main.jack:2:16: Unknown type: the class 'bool' of 'isPlaying' has not been declared",
+        "Main.jack:3:18: Unknown type: the class 'Point' returned by 'Main.f' has not been declared",
+        "Main.jack:3:24: Unknown type: the class 'Foo' of 'x' has not been declared",
+        "Main.jack:4:17: Unknown type: the class 'Bar' of 'y' has not been declared",
+        "Main.jack:10:24: Unknown type: the class 'Baz' of 'z' has not been declared",
+    ]);
+}
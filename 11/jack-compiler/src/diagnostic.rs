@@ -0,0 +1,116 @@
+use crate::tokenizer::token::Span;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// An error (or warning) in a jack-file, located at the span of the token that caused it.
+// Notes explain the message further, they are shown below the line of the token.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub span: Span,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(span: &Span, message: String) -> Self {
+        Diagnostic { level: Level::Error, span: span.clone(), message, notes: Vec::new() }
+    }
+    pub fn warning(span: &Span, message: String) -> Self {
+        Diagnostic { level: Level::Warning, span: span.clone(), message, notes: Vec::new() }
+    }
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Error
+    }
+
+    // Renders the diagnostic like rustc does: the message, the location,
+    // the line of the source with a caret under the token and the notes.
+    pub fn render(&self, source: &str) -> String {
+        let mut rendered = format!("{}: {}\n --> {}", self.level, self.message, self.span);
+        let line_number = self.span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let source_line = source.split('\n').nth(self.span.line.wrapping_sub(1)).filter(|_| !source.is_empty());
+        if let Some(source_line) = source_line {
+            let source_line = source_line.trim_end_matches('\r');
+            // Tabs in front of the token stay tabs, so the caret lines up with it.
+            let indent: String = source_line
+                .chars()
+                .take(self.span.column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let width = source
+                .get(self.span.bytes.clone())
+                // (a span over several lines is marked up to the end of it's first line)
+                .map(|token| token.split('\n').next().unwrap_or("").trim_end_matches('\r').chars().count())
+                .unwrap_or(1)
+                .max(1);
+            rendered.push_str(&format!("\n{} |\n{} | {}\n{} | {}{}", gutter, line_number, source_line, gutter, indent, "^".repeat(width)));
+        }
+        for note in &self.notes {
+            rendered.push_str(&format!("\n{} = note: {}", gutter, note));
+        }
+        rendered
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
+
+// Renders all diagnostics of a file (separated by empty lines)
+pub fn render_all(diagnostics: &[Diagnostic], source: &str) -> String {
+    diagnostics.iter().map(|diagnostic| diagnostic.render(source)).collect::<Vec<String>>().join("\n\n")
+}
+
+// TESTS
+#[test]
+fn error_points_at_the_token() {
+    let source = "class Main {\n    function void main() {\n        let x = 3;\n";
+    let error = Diagnostic::error(&Span::new("Main.jack", 3, 13, 52..53), "Variable 'x' has not been declared".to_string());
+    assert_eq!(error.render(source), "error: Variable 'x' has not been declared
+ --> Main.jack:3:13
+  |
+3 |         let x = 3;
+  |             ^");
+}
+
+#[test]
+fn error_without_source_has_only_the_location() {
+    let error = Diagnostic::error(&Span::new("Main.jack", 7, 1, 90..96), "Expected ';'".to_string());
+    assert_eq!(error.render(""), "error: Expected ';'\n --> Main.jack:7:1");
+    assert_eq!(error.to_string(), "Main.jack:7:1: Expected ';'");
+}
+
+#[test]
+fn warnings_show_their_notes_below_the_line() {
+    let source = "    return;\n    let x = 1;";
+    let warning = Diagnostic::warning(&Span::new("Main.jack", 2, 5, 16..19), "Unreachable statement".to_string())
+        .with_note("it follows the return in line 1".to_string());
+    assert!(!warning.is_error());
+    assert_eq!(warning.render(source), "warning: Unreachable statement
+ --> Main.jack:2:5
+  |
+2 |     let x = 1;
+  |     ^^^
+  = note: it follows the return in line 1");
+}
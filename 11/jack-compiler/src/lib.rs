@@ -1,3 +1,6 @@
+pub mod ast;
 pub mod compiler;
+pub mod diagnostic;
+pub mod parser;
 pub mod tokenizer;
 pub mod processing;
\ No newline at end of file
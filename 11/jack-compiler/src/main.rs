@@ -1,13 +1,31 @@
-use jack_compiler::processing::{ process_input };
+use jack_compiler::processing::{ process_input, Options };
 use std::env;
+use std::process;
 use std::path::{ Path };
 
 fn main() {
-  // Get path to .jack files from command-line
-  let path = env::args().skip(1).next();
-  match path {
-    Some(path) => process_input(Path::new(&path)),
-    None => panic!("please hand me a directory with jack-files"),
+  // Get path to .jack files from command-line,
+  // "--source-map" also writes a "<file>.vm.map" for every jack-file,
+  // "--precedence" compiles multiplications before additions before comparisons before `&` before `|`,
+  // "--check-types" warns about values of the wrong type, "--strict" makes warnings errors
+  let (flags, args): (Vec<String>, Vec<String>) = env::args().skip(1).partition(|arg| arg.starts_with("--"));
+  let mut options = Options::default();
+  for flag in &flags {
+    match flag.as_str() {
+      "--source-map" => options.source_map = true,
+      "--precedence" => options.precedence = true,
+      "--check-types" => options.type_check = true,
+      "--strict" => options.strict = true,
+      flag => panic!("Unknown flag '{}'", flag),
+    }
+  }
+  let compiled = match args.first() {
+    Some(path) => process_input(Path::new(path), &options),
+    None => panic!("please hand me a directory with jack-files (optionally with '--source-map', '--precedence', '--check-types' or '--strict')"),
+  };
+  // The files with errors have been reported
+  if !compiled {
+    process::exit(1);
   }
 
   // TOKENIZE INPUT (INTERMEDIATE STEP)
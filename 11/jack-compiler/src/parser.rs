@@ -0,0 +1,531 @@
+// Builds the syntax tree of a class from it's tokens.
+// Syntax errors are reported and the broken statement (or subroutine) is skipped,
+// so all errors of a class are found at once.
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use crate::tokenizer::token::{ Span, Token, TokenType, TokenStream };
+
+static SUBROUTINE_KEYWORDS: &[&str] = &["constructor", "function", "method"];
+static STATEMENT_KEYWORDS: &[&str] = &["let", "if", "while", "do", "return"];
+
+// A part of the class, or the error that stopped it
+type Parsed<T> = Result<T, Diagnostic>;
+
+pub struct Parser<'a> {
+    token_tail: TokenStream<'a>,
+    last_token: Option<&'a Token>,
+    // The file the tokens come from (for the end of a file without tokens)
+    file_name: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+// Parses a class, that has to be free of syntax errors
+pub fn parse(tokens: &[Token], file_name: &str) -> Result<Class, Vec<Diagnostic>> {
+    let mut parser = Parser::new(tokens, file_name);
+    match parser.parse_class() {
+        Some(class) if parser.diagnostics.is_empty() => Ok(class),
+        _ => Err(parser.into_diagnostics()),
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(tokens: &'a [Token], file_name: &str) -> Self {
+        Parser {
+            token_tail: tokens.iter().peekable(),
+            last_token: tokens.last(),
+            file_name: file_name.to_string(),
+            diagnostics: Vec::new(),
+        }
+    }
+    // The syntax errors, that have been found so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    // Parses the class of the tokens. Broken statements and subroutines are reported
+    // and left out of the class, without a valid class declaration there is no class.
+    pub fn parse_class(&mut self) -> Option<Class> {
+        match self.parse_class_declaration() {
+            Ok(class) => Some(class),
+            Err(diagnostic) => {
+                self.report(diagnostic);
+                None
+            },
+        }
+    }
+
+    // Token Helpers
+    fn error_at(&self, token: &Token, message: String) -> Diagnostic {
+        Diagnostic::error(&token.span, message)
+    }
+    // The file ends right after the last token
+    fn end_of_file(&self) -> Diagnostic {
+        let span = match self.last_token {
+            Some(token) => {
+                let Span { file, line, column, bytes } = &token.span;
+                Span { file: file.clone(), line: *line, column: column + token.value.chars().count(), bytes: bytes.end..bytes.end }
+            },
+            None => Span::new(&self.file_name, 1, 1, 0..0),
+        };
+        Diagnostic::error(&span, "Unexpected end of file".to_string())
+    }
+    // Keeps a diagnostic, the same error is only reported once
+    // (like the end of the file, that stops every part of the class).
+    fn report(&mut self, diagnostic: Diagnostic) {
+        if self.diagnostics.last() != Some(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+    // Takes the next token, the end of the file is an error
+    fn next_token(&mut self) -> Parsed<&'a Token> {
+        self.token_tail.next().ok_or_else(|| self.end_of_file())
+    }
+    // Looks at the next token without taking it, the end of the file is an error
+    fn peek_token(&mut self) -> Parsed<&'a Token> {
+        self.token_tail.peek().copied().ok_or_else(|| self.end_of_file())
+    }
+    fn next_is(&mut self, value: &str) -> bool {
+        self.token_tail.peek().is_some_and(|token| token.value == value && token.token_type != TokenType::StringConstant)
+    }
+    // Takes the next token, that has to be the symbol (or keyword).
+    // Any other token is left, so the parsing can continue behind it.
+    fn expect(&mut self, value: &str, context: &str) -> Parsed<&'a Token> {
+        let token = self.peek_token()?;
+        if token.value != value || token.token_type == TokenType::StringConstant {
+            return Err(self.error_at(token, format!("Expected '{}' {}, but got '{}'", value, context, token.value)));
+        }
+        self.token_tail.next();
+        Ok(token)
+    }
+    // Takes the next token, that has to be an identifier (the name of a ...)
+    fn identifier(&mut self, name_of: &str) -> Parsed<Ident> {
+        let token = self.peek_token()?;
+        if token.token_type != TokenType::Identifier {
+            return Err(self.error_at(token, format!("Expected the name of {}, but got '{}'", name_of, token.value)));
+        }
+        self.token_tail.next();
+        Ok(Ident { name: token.value.to_string(), span: token.span.clone() })
+    }
+    // Takes the type of a variable: int, char, boolean or a class name
+    fn parse_type(&mut self) -> Parsed<Type> {
+        let token = self.peek_token()?;
+        let typ = match (&token.token_type, token.value.as_str()) {
+            (TokenType::Keyword, "int") => Type::Int,
+            (TokenType::Keyword, "char") => Type::Char,
+            (TokenType::Keyword, "boolean") => Type::Boolean,
+            (TokenType::Identifier, class_name) => Type::Class(class_name.to_string()),
+            (_, value) => return Err(self.error_at(token, format!("Expected a type (int, char, boolean or a class name), but got '{}'", value))),
+        };
+        self.token_tail.next();
+        Ok(typ)
+    }
+    // Names separated by commas up to the semicolon, that is taken as well
+    fn parse_names(&mut self, name_of: &str) -> Parsed<Vec<Ident>> {
+        let mut names = vec![self.identifier(name_of)?];
+        while !self.next_is(";") {
+            self.expect(",", &format!("between the names of {}s", name_of))?;
+            names.push(self.identifier(name_of)?);
+        }
+        self.expect(";", "at the end of the declaration")?;
+        Ok(names)
+    }
+
+    // Skips the rest of a broken statement (or declaration): up to it's ';',
+    // over it's blocks (like the body of an if with it's else), up to the
+    // next statement or up to the '}' of the surrounding block, that is left for it.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.token_tail.peek() {
+            if depth == 0 && token.token_type == TokenType::Keyword && STATEMENT_KEYWORDS.contains(&token.value.as_str()) {
+                return;
+            }
+            if token.token_type == TokenType::Symbol {
+                match token.value.as_str() {
+                    ";" if depth == 0 => {
+                        self.token_tail.next();
+                        return;
+                    },
+                    "{" => depth += 1,
+                    "}" if depth == 0 => return,
+                    "}" => {
+                        depth -= 1;
+                        self.token_tail.next();
+                        if depth == 0 && !self.next_is("else") {
+                            return;
+                        }
+                        continue;
+                    },
+                    _ => {},
+                }
+            }
+            self.token_tail.next();
+        }
+    }
+    // Skips the rest of a broken subroutine, up to the next subroutine or the end of the class
+    fn skip_subroutine(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.token_tail.peek() {
+            match token.value.as_str() {
+                "{" if token.token_type == TokenType::Symbol => depth += 1,
+                "}" if token.token_type == TokenType::Symbol => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                },
+                keyword if depth == 0 && token.token_type == TokenType::Keyword && SUBROUTINE_KEYWORDS.contains(&keyword) => return,
+                _ => {},
+            }
+            self.token_tail.next();
+        }
+    }
+
+    // Program Structure
+    fn parse_class_declaration(&mut self) -> Parsed<Class> {
+        let class_keyword = self.next_token()?;
+        if class_keyword.value != "class" {
+            return Err(self.error_at(class_keyword, format!("Class files need to start with a class declaration, but got '{}'", class_keyword.value)));
+        }
+        let class_name = self.next_token()?;
+        if class_name.token_type != TokenType::Identifier {
+            return Err(self.error_at(class_name, format!("Classes need a valid class name, but got '{}'", class_name.value)));
+        }
+        let name = Ident { name: class_name.value.to_string(), span: class_name.span.clone() };
+        self.expect("{", "after the class name")?;
+
+        let mut vars = Vec::new();
+        while self.next_is("static") || self.next_is("field") {
+            match self.parse_class_var_dec() {
+                Ok(var_dec) => vars.push(var_dec),
+                Err(diagnostic) => {
+                    self.report(diagnostic);
+                    self.synchronize();
+                },
+            }
+        }
+        let mut subroutines = Vec::new();
+        while self.peek_token()?.value != "}" {
+            match self.parse_subroutine() {
+                Ok(subroutine) => subroutines.push(subroutine),
+                Err(diagnostic) => {
+                    self.report(diagnostic);
+                    self.skip_subroutine();
+                },
+            }
+        }
+        self.expect("}", "at the end of the class")?;
+
+        match self.token_tail.next() {
+            None => Ok(Class { name, vars, subroutines }),
+            Some(token) => Err(self.error_at(token, format!("Expected the end of the file after the class, but got '{}'", token.value))),
+        }
+    }
+    fn parse_class_var_dec(&mut self) -> Parsed<ClassVarDec> {
+        let keyword = self.next_token()?;
+        let kind = if keyword.value == "static" { ClassVarKind::Static } else { ClassVarKind::Field };
+        let typ = self.parse_type()?;
+        let names = self.parse_names("a class variable")?;
+        Ok(ClassVarDec { kind, typ, names, span: keyword.span.clone() })
+    }
+    fn parse_subroutine(&mut self) -> Parsed<Subroutine> {
+        let keyword = self.next_token()?;
+        let kind = match keyword.value.as_str() {
+            "constructor" => SubroutineKind::Constructor,
+            "function" => SubroutineKind::Function,
+            "method" => SubroutineKind::Method,
+            _ => return Err(self.error_at(keyword, format!("Expected a subroutine (constructor, function or method), but got '{}'", keyword.value))),
+        };
+        let return_type = if self.next_is("void") {
+            self.token_tail.next();
+            ReturnType::Void
+        } else {
+            ReturnType::Type(self.parse_type()?)
+        };
+        let name = self.identifier("a subroutine")?;
+
+        self.expect("(", "after the subroutine name")?;
+        let mut parameters = Vec::new();
+        while !self.next_is(")") {
+            if !parameters.is_empty() {
+                self.expect(",", "between the parameters")?;
+            }
+            let typ = self.parse_type()?;
+            parameters.push(Parameter { typ, name: self.identifier("a parameter")? });
+        }
+        self.expect(")", "after the parameters")?;
+
+        self.expect("{", "at the start of the subroutine body")?;
+        let mut locals = Vec::new();
+        while self.next_is("var") {
+            match self.parse_var_dec() {
+                Ok(var_dec) => locals.push(var_dec),
+                Err(diagnostic) => {
+                    self.report(diagnostic);
+                    self.synchronize();
+                },
+            }
+        }
+        let statements = self.parse_statements()?;
+        self.expect("}", "at the end of the subroutine body")?;
+        Ok(Subroutine { kind, return_type, name, parameters, locals, statements, span: keyword.span.clone() })
+    }
+    fn parse_var_dec(&mut self) -> Parsed<VarDec> {
+        let keyword = self.expect("var", "at the start of the local variables")?;
+        let typ = self.parse_type()?;
+        let names = self.parse_names("a local variable")?;
+        Ok(VarDec { typ, names, span: keyword.span.clone() })
+    }
+
+    // Statements
+    // Parses the statements up to the closing curly (that is left).
+    // A broken statement is reported and skipped, the statements behind it are still parsed.
+    fn parse_statements(&mut self) -> Parsed<Vec<Statement>> {
+        let mut statements = Vec::new();
+        while self.peek_token()?.value != "}" {
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(diagnostic) => {
+                    self.report(diagnostic);
+                    self.synchronize();
+                },
+            }
+        }
+        Ok(statements)
+    }
+    // `{ statements }`
+    fn parse_block(&mut self, context: &str) -> Parsed<Vec<Statement>> {
+        self.expect("{", context)?;
+        let statements = self.parse_statements()?;
+        self.expect("}", "at the end of the statements")?;
+        Ok(statements)
+    }
+    pub fn parse_statement(&mut self) -> Parsed<Statement> {
+        let keyword = self.peek_token()?;
+        let span = keyword.span.clone();
+        match keyword.value.as_str() {
+            "let" => {
+                self.token_tail.next();
+                let target = self.identifier("a variable")?;
+                let index = if self.next_is("[") {
+                    self.token_tail.next();
+                    let index = self.parse_expression()?;
+                    self.expect("]", "after the array index")?;
+                    Some(Box::new(index))
+                } else {
+                    None
+                };
+                self.expect("=", "in the let statement")?;
+                let value = self.parse_expression()?;
+                self.expect(";", "at the end of the let statement")?;
+                Ok(Statement::Let { target, index, value, span })
+            },
+            "if" => {
+                self.token_tail.next();
+                self.expect("(", "after if")?;
+                let condition = self.parse_expression()?;
+                self.expect(")", "after the if condition")?;
+                let statements = self.parse_block("after the if condition")?;
+                let else_statements = if self.next_is("else") {
+                    self.token_tail.next();
+                    Some(self.parse_block("after else")?)
+                } else {
+                    None
+                };
+                Ok(Statement::If { condition, statements, else_statements, span })
+            },
+            "while" => {
+                self.token_tail.next();
+                self.expect("(", "after while")?;
+                let condition = self.parse_expression()?;
+                self.expect(")", "after the while condition")?;
+                let statements = self.parse_block("after the while condition")?;
+                Ok(Statement::While { condition, statements, span })
+            },
+            "do" => {
+                self.token_tail.next();
+                let name = self.identifier("a subroutine")?;
+                let call = self.parse_call(name)?;
+                self.expect(";", "at the end of the do statement")?;
+                Ok(Statement::Do { call, span })
+            },
+            "return" => {
+                self.token_tail.next();
+                let value = if self.next_is(";") { None } else { Some(self.parse_expression()?) };
+                self.expect(";", "at the end of the return statement")?;
+                Ok(Statement::Return { value, span })
+            },
+            s => Err(self.error_at(keyword, format!("Expected a statement (let, if, while, do or return), but got '{}'", s))),
+        }
+    }
+
+    // Expressions
+    pub fn parse_expression(&mut self) -> Parsed<Expression> {
+        let term = self.parse_term()?;
+        let mut operations = Vec::new();
+        while let Some(op) = self.token_tail.peek().filter(|token| token.token_type == TokenType::Symbol).and_then(|token| Op::from_symbol(&token.value)) {
+            self.token_tail.next();
+            operations.push((op, self.parse_term()?));
+        }
+        let last = operations.last().map_or(&term, |(_, term)| term);
+        let span = term.span.to(&last.span);
+        Ok(Expression { term, operations, span })
+    }
+    fn parse_term(&mut self) -> Parsed<Term> {
+        let token = self.peek_token()?;
+        let kind = match (&token.token_type, token.value.as_str()) {
+            (TokenType::IntegerConstant, value) => match value.parse() {
+                Ok(value) => TermKind::Integer(value),
+                Err(_) => return Err(self.error_at(token, format!("Integer constant {} is too large (the maximum is 32767)", value))),
+            },
+            (TokenType::StringConstant, value) => TermKind::String(value.to_string()),
+            (TokenType::Keyword, "true") => TermKind::Keyword(KeywordConstant::True),
+            (TokenType::Keyword, "false") => TermKind::Keyword(KeywordConstant::False),
+            (TokenType::Keyword, "null") => TermKind::Keyword(KeywordConstant::Null),
+            (TokenType::Keyword, "this") => TermKind::Keyword(KeywordConstant::This),
+            (TokenType::Keyword, value) => return Err(self.error_at(token, format!("Expected a term, but got the keyword '{}'", value))),
+            (TokenType::Symbol, "(") => {
+                self.token_tail.next();
+                let expression = self.parse_expression()?;
+                let closing = self.expect(")", "after the expression")?;
+                return Ok(Term { kind: TermKind::Parenthesized(Box::new(expression)), span: token.span.to(&closing.span) });
+            },
+            (TokenType::Symbol, op @ "-") | (TokenType::Symbol, op @ "~") => {
+                self.token_tail.next();
+                let unary_op = if op == "-" { UnaryOp::Neg } else { UnaryOp::Not };
+                let term = self.parse_term()?;
+                let span = token.span.to(&term.span);
+                return Ok(Term { kind: TermKind::Unary(unary_op, Box::new(term)), span });
+            },
+            (TokenType::Symbol, value) => return Err(self.error_at(token, format!("Expected a term, but got '{}'", value))),
+            (TokenType::Identifier, _) => {
+                let name = self.identifier("a variable")?;
+                return if self.next_is("[") {
+                    self.token_tail.next();
+                    let index = self.parse_expression()?;
+                    let closing = self.expect("]", "after the array index")?;
+                    let span = name.span.to(&closing.span);
+                    Ok(Term { kind: TermKind::Index(name, Box::new(index)), span })
+                } else if self.next_is("(") || self.next_is(".") {
+                    let call = self.parse_call(name)?;
+                    let span = call.span.clone();
+                    Ok(Term { kind: TermKind::Call(call), span })
+                } else {
+                    let span = name.span.clone();
+                    Ok(Term { kind: TermKind::Var(name), span })
+                };
+            },
+        };
+        self.token_tail.next();
+        Ok(Term { kind, span: token.span.clone() })
+    }
+    // The call of a subroutine, that starts with the name (which might be the receiver)
+    fn parse_call(&mut self, first: Ident) -> Parsed<SubroutineCall> {
+        let (receiver, name) = if self.next_is(".") {
+            self.token_tail.next();
+            let name = self.identifier("a subroutine")?;
+            (Some(first), name)
+        } else {
+            (None, first)
+        };
+        self.expect("(", "after the subroutine name")?;
+        let mut arguments = Vec::new();
+        while !self.next_is(")") {
+            if !arguments.is_empty() {
+                self.expect(",", "between the arguments")?;
+            }
+            arguments.push(self.parse_expression()?);
+        }
+        let closing = self.expect(")", "after the arguments")?;
+        let start = receiver.as_ref().unwrap_or(&name).span.clone();
+        Ok(SubroutineCall { receiver, name, arguments, span: start.to(&closing.span) })
+    }
+}
+
+// TESTS
+#[cfg(test)]
+fn parse_code(code: &str) -> Result<Class, Vec<Diagnostic>> {
+    let tokens = crate::tokenizer::tokenize_file(code, "Main.jack").unwrap();
+    parse(&tokens, "Main.jack")
+}
+
+#[test]
+fn class_structure_is_parsed() {
+    let class = parse_code("class Main {
+        field int x, y;
+        static Main instance;
+        method boolean isOrigin(int offset) {
+            var int sum;
+            let sum = x + y;
+            return sum = offset;
+        }
+        function void main() { return; }
+    }").unwrap();
+    assert_eq!(class.name.name, "Main");
+    assert_eq!(class.vars.len(), 2);
+    assert_eq!(class.vars[0].kind, ClassVarKind::Field);
+    assert_eq!(class.vars[0].names.iter().map(|name| name.name.as_str()).collect::<Vec<&str>>(), vec!["x", "y"]);
+    assert_eq!(class.vars[1].typ, Type::Class("Main".to_string()));
+    let method = &class.subroutines[0];
+    assert_eq!(method.kind, SubroutineKind::Method);
+    assert_eq!(method.return_type, ReturnType::Type(Type::Boolean));
+    assert_eq!(method.parameters[0].name.name, "offset");
+    assert_eq!(method.locals[0].names[0].name, "sum");
+    assert_eq!(method.statements.len(), 2);
+    assert_eq!(method.statements[1].span().line, 7);
+    assert_eq!(class.subroutines[1].return_type, ReturnType::Void);
+}
+
+#[test]
+fn expressions_keep_the_operations_in_order() {
+    let tokens = crate::tokenizer::tokenize("-a.get(1) * (b - 2) / c[0]");
+    let expression = Parser::new(&tokens, "").parse_expression().unwrap();
+    let ops: Vec<Op> = expression.operations.iter().map(|(op, _)| *op).collect();
+    assert_eq!(ops, vec![Op::Mul, Op::Div]);
+    // The unary minus belongs to the call only
+    match &expression.term.kind {
+        TermKind::Unary(UnaryOp::Neg, term) => match &term.kind {
+            TermKind::Call(call) => {
+                assert_eq!(call.receiver.as_ref().map(|receiver| receiver.name.as_str()), Some("a"));
+                assert_eq!(call.arguments.len(), 1);
+            },
+            kind => panic!("expected a call, got {:?}", kind),
+        },
+        kind => panic!("expected a negation, got {:?}", kind),
+    }
+    assert!(matches!(expression.operations[0].1.kind, TermKind::Parenthesized(_)));
+    assert!(matches!(expression.operations[1].1.kind, TermKind::Index(_, _)));
+    assert_eq!(expression.span.bytes, 0..26);
+}
+
+#[test]
+fn broken_statements_are_left_out() {
+    let code = "class Main {
+        function void main() {
+            let x = ;
+            do Output.printInt(1);
+            if (x) { let y = 2 } else { return; }
+            return;
+        }
+    }";
+    let tokens = crate::tokenizer::tokenize_file(code, "Main.jack").unwrap();
+    let mut parser = Parser::new(&tokens, "Main.jack");
+    let class = parser.parse_class().unwrap();
+    let errors: Vec<String> = parser.diagnostics().iter().map(|error| error.to_string()).collect();
+    assert_eq!(errors, vec![
+        "Main.jack:3:21: Expected a term, but got ';'",
+        "Main.jack:5:32: Expected ';' at the end of the let statement, but got '}'",
+    ]);
+    // The if keeps it's else, only the let inside of it is missing
+    let statements = &class.subroutines[0].statements;
+    assert_eq!(statements.len(), 3);
+    match &statements[1] {
+        Statement::If { statements, else_statements, .. } => {
+            assert!(statements.is_empty());
+            assert_eq!(else_statements.as_ref().map(Vec::len), Some(1));
+        },
+        statement => panic!("expected an if, got {:?}", statement),
+    }
+}
@@ -1,37 +1,120 @@
-use crate::tokenizer::{ tokenize };
+use crate::tokenizer::{ tokenize_file };
+use crate::tokenizer::token::Token;
+use crate::diagnostic::{ render_all, Diagnostic };
 use crate::compiler::{ Compiler };
+use crate::compiler::registry::Registry;
+use crate::parser::Parser;
+use crate::ast::Class;
 use std::fs;
 use std::io::prelude::*;
 use std::path::{ Path, PathBuf };
 use std::ffi::OsStr;
 
 
-pub fn process_input(path: &Path) {
+// How the jack-files are compiled (the flags of the command line)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+  // Also write a "<file>.vm.map", that maps the vm-lines to the jack-lines (--source-map)
+  pub source_map: bool,
+  // Multiplications before additions before comparisons before `&` before `|` (--precedence)
+  pub precedence: bool,
+  // Warn about values of the wrong type (--check-types)
+  pub type_check: bool,
+  // Warnings are errors, files with warnings are not written (--strict)
+  pub strict: bool,
+}
+
+// Compiles every jack-file below path into a vm-file next to it.
+// The jack-files of a directory are one program: they are all parsed first,
+// so calls to the other classes (and the OS) can be checked.
+// Files with errors are reported (and not written), the other files are still compiled.
+// Returns if all files have been compiled.
+pub fn process_input(path: &Path, options: &Options) -> bool {
     let dir = fs::read_dir(path).expect("No Path has been passed");
+    let mut compiled = true;
+    let mut jack_files = Vec::new();
     for item in dir {
       let item = item.expect("no item in path");
       let item_path = item.path();
       if item_path.is_dir() {
-        process_input(&item_path);
+        compiled &= process_input(&item_path, options);
       } else if item_path.extension() == Some(OsStr::new("jack")) {
-        let result_dir = item_path.parent().unwrap();
-        parse_jack_file(&item_path, result_dir);
+        jack_files.push(item_path);
+      }
+    }
+    jack_files.sort();
+    compiled &= compile_program(&jack_files, options);
+    compiled
+  }
+
+
+  fn compile_program(jack_files: &[PathBuf], options: &Options) -> bool {
+    let mut compiled = true;
+    // Read and tokenize the code in the files
+    // (a file with unterminated comments/strings or invalid tokens is not compiled)
+    let mut files = Vec::new();
+    let mut failed_classes = Vec::new();
+    for jack_file in jack_files {
+      let jack_code = fs::read_to_string(jack_file).expect("could not read file");
+      let file_name = jack_file.file_name().unwrap().to_string_lossy();
+      match tokenize_file(&jack_code, &file_name) {
+        Ok(tokens) => files.push((jack_file, jack_code, tokens)),
+        Err(errors) => {
+          eprintln!("{}\n", render_all(&errors, &jack_code));
+          failed_classes.push(class_name(jack_file, None));
+          compiled = false;
+        },
+      }
+    }
+    println!("The code from the files has been tokenized.");
+    // Parse all classes, before the first one is compiled
+    let parsed: Vec<(Option<Class>, Vec<Diagnostic>)> = files
+      .iter()
+      .map(|(jack_file, _, tokens)| {
+        let mut parser = Parser::new(tokens, &jack_file.file_name().unwrap().to_string_lossy());
+        let class = parser.parse_class();
+        (class, parser.into_diagnostics())
+      })
+      .collect();
+    // The subroutines of a class with syntax errors are not all known,
+    // calls into it are compiled without checks (instead of reporting them too)
+    let mut registry = Registry::with_os();
+    for ((jack_file, _, _), (class, syntax_errors)) in files.iter().zip(&parsed) {
+      match class {
+        Some(class) if syntax_errors.is_empty() => registry.add_class(class),
+        class => failed_classes.push(class_name(jack_file, class.as_ref())),
       }
     }
+    for class_name in &failed_classes {
+      registry.add_unchecked_class(class_name);
+    }
+    for ((jack_file, jack_code, tokens), (class, syntax_errors)) in files.iter().zip(parsed) {
+      compiled &= compile_jack_file(jack_file, jack_code, tokens, class.as_ref(), syntax_errors, &registry, options);
+    }
+    compiled
   }
 
 
-  fn parse_jack_file(jack_file: &Path, result_dir: &Path) {
+  fn compile_jack_file(jack_file: &Path, jack_code: &str, tokens: &[Token], class: Option<&Class>, syntax_errors: Vec<Diagnostic>, registry: &Registry, options: &Options) -> bool {
     let file_stem = jack_file.file_stem().expect("could not read the file stem of the input file");
-    // Read a File
-    let jack_code = fs::read_to_string(jack_file).expect("could not read file");
-    
-    // Tokenize code in file
-    let tokens = tokenize(&jack_code);
-    println!("The code from the file has been tokenized.");
-    // Parse tokenized code
-    let mut compiler = Compiler::new(&tokens, file_stem.to_str().unwrap());
-    let parsed_input = compiler.analyze_tokens();
+    let result_dir = jack_file.parent().unwrap();
+    // Compile the parsed class (errors show the line of the jack-code)
+    let mut compiler = Compiler::new(tokens, file_stem.to_str().unwrap())
+      .with_source(jack_code)
+      .with_precedence(options.precedence)
+      .with_type_check(options.type_check)
+      .with_strict(options.strict)
+      .with_registry(registry);
+    let parsed_input = match compiler.compile_parsed(class, syntax_errors) {
+      Ok(parsed_input) => parsed_input,
+      Err(diagnostics) => {
+        eprintln!("{}\n", render_all(&diagnostics, jack_code));
+        return false;
+      },
+    };
+    if !compiler.warnings().is_empty() {
+      eprintln!("{}\n", render_all(compiler.warnings(), jack_code));
+    }
     println!("The tokens have been analyzed and parsed.");
     // Format xml-data (add line breaks)
     let parsed_and_newline_seperated = seperate_with_newline(parsed_input);
@@ -40,8 +123,21 @@ pub fn process_input(path: &Path) {
     let mut output_file = PathBuf::from(result_dir);
     output_file.push(file_stem); //.to_str().unwrap().to_string() + "niilz");
     output_file.set_extension("vm");
+    if options.source_map {
+      fs::write(output_file.with_extension("vm.map"), compiler.source_map()).expect("could not write source map");
+    }
     let mut output_file =  fs::File::create(output_file).expect("Could not create file");
     output_file.write_all(parsed_and_newline_seperated.as_bytes()).expect("could not write to file");
+    true
+  }
+
+
+  // The name of the class in a file (the file is named after it)
+  fn class_name(jack_file: &Path, class: Option<&Class>) -> String {
+    match class {
+      Some(class) => class.name.name.to_string(),
+      None => jack_file.file_stem().unwrap().to_string_lossy().to_string(),
+    }
   }
 
 
@@ -54,9 +150,9 @@ fn seperate_with_newline(commands: Vec<String>) -> String {
 // Tests
 #[test]
 fn new_line_seperation_works() {
-  let dummy_tokens = tokenize("class Test {}");
+  let dummy_tokens = tokenize_file("class Test {}", "Test.jack").unwrap();
   let mut compiler = Compiler::new(&dummy_tokens, "Test");
-  let dummy_parsed_code = compiler.analyze_tokens();
+  let dummy_parsed_code = compiler.analyze_tokens().unwrap();
   let dummy_xml_sererated = String::from("TODO Concat with n");
   assert_eq!(seperate_with_newline(dummy_parsed_code), dummy_xml_sererated);
 
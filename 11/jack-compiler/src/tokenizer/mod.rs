@@ -1,6 +1,8 @@
 pub mod token;
 
-use token::{ TokenType, Token };
+use token::{ TokenType, Token, Span };
+use crate::diagnostic::{ Diagnostic, render_all };
+use std::rc::Rc;
 
 use lazy_static::lazy_static;
 
@@ -10,7 +12,6 @@ static VALID_SYMBOLS: &'static [char] = &[
   '{', '}', '(', ')', '[', ']', '.', ',', ';', '*',
   '+', '-', '/', '&', '|', '<', '>', '=', '~'
   ];
-static BREAK_CHARACTERS: &'static [char] = &[' ', '\n'];
 static VALID_KEYWORDS: &'static [&str] = &[
     "class", "constructor", "function", "method", "field", "static", "var",
     "int", "char", "boolean", "void", "true", "false", "null", "this", "let",
@@ -18,12 +19,7 @@ static VALID_KEYWORDS: &'static [&str] = &[
     ];
 static UPPER_INTEGER_BOUND: u32 = 32767;
 lazy_static! {
-  static ref INVALID_CHARACTERS: Regex = Regex::new("[\"\n]+").unwrap();
   static ref VALID_IDENTIFIER_PATTERN: Regex = Regex::new("^[a-zA-Z_][a-zA-Z0-9_]*").unwrap();
-  static ref LINE_COMMENT_IDENTIFIER: Regex = Regex::new(r"//.*").unwrap();
-  // End of Block comment ( */ ) is not considered. So block-comments in between
-  // code will not work, because everything after /** is ignored:
-  static ref BLOCK_COMMENT_IDENTIFIER: Regex = Regex::new(r"/\*\*.*").unwrap();
 }
 
 // Public method, which is used in the main
@@ -39,112 +35,185 @@ pub fn get_tokens_in_xml(tokens: &str) -> String {
 
 
 // Turns a given string (expects valid jack code) into a Vec of Tokens
+// (the spans of the tokens have no file name)
 pub fn tokenize(token_stream: &str) -> Vec<Token> {
-  let mut tokens = Vec::new();
-  for line in token_stream.split("\n") {
-    let cleaned_line = clean_line(line);
-    if !cleaned_line.is_empty() {
-      tokens.append(&mut tokenize_line(&cleaned_line));
-    };
+  match tokenize_file(token_stream, "") {
+    Ok(tokens) => tokens,
+    Err(errors) => panic!("\n{}", render_all(&errors, token_stream)),
   }
-  tokens
 }
 
-// cleanes a line from comments
-fn clean_line(line: &str) -> String {
-  let line_without_line_comments = LINE_COMMENT_IDENTIFIER.replace(line, "");
-  let line_without_block_comments = BLOCK_COMMENT_IDENTIFIER.replace(&line_without_line_comments, "");
-  if line_without_block_comments.trim().starts_with("*") {
-    return String::from("");
-  }
-  line_without_block_comments.trim().to_string()
+// Tokenizes the content of a jack-file, every token knows
+// where in the file it has been found.
+// All unterminated comments and strings and invalid tokens of the file are reported.
+pub fn tokenize_file(token_stream: &str, file_name: &str) -> Result<Vec<Token>, Vec<Diagnostic>> {
+  let mut scanner = Scanner {
+    chars: token_stream.char_indices().collect(),
+    idx: 0,
+    line: 1,
+    column: 1,
+    file: Rc::from(file_name),
+    tokens: Vec::new(),
+    errors: Vec::new(),
+  };
+  scanner.scan();
+  if scanner.errors.is_empty() { Ok(scanner.tokens) } else { Err(scanner.errors) }
 }
 
 // Workhorse of the Tokenizer-module.
-// Cotegorizes character(s) in a line into Tokens
-// with their associated type and value
-fn tokenize_line(token_line: &str) -> Vec<Token> {
-  let mut tokens = Vec::new();
-  let mut is_string_sequence = false;
-  let mut token_string = String::new();
-
-  for (idx, character) in token_line.chars().enumerate() {
-    // start/end of string-sequence
-    if character == '"' {
-      // if sequence ends:
-      if is_string_sequence {
-        tokens.push(Token {
-          token_type: TokenType::StringConstant,
-          value: token_string,
-        });
-        token_string = String::new();
-        is_string_sequence = !is_string_sequence;
-        continue;
+// Walks over the characters of the code and cotegorizes them into Tokens
+// with their associated type, value and span. Comments and whitespace
+// only separate the tokens.
+struct Scanner {
+  chars: Vec<(usize, char)>,
+  idx: usize,
+  // line and column (both starting at 1) of the char at idx
+  line: usize,
+  column: usize,
+  file: Rc<str>,
+  tokens: Vec<Token>,
+  errors: Vec<Diagnostic>,
+}
+
+// Where a token starts: line, column and byte
+type Start = (usize, usize, usize);
+
+impl Scanner {
+  fn scan(&mut self) {
+    while let Some(character) = self.peek(0) {
+      let start = self.start();
+      match (character, self.peek(1)) {
+        ('/', Some('/')) => self.skip_line_comment(),
+        ('/', Some('*')) => self.skip_block_comment(start),
+        ('"', _) => self.scan_string(start),
+        (c, _) if c.is_whitespace() => self.advance(),
+        (c, _) if is_symbol(c) => {
+          self.advance();
+          self.push(TokenType::Symbol, c.to_string(), start);
+        },
+        (c, _) if c.is_ascii_alphanumeric() || c == '_' => self.scan_word(start),
+        (c, _) => {
+          self.advance();
+          let span = self.span(start);
+          self.errors.push(Diagnostic::error(&span, format!("Unexpected character '{}'", c)));
+        },
       }
-      // if sequence starts, flag it and keep going
-      is_string_sequence = !is_string_sequence;
-      continue;
     }
-    // for all other (non-string-tokens):
-    if !is_string_sequence {
-      // If it's a symbol add it to the result tokens.
-      // Before push token in token_string, if there is one.
-      if is_symbol(character) {
-        if !token_string.is_empty() {
-          tokens.push(resolve_token(&token_string));
-          token_string = String::new();
-        }
-        tokens.push(Token { token_type: TokenType::Symbol, value: character.to_string() });
-        // If current char isnt marking a break (new-line, blank, end-of-buffer)
-        // add it the token_string
-      } else if !char_at_idx_is_break(idx, &token_line) {
-        token_string.push(character);
-      }
-      // If next char is marking a break (new-line, blank, end-of-buffer)
-      // add token to result Vec
-      if char_at_idx_is_break(idx+1, &token_line) && !token_string.is_empty() {
-        tokens.push(resolve_token(&token_string));
-        token_string = String::new();
+  }
+
+  fn peek(&self, ahead: usize) -> Option<char> {
+    self.chars.get(self.idx + ahead).map(|(_, c)| *c)
+  }
+  fn byte(&self) -> usize {
+    match self.chars.get(self.idx) {
+      Some((byte, _)) => *byte,
+      None => self.chars.last().map(|(byte, c)| byte + c.len_utf8()).unwrap_or(0),
+    }
+  }
+  fn start(&self) -> Start {
+    (self.line, self.column, self.byte())
+  }
+  // The span from start up to the current char
+  fn span(&self, start: Start) -> Span {
+    let (line, column, byte) = start;
+    Span { file: self.file.clone(), line, column, bytes: byte..self.byte() }
+  }
+  fn advance(&mut self) {
+    if let Some(character) = self.peek(0) {
+      self.idx += 1;
+      if character == '\n' {
+        self.line += 1;
+        self.column = 1;
+      } else {
+        self.column += 1;
       }
-    // we're in a string-sequence, so add the character to the token_string
-    } else {
-      token_string.push(character);
     }
   }
-  tokens
-}
-
+  fn push(&mut self, token_type: TokenType, value: String, start: Start) {
+    let span = self.span(start);
+    self.tokens.push(Token { token_type, value, span });
+  }
 
-// Check if next char is a bound between Tokens
-fn char_at_idx_is_break(idx: usize, char_str: &str) -> bool {
-  let next_char = char_str.chars().nth(idx);
-  match next_char {
-    Some(c) => BREAK_CHARACTERS.contains(&c),
-    None => true,
+  // A line comment ends with the line (the new line separates the next token)
+  fn skip_line_comment(&mut self) {
+    while self.peek(0).is_some() && self.peek(0) != Some('\n') {
+      self.advance();
+    }
+  }
+  // Block comments (/* */ and /** */) can span lines and stand between tokens
+  fn skip_block_comment(&mut self, start: Start) {
+    self.advance();
+    self.advance();
+    loop {
+      match (self.peek(0), self.peek(1)) {
+        (Some('*'), Some('/')) => {
+          self.advance();
+          self.advance();
+          return;
+        },
+        (Some(_), _) => self.advance(),
+        (None, _) => {
+          let (line, column, byte) = start;
+          let span = Span { file: self.file.clone(), line, column, bytes: byte..byte + 2 };
+          self.errors.push(Diagnostic::error(&span, "Unterminated comment, '*/' is missing".to_string()));
+          return;
+        },
+      }
+    }
+  }
+  // String constants end on the same line, comments inside of them are part of the string
+  fn scan_string(&mut self, start: Start) {
+    self.advance();
+    let mut value = String::new();
+    loop {
+      match self.peek(0) {
+        Some('"') => {
+          self.advance();
+          self.push(TokenType::StringConstant, value, start);
+          return;
+        },
+        Some('\n') | None => {
+          let span = self.span(start);
+          self.errors.push(Diagnostic::error(&span, "Unterminated string, '\"' is missing at the end of the line".to_string()));
+          return;
+        },
+        Some(character) => {
+          value.push(character);
+          self.advance();
+        },
+      }
+    }
+  }
+  // Keywords, identifiers and integer constants
+  fn scan_word(&mut self, start: Start) {
+    let mut word = String::new();
+    while let Some(character) = self.peek(0).filter(|c| c.is_ascii_alphanumeric() || *c == '_') {
+      word.push(character);
+      self.advance();
+    }
+    let span = self.span(start);
+    match resolve_token(&word) {
+      Some(token_type) => self.tokens.push(Token { token_type, value: word, span }),
+      None if word.chars().all(|c| c.is_ascii_digit()) => {
+        self.errors.push(Diagnostic::error(&span, format!("Integer constant {} is too large (the maximum is {})", word, UPPER_INTEGER_BOUND)));
+      },
+      None => self.errors.push(Diagnostic::error(&span, format!("'{}' is no valid token (identifiers can not start with a digit)", word))),
+    }
   }
 }
 
 // Token-Resolver
-fn resolve_token(maybe_token: &str) -> Token {
+fn resolve_token(maybe_token: &str) -> Option<TokenType> {
   if is_keyword(maybe_token) {
-    return Token {
-      token_type: TokenType::Keyword,
-      value: maybe_token.to_string(),
-    };
+    return Some(TokenType::Keyword);
   }
   if is_valid_identifier(maybe_token) {
-    return Token {
-      token_type: TokenType::Identifier,
-      value: maybe_token.to_string(),
-    };
+    return Some(TokenType::Identifier);
   }
   if is_integer_constant(maybe_token) {
-    return Token {
-      token_type: TokenType::IntegerConstant,
-      value: maybe_token.to_string(),
-    };
+    return Some(TokenType::IntegerConstant);
   }
-  panic!("no valid token has been passed: {}<-Invalid", maybe_token);
+  None
 }
 
 // Matcher functions
@@ -186,18 +255,18 @@ fn turns_string_into_token_xml() {
 #[test]
 fn multilines_with_comments_can_be_tokenized() {
   let mock_tokens = vec![
-    Token { token_type: TokenType::Keyword, value: String::from("let") },
-    Token { token_type: TokenType::Identifier, value: String::from("x") },
-    Token { token_type: TokenType::Symbol, value: String::from("=") },
-    Token { token_type: TokenType::Identifier, value: String::from("y") },
-    Token { token_type: TokenType::Symbol, value: String::from("+") },
-    Token { token_type: TokenType::IntegerConstant, value: String::from("2") },
-    Token { token_type: TokenType::Symbol, value: String::from(";") },
-    Token { token_type: TokenType::Keyword, value: String::from("let") },
-    Token { token_type: TokenType::Identifier, value: String::from("s") },
-    Token { token_type: TokenType::Symbol, value: String::from("=") },
-    Token { token_type: TokenType::StringConstant, value: String::from("Hello World") },
-    Token { token_type: TokenType::Symbol, value: String::from(";") },
+    Token { token_type: TokenType::Keyword, value: String::from("let"), span: Span::new("", 4, 3, 110..113) },
+    Token { token_type: TokenType::Identifier, value: String::from("x"), span: Span::new("", 4, 7, 114..115) },
+    Token { token_type: TokenType::Symbol, value: String::from("="), span: Span::new("", 4, 9, 116..117) },
+    Token { token_type: TokenType::Identifier, value: String::from("y"), span: Span::new("", 4, 11, 118..119) },
+    Token { token_type: TokenType::Symbol, value: String::from("+"), span: Span::new("", 4, 13, 120..121) },
+    Token { token_type: TokenType::IntegerConstant, value: String::from("2"), span: Span::new("", 4, 15, 122..123) },
+    Token { token_type: TokenType::Symbol, value: String::from(";"), span: Span::new("", 4, 16, 123..124) },
+    Token { token_type: TokenType::Keyword, value: String::from("let"), span: Span::new("", 5, 3, 127..130) },
+    Token { token_type: TokenType::Identifier, value: String::from("s"), span: Span::new("", 5, 7, 131..132) },
+    Token { token_type: TokenType::Symbol, value: String::from("="), span: Span::new("", 5, 9, 133..134) },
+    Token { token_type: TokenType::StringConstant, value: String::from("Hello World"), span: Span::new("", 5, 11, 135..148) },
+    Token { token_type: TokenType::Symbol, value: String::from(";"), span: Span::new("", 5, 24, 148..149) },
     ];
   let statements = r#"// Comments and should be ignored, so shoul empty lines (line 2)
 
@@ -208,59 +277,105 @@ fn multilines_with_comments_can_be_tokenized() {
   assert_eq!(tokenize(statements), mock_tokens);
 }
 
+#[test]
+fn tokens_know_their_file_and_position() {
+  let tokens = tokenize_file("class Main {\n\tfield String s; // ünïcode\n  let s = \"a b\";\n}", "Main.jack").unwrap();
+  assert_eq!(tokens[3].span, Span::new("Main.jack", 2, 2, 14..19));
+  assert_eq!(tokens[10].value, "a b");
+  assert_eq!(tokens[10].span, Span::new("Main.jack", 3, 11, 53..58));
+  assert_eq!(tokens.last().unwrap().span.to_string(), "Main.jack:4:1");
+}
+
+#[test]
+fn block_comments_end_where_they_end() {
+  let code = "let /* between */ x = 1; /** spanning\n * lines\n */ let y /**/= 2;";
+  let values: Vec<String> = tokenize(code).into_iter().map(|token| token.value).collect();
+  assert_eq!(values, vec!["let", "x", "=", "1", ";", "let", "y", "=", "2", ";"]);
+  assert_eq!(tokenize(code)[5].span.line, 3);
+}
+
+#[test]
+fn comments_inside_of_strings_are_part_of_the_string() {
+  let tokens = tokenize("do Output.printString(\"http://x /* y */\"); // comment");
+  assert_eq!(tokens[5].token_type, TokenType::StringConstant);
+  assert_eq!(tokens[5].value, "http://x /* y */");
+  assert_eq!(tokens.len(), 8);
+}
+
+#[test]
+fn unterminated_comments_and_strings_are_errors() {
+  let errors = tokenize_file("let s = \"open;\nlet x = 1;\n/* never closed\nlet y = 2;", "Main.jack").unwrap_err();
+  let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+  assert_eq!(errors, vec![
+    "Main.jack:1:9: Unterminated string, '\"' is missing at the end of the line",
+    "Main.jack:3:1: Unterminated comment, '*/' is missing",
+  ]);
+}
+
+#[test]
+fn invalid_tokens_are_errors() {
+  let errors = tokenize_file("let x = 40000 + 1abc $;", "Main.jack").unwrap_err();
+  let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+  assert_eq!(errors, vec![
+    "Main.jack:1:9: Integer constant 40000 is too large (the maximum is 32767)",
+    "Main.jack:1:17: '1abc' is no valid token (identifiers can not start with a digit)",
+    "Main.jack:1:22: Unexpected character '$'",
+  ]);
+}
+
 // Tokens-in-line-parser-Tests
 #[test]
 fn division_is_token_not_comment() {
     let code = "let j = j / (-2);";
     let mock_tokens = vec! [
-      Token { token_type: TokenType::Keyword, value: String::from("let") },
-      Token { token_type: TokenType::Identifier, value: String::from("j") },
-      Token { token_type: TokenType::Symbol, value: String::from("=") },
-      Token { token_type: TokenType::Identifier, value: String::from("j") },
-      Token { token_type: TokenType::Symbol, value: String::from("/") },
-      Token { token_type: TokenType::Symbol, value: String::from("(") },
-      Token { token_type: TokenType::Symbol, value: String::from("-") },
-      Token { token_type: TokenType::IntegerConstant, value: String::from("2") },
-      Token { token_type: TokenType::Symbol, value: String::from(")") },
-      Token { token_type: TokenType::Symbol, value: String::from(";") },
+      Token { token_type: TokenType::Keyword, value: String::from("let"), span: Span::new("", 1, 1, 0..3) },
+      Token { token_type: TokenType::Identifier, value: String::from("j"), span: Span::new("", 1, 5, 4..5) },
+      Token { token_type: TokenType::Symbol, value: String::from("="), span: Span::new("", 1, 7, 6..7) },
+      Token { token_type: TokenType::Identifier, value: String::from("j"), span: Span::new("", 1, 9, 8..9) },
+      Token { token_type: TokenType::Symbol, value: String::from("/"), span: Span::new("", 1, 11, 10..11) },
+      Token { token_type: TokenType::Symbol, value: String::from("("), span: Span::new("", 1, 13, 12..13) },
+      Token { token_type: TokenType::Symbol, value: String::from("-"), span: Span::new("", 1, 14, 13..14) },
+      Token { token_type: TokenType::IntegerConstant, value: String::from("2"), span: Span::new("", 1, 15, 14..15) },
+      Token { token_type: TokenType::Symbol, value: String::from(")"), span: Span::new("", 1, 16, 15..16) },
+      Token { token_type: TokenType::Symbol, value: String::from(";"), span: Span::new("", 1, 17, 16..17) },
     ];
-    assert_eq!(tokenize_line(code), mock_tokens);
+    assert_eq!(tokenize(code), mock_tokens);
 }
 #[test]
 fn simple_tokens_are_categorized() {
   let mock_tokens = vec![
-    Token { token_type: TokenType::Identifier, value: String::from("x") },
-    Token { token_type: TokenType::Symbol, value: String::from("+") },
-    Token { token_type: TokenType::IntegerConstant, value: String::from("2") },
+    Token { token_type: TokenType::Identifier, value: String::from("x"), span: Span::new("", 1, 1, 0..1) },
+    Token { token_type: TokenType::Symbol, value: String::from("+"), span: Span::new("", 1, 3, 2..3) },
+    Token { token_type: TokenType::IntegerConstant, value: String::from("2"), span: Span::new("", 1, 5, 4..5) },
   ];
-  assert_eq!(tokenize_line("x + 2"), mock_tokens);
+  assert_eq!(tokenize("x + 2"), mock_tokens);
 }
 
 #[test]
 fn all_statement_tokens_are_categorized() {
     let mock_tokens = vec![
-      Token { token_type: TokenType::Keyword, value: String::from("let") },
-      Token { token_type: TokenType::Identifier, value: String::from("x") },
-      Token { token_type: TokenType::Symbol, value: String::from("=") },
-      Token { token_type: TokenType::Identifier, value: String::from("y") },
-      Token { token_type: TokenType::Symbol, value: String::from("+") },
-      Token { token_type: TokenType::IntegerConstant, value: String::from("2") },
+      Token { token_type: TokenType::Keyword, value: String::from("let"), span: Span::new("", 1, 1, 0..3) },
+      Token { token_type: TokenType::Identifier, value: String::from("x"), span: Span::new("", 1, 5, 4..5) },
+      Token { token_type: TokenType::Symbol, value: String::from("="), span: Span::new("", 1, 7, 6..7) },
+      Token { token_type: TokenType::Identifier, value: String::from("y"), span: Span::new("", 1, 9, 8..9) },
+      Token { token_type: TokenType::Symbol, value: String::from("+"), span: Span::new("", 1, 11, 10..11) },
+      Token { token_type: TokenType::IntegerConstant, value: String::from("2"), span: Span::new("", 1, 13, 12..13) },
     ];
-    assert_eq!(tokenize_line("let x = y + 2"), mock_tokens);
+    assert_eq!(tokenize("let x = y + 2"), mock_tokens);
 }
 
 // Break-character-Tests
 #[test]
 fn blank_is_break_character() {
-    assert_eq!(char_at_idx_is_break(3, "let x"), true);
+    assert_eq!(tokenize("let x").len(), 2);
 }
 #[test]
 fn char_is_not_break_character() {
-    assert_eq!(char_at_idx_is_break(1, "let x"), false);
+    assert_eq!(tokenize("letx")[0].value, "letx");
 }
 #[test]
 fn end_of_sequence_is_break() {
-    assert_eq!(char_at_idx_is_break(5, "let x"), true);
+    assert_eq!(tokenize("let x")[1].value, "x");
 }
 
 // VALIDATORS
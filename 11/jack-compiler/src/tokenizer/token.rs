@@ -1,5 +1,7 @@
 use std::fmt;
 use std::iter::Peekable;
+use std::ops::Range;
+use std::rc::Rc;
 
 // Token Struct
 #[derive(PartialEq)]
@@ -7,6 +9,35 @@ use std::iter::Peekable;
 pub struct Token {
   pub token_type: TokenType,
   pub value: String,
+  pub span: Span,
+}
+
+// Where a token has been found: the file, the line and column (both starting at 1)
+// and the bytes of the file it covers.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Span {
+  pub file: Rc<str>,
+  pub line: usize,
+  pub column: usize,
+  pub bytes: Range<usize>,
+}
+impl Span {
+  pub fn new(file: &str, line: usize, column: usize, bytes: Range<usize>) -> Self {
+    Span { file: Rc::from(file), line, column, bytes }
+  }
+  // The span from the start of this span to the end of the other one
+  pub fn to(&self, end: &Span) -> Span {
+    Span { file: self.file.clone(), line: self.line, column: self.column, bytes: self.bytes.start..end.bytes.end }
+  }
+}
+impl fmt::Display for Span {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.file.is_empty() {
+      write!(f, "{}:{}", self.line, self.column)
+    } else {
+      write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+  }
 }
 impl Token {
   pub fn to_xml(&self) -> String {
@@ -66,7 +97,7 @@ pub type TokenStream<'a> = Peekable<std::slice::Iter<'a, Token>>;
 // TESTS
 #[test]
 fn token_stream_can_be_taken_by_function() {
-    let token_vec = vec![Token { token_type: TokenType::Symbol, value: String::from("+") }];
+    let token_vec = vec![Token { token_type: TokenType::Symbol, value: String::from("+"), span: Span::new("", 1, 1, 0..1) }];
     fn takes_token_stream<'a>(token_stream: &mut TokenStream<'a>) -> String {
         token_stream.peek().unwrap().value.to_string()
     }
@@ -76,7 +107,9 @@ fn token_stream_can_be_taken_by_function() {
 
 #[test]
 fn token_fields_are_accessable() {
-    let mock_token = Token { token_type: TokenType::Identifier, value: String::from("x") };
+    let mock_token = Token { token_type: TokenType::Identifier, value: String::from("x"), span: Span::new("Main.jack", 3, 9, 40..41) };
     assert_eq!(mock_token.token_type, TokenType::Identifier);
     assert_eq!(mock_token.value, String::from("x"));
+    assert_eq!(mock_token.span.line, 3);
+    assert_eq!(mock_token.span.to_string(), "Main.jack:3:9");
 }
\ No newline at end of file
@@ -0,0 +1,339 @@
+// Compiles Jack classes and runs them in the VM emulator,
+// so the tests check what a program does instead of the VM text.
+use jack_compiler::compiler::Compiler;
+use jack_compiler::processing::{ process_input, Options };
+use jack_compiler::tokenizer::tokenize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use hack_assembler::assembler::assemble_with_symbols;
+use hack_assembler::listing::write_source_map;
+use hack_emulator::Machine;
+use vm_translator::emulator::{ Stop, Vm };
+use vm_translator::source_map::{ trace, Origin, SourceMap };
+use vm_translator::translator::parser::Com;
+use vm_translator::{ translate_dir, translate_dir_with_map, Bootstrap, Routines };
+
+fn compile(class_name: &str, jack_code: &str) -> String {
+    compile_with_precedence(class_name, jack_code, false)
+}
+
+fn compile_with_precedence(class_name: &str, jack_code: &str, precedence: bool) -> String {
+    let tokens = tokenize(jack_code);
+    let mut compiler = Compiler::new(&tokens, class_name).with_precedence(precedence);
+    compiler.analyze_tokens().unwrap().join("\n")
+}
+
+// Runs `let result = <expression>` and returns the result
+fn evaluate(expression: &str, precedence: bool) -> i16 {
+    let main = compile_with_precedence("Main", &format!("class Main {{
+        static int result;
+        function void main() {{
+            var int a, b, c;
+            let a = 10;
+            let b = 3;
+            let c = 2;
+            let result = {};
+            return;
+        }}
+    }}", expression), precedence);
+    let mut vm = Vm::new(&[("Main", &main)]).unwrap();
+    assert_eq!(vm.run(10_000), Ok(Stop::Halted), "{}", expression);
+    vm.ram(16)
+}
+
+const SYS: &str = "class Sys { function void init() { do Main.main(); return; } }";
+
+#[test]
+fn loop_sums_numbers() {
+    let main = compile("Main", "class Main {
+        static int result;
+        function void main() {
+            var int i, sum;
+            let i = 0;
+            let sum = 0;
+            while (i < 10) {
+                let sum = sum + i;
+                let i = i + 1;
+            }
+            let result = sum;
+            return;
+        }
+    }");
+    let sys = compile("Sys", SYS);
+    let mut vm = Vm::new(&[("Main", &main), ("Sys", &sys)]).unwrap();
+    assert_eq!(vm.run(10_000), Ok(Stop::Halted));
+    assert_eq!(vm.ram(16), 45);
+}
+
+#[test]
+fn if_takes_the_right_branch() {
+    let main = compile("Main", "class Main {
+        static int result;
+        function void main() {
+            var int x;
+            let x = Main.double(3);
+            if (x = 6) {
+                let result = 1;
+            } else {
+                let result = 2;
+            }
+            return;
+        }
+        function int double(int x) {
+            return x + x;
+        }
+    }");
+    let sys = compile("Sys", SYS);
+    let mut vm = Vm::new(&[("Main", &main), ("Sys", &sys)]).unwrap();
+    assert_eq!(vm.run(10_000), Ok(Stop::Halted));
+    assert_eq!(vm.ram(16), 1);
+}
+
+#[test]
+fn operations_are_applied_from_the_left() {
+    assert_eq!(evaluate("a - b - c", false), 5);
+    assert_eq!(evaluate("10 / 2 * 5", false), 25);
+    assert_eq!(evaluate("a - b + c", false), 9);
+    assert_eq!(evaluate("a / c / c", false), 2);
+    assert_eq!(evaluate("2 + 3 * 4", false), 20);
+    assert_eq!(evaluate("a - (b - c)", false), 9);
+    assert_eq!(evaluate("-a + b", false), -7);
+}
+
+#[test]
+fn precedence_applies_multiplications_first() {
+    assert_eq!(evaluate("2 + 3 * 4", true), 14);
+    assert_eq!(evaluate("a - b * c - 1", true), 3);
+    assert_eq!(evaluate("a - b - c", true), 5);
+    assert_eq!(evaluate("a / c * b", true), 15);
+    assert_eq!(evaluate("a - 8 / 2 / c", true), 8);
+    // Comparisons come after the arithmetic, `&` and then `|` after the comparisons
+    assert_eq!(evaluate("1 + b < c * c", true), 0);
+    assert_eq!(evaluate("a = b * 3 + 1", true), -1);
+    assert_eq!(evaluate("b | 4 & 6", true), 7);
+    assert_eq!(evaluate("b < a & c > 1", true), -1);
+    assert_eq!(evaluate("a = 10 | b = 10", true), -1);
+    assert_eq!(evaluate("b & c + 2", true), 0);
+    assert_eq!(evaluate("a > b | c", true), -1);
+}
+
+#[test]
+fn os_is_native_without_sys() {
+    let main = compile("Main", "class Main {
+        static int result;
+        function void main() {
+            var String s;
+            var Array a;
+            let s = \"6*7=\";
+            do Output.printString(s);
+            let a = Array.new(2);
+            let a[1] = Math.multiply(6, 7);
+            do Output.printInt(a[1]);
+            let result = a[1];
+            return;
+        }
+    }");
+    let mut vm = Vm::new(&[("Main", &main)]).unwrap();
+    assert_eq!(vm.run(10_000), Ok(Stop::Halted));
+    assert_eq!(vm.ram(16), 42);
+    // "6*7=42" is printed with two characters per word, '6' starts with 28 (00011100).
+    assert_eq!(vm.screen()[0] & 0xFF, 28);
+    assert_ne!(vm.screen()[2], 0);
+}
+
+#[test]
+fn rom_addresses_trace_back_to_jack() {
+    let jack_code = "class Main {\n    static int result;\n    function void main() {\n        let result = 7;\n        let result = result * 6;\n        return;\n    }\n}";
+    let tokens = tokenize(jack_code);
+    let mut compiler = Compiler::new(&tokens, "Main");
+    let main = compiler.analyze_tokens().unwrap().join("\n");
+    let dir = std::env::temp_dir().join("jack_compiler_source_map");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Main.vm"), main).unwrap();
+
+    // Jack -> VM -> assembly -> ROM, every stage with it's source map.
+    let (asm, asm_map) = translate_dir_with_map(&dir, Bootstrap::Never, Routines::Inline).unwrap();
+    let assembly = assemble_with_symbols(&asm).unwrap();
+    let mut maps = HashMap::new();
+    maps.insert("Main.hack".to_string(), SourceMap::parse(&write_source_map(&assembly, "Main.asm"), "Main.hack.map").unwrap());
+    maps.insert("Main.asm".to_string(), asm_map);
+    maps.insert("Main.vm".to_string(), SourceMap::parse(&compiler.source_map(), "Main.vm.map").unwrap());
+
+    let jack_line = |address: usize| trace(&maps, "Main.hack", address).last().cloned();
+    // The function starts at the first address, the multiplication is called in the second let.
+    let multiply_line = asm.split('\n').position(|line| line == "@Math.multiply").unwrap() + 1;
+    let multiply = assembly.source_lines.iter().position(|line| *line == multiply_line).unwrap();
+    assert_eq!(jack_line(0), Some(Origin::new("Main.jack", 3)));
+    assert_eq!(jack_line(multiply), Some(Origin::new("Main.jack", 5)));
+    assert_eq!(jack_line(assembly.program.len() - 1), Some(Origin::new("Main.jack", 6)));
+}
+
+// Compiles the jack-files of the directories together into a new directory
+fn compile_dirs(name: &str, dirs: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for source in dirs {
+        for entry in fs::read_dir(source).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "jack") {
+                fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+    }
+    assert!(process_input(&dir, &Options::default()));
+    dir
+}
+
+// A computer, that runs a program
+trait Computer {
+    // Runs until `function` is called next (or the program has halted),
+    // false if that does not happen within the limit of the computer.
+    fn run_until_call(&mut self, function: &str) -> bool;
+    fn ram(&self, address: usize) -> i16;
+}
+
+// The program on the HACK computer, a call is reached with the start of the function
+struct Cpu {
+    machine: Machine,
+    labels: HashMap<String, u16>,
+}
+
+impl Computer for Cpu {
+    fn run_until_call(&mut self, function: &str) -> bool {
+        let address = self.labels[function];
+        let limit = self.machine.cycles() + 100_000_000;
+        self.machine.step();
+        while self.machine.pc != address {
+            if self.machine.cycles() > limit {
+                return false;
+            }
+            self.machine.step();
+        }
+        true
+    }
+    fn ram(&self, address: usize) -> i16 {
+        self.machine.ram(address)
+    }
+}
+
+impl Computer for Vm {
+    fn run_until_call(&mut self, function: &str) -> bool {
+        let limit = self.steps() + 10_000_000;
+        loop {
+            self.step().unwrap();
+            match self.current_command() {
+                Some(Com::Call(name, _)) if name == function => return true,
+                None => return true,
+                _ if self.steps() > limit => return false,
+                _ => {},
+            }
+        }
+    }
+    fn ram(&self, address: usize) -> i16 {
+        Vm::ram(self, address)
+    }
+}
+
+// The heap (from 2048) and the screen (up to 24575)
+fn heap_and_screen(computer: &dyn Computer) -> Vec<i16> {
+    (2048..24576).map(|address| computer.ram(address)).collect()
+}
+
+#[test]
+fn shared_routines_fit_magic8_with_the_os_into_the_rom() {
+    let dir = compile_dirs("jack_compiler_magic8", &["../../09/magic8/source", "../../12/src"]);
+    let asm = translate_dir(&dir, Bootstrap::Auto, Routines::Shared).unwrap();
+    let assembly = assemble_with_symbols(&asm).unwrap();
+    assert!(assembly.program.len() < 32768, "{} instructions", assembly.program.len());
+
+    // The inline translation does not fit into the ROM, the VM emulator runs the commands
+    // it is made of (with the compiled OS, nothing is native). The OS of 12 draws every
+    // pixel with a few thousand commands, so the ball of magic8 is compared after
+    // it's first pixels (the heap holds the font of the OS and the first objects by then).
+    let inline = translate_dir(&dir, Bootstrap::Auto, Routines::Inline).unwrap();
+    let instructions = inline.split('\n').filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('(')).count();
+    assert!(instructions > 32768);
+    let mut cpu = Cpu { machine: Machine::with_program(&assembly.program), labels: assembly.labels.into_iter().collect() };
+    let mut vm = Vm::from_dir(&dir).unwrap();
+    for computer in [&mut cpu as &mut dyn Computer, &mut vm] {
+        for _ in 0..100 {
+            assert!(computer.run_until_call("Screen.drawPixel"));
+        }
+    }
+    assert!((16384..24576).any(|address| cpu.ram(address) != 0));
+    assert!(heap_and_screen(&cpu) == heap_and_screen(&vm), "the heap or the screen differ");
+}
+
+// Whether the characters from `column` to `column + length` of a line of text
+// (11 rows of pixels, 8 pixels per character) show text: an empty area is
+// white or black, the words of characters are neither.
+fn has_text(computer: &dyn Computer, line: usize, column: usize, length: usize) -> bool {
+    (line * 11..line * 11 + 11)
+        .flat_map(|row| (column / 2..(column + length).div_ceil(2)).map(move |word| 16384 + row * 32 + word))
+        .any(|address| computer.ram(address) != 0 && computer.ram(address) != -1)
+}
+
+#[test]
+fn magic8_runs_on_the_native_os() {
+    let dir = compile_dirs("jack_compiler_magic8_native", &["../../09/magic8/source"]);
+    let mut vm = Vm::from_dir(&dir).unwrap();
+    // The instructions, then 'ENTER' shakes the ball and the answer is shown in it's door
+    assert!(vm.run_until_call("Keyboard.keyPressed"));
+    assert!(has_text(&vm, 17, 45, 15) && !has_text(&vm, 16, 26, 7));
+    vm.press_key(128);
+    assert!(vm.run_until_call("Keyboard.keyPressed"));
+    assert!(has_text(&vm, 16, 26, 7));
+    // 'Q' quits with a thank you
+    vm.press_key(81);
+    assert!(vm.run_until_call("Keyboard.keyPressed"));
+    assert!(vm.is_halted());
+    assert!(has_text(&vm, 11, 20, 21) && !has_text(&vm, 17, 45, 15));
+}
+
+#[test]
+fn compiled_os_classes_compute_like_the_native_ones() {
+    let calls = ["Math.multiply(-181, 181)", "Math.multiply(7, -6)", "Math.multiply(300, 300)", "Math.divide(-100, 7)",
+        "Math.divide(32767, 3)", "Math.divide(5, 9)", "Math.sqrt(144)", "Math.sqrt(32767)", "Math.sqrt(0)", "Math.sqrt(1)",
+        "Math.sqrt(16383)", "Math.abs(-32767)", "Math.min(-3, 2)", "Math.max(-3, 2)"];
+    let lets: String = calls.iter().enumerate().map(|(idx, call)| format!("do Memory.poke({}, {});\n", 8000 + idx, call)).collect();
+    let main = compile("Main", &format!("class Main {{ function void main() {{ do Math.init(); {} return; }} }}", lets));
+    let math = compile("Math", &fs::read_to_string("../../12/src/Math.jack").unwrap());
+
+    let mut native = Vm::new(&[("Main", &main)]).unwrap();
+    let mut compiled = Vm::new(&[("Main", &main), ("Math", &math)]).unwrap();
+    for vm in [&mut native, &mut compiled] {
+        assert_eq!(vm.run(10_000_000), Ok(Stop::Halted));
+    }
+    for (idx, call) in calls.iter().enumerate() {
+        assert_eq!(compiled.ram(8000 + idx), native.ram(8000 + idx), "{}", call);
+    }
+    assert_eq!(native.ram(8006), 12);
+}
+
+#[test]
+fn callers_of_broken_classes_are_still_compiled() {
+    let dir = std::env::temp_dir().join("jack_compiler_broken_class");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("Main.jack"), "class Main {
+        function void main() {
+            var Point p;
+            let p = Point.new(1, 2);
+            do Text.print(p.getX());
+            return;
+        }
+    }").unwrap();
+    // A syntax error (getX is skipped) and a token error (an unterminated string)
+    fs::write(dir.join("Point.jack"), "class Point {
+        field int x;
+        constructor Point new(int ax, int ay) { let x = ax; return this; }
+        method int getX( { return x; }
+    }").unwrap();
+    fs::write(dir.join("Text.jack"), "class Text { function void print(int x) { do Output.printString(\"x); return; } }").unwrap();
+    assert!(!process_input(&dir, &Options::default()));
+    let main = fs::read_to_string(dir.join("Main.vm")).unwrap();
+    assert!(main.contains("call Point.new 2\npop local 0\npush local 0\ncall Point.getX 1\ncall Text.print 1"));
+    assert!(!dir.join("Point.vm").exists() && !dir.join("Text.vm").exists());
+}
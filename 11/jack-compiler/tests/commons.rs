@@ -1,4 +1,4 @@
-use jack_compiler::processing::{ process_input };
+use jack_compiler::processing::{ process_input, Options };
 use std::path::{ Path };
 use std::fs;
 
@@ -6,7 +6,7 @@ pub fn compile_file_and_get_output(folder: &str, file: &str) -> String {
     let path_string = format!("../../11/{}", folder);
     let path = Path::new(&path_string);
     // Compiles jack-file(s) in dir and writes it into file.vm
-    process_input(path);
+    process_input(path, &Options::default());
     let result_path_string = format!("../../11/{}/{}.vm", folder, file);
     let result_file_path = Path::new(&result_path_string);
     // Reads back the compiled output
@@ -56,7 +56,6 @@ push pointer 0
 call Square.draw 1
 pop temp 0
 push pointer 0
-// non_void_no_dummy_0
 return
 
 
@@ -633,7 +632,6 @@ pop this 2
 push constant 0
 pop this 5
 push pointer 0
-// non_void_no_dummy_0
 return
 
 
@@ -665,7 +663,6 @@ return
 function PongGame.getInstance 0
 
 push static 0
-// non_void_no_dummy_0
 return
 
 
@@ -1604,7 +1601,6 @@ function Main.double 0
 push argument 0
 push constant 2
 call Math.multiply 2
-// non_void_no_dummy_0
 return
 
 